@@ -0,0 +1,4 @@
+-- 全てのテーブルを消す。データも消えるので、アプリケーションのmigrate revertでは戻さない
+
+DROP TABLE `t_user_slot`;
+DROP TABLE `t_user`;
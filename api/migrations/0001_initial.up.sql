@@ -0,0 +1,34 @@
+-- 最初のスキーマと初期データ。以前のdb/ddl.sqlで作ったDBにも適用できるよう、それと同じものにして、
+-- 既にあるテーブルとデータはそのままにする。以降の変更は0002から
+
+CREATE TABLE IF NOT EXISTS `t_user` (
+  `id` int UNSIGNED NOT NULL AUTO_INCREMENT,
+  `email` varchar(256) CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci NOT NULL,
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `updated_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `email` (`email`)
+) ENGINE=InnoDB AUTO_INCREMENT=11 DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+
+INSERT IGNORE INTO `t_user` (`id`, `email`, `created_at`) VALUES
+(1, 'test1@example.com', '2023-07-18 21:47:33'),
+(2, 'test2@example.com', '2023-07-18 21:47:33'),
+(3, 'test3@example.com', '2023-07-18 21:47:33'),
+(4, 'test4@example.com', '2023-07-18 21:47:33'),
+(5, 'test5@example.com', '2023-07-18 21:47:33'),
+(6, 'test6@example.com', '2023-07-18 21:47:33'),
+(7, 'test7@example.com', '2023-07-18 21:47:33'),
+(8, 'test8@example.com', '2023-07-18 21:47:33'),
+(9, 'test9@example.com', '2023-07-18 21:47:33'),
+(10, 'test10@example.com', '2023-07-18 21:47:33');
+
+CREATE TABLE IF NOT EXISTS `t_user_slot` (
+  `id` int UNSIGNED NOT NULL AUTO_INCREMENT,
+  `user_id` int UNSIGNED NOT NULL,
+  `start` datetime NOT NULL,
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `updated_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `user_id` (`user_id`,`start`),
+  CONSTRAINT `t_user_slot_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `t_user` (`id`) ON DELETE RESTRICT ON UPDATE RESTRICT
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
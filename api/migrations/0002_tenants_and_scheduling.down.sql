@@ -0,0 +1,52 @@
+-- 0002で加えたテーブルと列を消す。テナントが複数あり、同じメールアドレスのユーザがいる場合は戻せない
+
+
+DROP TABLE `t_idempotency_key`;
+
+DROP TABLE `t_audit_log`;
+
+DROP TABLE `t_email`;
+
+DROP TABLE `t_outbox`;
+
+DROP TABLE `t_webhook_delivery`;
+
+DROP TABLE `t_webhook`;
+
+DROP TABLE `t_user_busy`;
+
+DROP TABLE `t_meeting_attendee`;
+
+DROP TABLE `t_resource_slot`;
+
+-- 外部キーが使っているインデックスは、外部キーを消してからでないと消せない
+ALTER TABLE `t_user_slot`
+  DROP FOREIGN KEY `t_user_slot_ibfk_2`;
+ALTER TABLE `t_user_slot`
+  DROP INDEX `meeting_id`,
+  DROP COLUMN `meeting_id`,
+  DROP COLUMN `source`;
+
+DROP TABLE `t_meeting`;
+
+DROP TABLE `t_resource`;
+
+DROP TABLE `t_user_ooo`;
+
+DROP TABLE `t_holiday`;
+
+DROP TABLE `t_holiday_calendar`;
+
+DROP TABLE `t_user_cap`;
+
+ALTER TABLE `t_user`
+  DROP FOREIGN KEY `t_user_ibfk_1`;
+ALTER TABLE `t_user`
+  DROP INDEX `tenant_id`,
+  ADD UNIQUE KEY `email` (`email`),
+  DROP COLUMN `tenant_id`,
+  DROP COLUMN `buffer_before`,
+  DROP COLUMN `buffer_after`,
+  DROP COLUMN `region`;
+
+DROP TABLE `t_tenant`;
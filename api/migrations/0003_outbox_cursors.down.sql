@@ -0,0 +1,3 @@
+-- 0003で加えたテーブルを消す。sinkごとの進み具合は失われ、t_outboxのpublished_atから配信し直す
+
+DROP TABLE `t_outbox_cursor`;
@@ -0,0 +1,12 @@
+-- outboxの配信の進み具合をsinkごとに持つ。一つのsinkが失敗しても、他のsinkに同じイベントを配信し直さない。
+-- 複数のプロセスが同じsinkに配信しないよう、locked_untilまでtokenを持つプロセスだけが配信する
+
+CREATE TABLE `t_outbox_cursor` (
+  `sink` varchar(64) CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci NOT NULL,
+  `last_id` bigint UNSIGNED NOT NULL,
+  `token` varchar(64) CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci DEFAULT NULL,
+  `locked_until` datetime DEFAULT NULL,
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  `updated_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  PRIMARY KEY (`sink`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
@@ -0,0 +1,788 @@
+use std::{fmt, net::SocketAddr, path::Path, time::Duration};
+
+use serde::Deserialize;
+use sqlx::mysql::MySqlPoolOptions;
+
+use crate::{
+    domains::{
+        auth::{Authenticator, JwtVerifier},
+        buffer::Buffer,
+        business_hours::BusinessHours,
+        email::EmailTemplates,
+        slot::Slot,
+    },
+    smtp_clients::email_sender::SmtpEmailSender,
+};
+
+/// 起動時の設定。設定ファイル(TOML)、環境変数、コマンドライン引数の順に後のものが優先される
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub log: LogConfig,
+    pub database: DatabaseConfig,
+    pub slots: SlotsConfig,
+    pub features: FeaturesConfig,
+    pub outbox: OutboxConfig,
+    pub smtp: SmtpConfig,
+    pub email: EmailConfig,
+    pub auth: AuthConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen: String,
+    /// 未指定の場合はCPUの数だけ起動する
+    pub workers: Option<usize>,
+    /// "development", "test", "production"のいずれか
+    pub mode: String,
+    /// 停止のシグナルを受け取ってから、処理中のリクエストとバックグラウンドのタスクを待つ秒数。
+    /// 処理中のリクエストを待つのはこのうち半分まで
+    pub shutdown_timeout_seconds: u64,
+}
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen: "0.0.0.0:8080".to_string(),
+            workers: None,
+            mode: "production".to_string(),
+            shutdown_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// RUST_LOGが設定されている場合はそちらを使う
+    pub level: String,
+    /// "json"または"text"
+    pub format: String,
+    /// 設定されている場合はspanをOTLP(gRPC)で送る。例えば"http://localhost:4317"
+    pub otlp_endpoint: Option<String>,
+}
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: "json".to_string(),
+            otlp_endpoint: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_seconds: u64,
+    /// 0の場合は使われていない接続を閉じない
+    pub idle_timeout_seconds: u64,
+    /// 0の場合は接続を作り直さない
+    pub max_lifetime_seconds: u64,
+    /// 起動時にmigrations/以下の変更を適用する。falseの場合は`api migrate`で適用する
+    pub migrate_on_startup: bool,
+}
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+            idle_timeout_seconds: 600,
+            max_lifetime_seconds: 1800,
+            migrate_on_startup: true,
+        }
+    }
+}
+impl DatabaseConfig {
+    pub fn pool_options(&self) -> MySqlPoolOptions {
+        let seconds = |x: u64| (x > 0).then(|| Duration::from_secs(x));
+        MySqlPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_seconds))
+            .idle_timeout(seconds(self.idle_timeout_seconds))
+            .max_lifetime(seconds(self.max_lifetime_seconds))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlotsConfig {
+    /// 1枠の長さ(分)。60の約数だけを受け付ける
+    pub duration_minutes: i64,
+    /// 会議の前後に確保するバッファ(分)。ユーザ個別の設定はt_userに持つ
+    pub buffer_before_minutes: i64,
+    pub buffer_after_minutes: i64,
+    /// "09:00"のような形式。始業から終業までに収まる枠だけを返し、確定できる。
+    /// 既定は以前の固定の時間帯と同じ10:00から20:00まで
+    pub business_hours_start: Option<String>,
+    pub business_hours_end: Option<String>,
+}
+impl Default for SlotsConfig {
+    fn default() -> Self {
+        Self {
+            duration_minutes: 30,
+            buffer_before_minutes: 0,
+            buffer_after_minutes: 0,
+            business_hours_start: Some("10:00".to_string()),
+            business_hours_end: Some("20:00".to_string()),
+        }
+    }
+}
+impl SlotsConfig {
+    pub fn slot_duration(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.duration_minutes)
+    }
+    pub fn buffer(&self) -> Buffer {
+        Buffer::from_minutes(self.buffer_before_minutes, self.buffer_after_minutes)
+    }
+    pub fn business_hours(&self) -> Result<Option<BusinessHours>, String> {
+        match (&self.business_hours_start, &self.business_hours_end) {
+            (None, None) => Ok(None),
+            (Some(start), Some(end)) => BusinessHours::parse(start, end)
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            _ => {
+                Err("BUSINESS_HOURS_START and BUSINESS_HOURS_END must be set together".to_string())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// /data以下を登録する。一度の誤操作で全ての予約が消えるので、productionでは有効にできない
+    pub test_data_routes: bool,
+    /// Webhookを送信する
+    pub webhook_delivery: bool,
+}
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            test_data_routes: false,
+            webhook_delivery: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutboxConfig {
+    /// "log", "file"のうち、イベントを配信するもの
+    pub sinks: Vec<String>,
+    pub file: String,
+}
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            sinks: vec![],
+            file: "events.jsonl".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpConfig {
+    /// 設定されている場合だけ招待メールを送る
+    pub host: Option<String>,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub starttls: bool,
+    pub from: String,
+}
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: None,
+            port: 25,
+            username: None,
+            password: None,
+            starttls: false,
+            from: "noreply@example.com".to_string(),
+        }
+    }
+}
+impl SmtpConfig {
+    /// SMTP_HOSTが設定されている場合だけ作る
+    pub fn sender(&self) -> Result<Option<SmtpEmailSender>, String> {
+        let Some(host) = &self.host else {
+            return Ok(None);
+        };
+        let credentials = self.username.clone().zip(self.password.clone());
+        SmtpEmailSender::new(host, self.port, credentials, self.starttls, &self.from)
+            .map(Some)
+            .map_err(|e| format!("SMTP: {}", e))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    /// テンプレートがあれば既定のものを置き換える
+    pub template_dir: Option<String>,
+}
+impl EmailConfig {
+    pub fn templates(&self) -> Result<EmailTemplates, String> {
+        match &self.template_dir {
+            Some(dir) => EmailTemplates::load(Path::new(dir))
+                .map_err(|e| format!("EMAIL_TEMPLATE_DIR: {}", e)),
+            None => Ok(EmailTemplates::default()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// "account:key[:admin]"のカンマ区切り
+    pub api_keys: String,
+    /// RS256で検証する公開鍵。JWT_SECRETより優先する
+    pub jwt_public_key_file: Option<String>,
+    /// HS256で検証する共通鍵
+    pub jwt_secret: Option<String>,
+    pub jwt_issuer: Option<String>,
+    pub jwt_audience: Option<String>,
+}
+impl AuthConfig {
+    /// JWT_PUBLIC_KEY_FILEとJWT_SECRETのどちらも無ければNone
+    pub fn jwt_verifier(&self) -> Result<Option<JwtVerifier>, String> {
+        let jwt = match &self.jwt_public_key_file {
+            Some(path) => {
+                let pem = std::fs::read(path)
+                    .map_err(|e| format!("JWT_PUBLIC_KEY_FILE: failed to read {}: {}", path, e))?;
+                let jwt = JwtVerifier::from_rsa_pem(&pem)
+                    .map_err(|e| format!("JWT_PUBLIC_KEY_FILE: {}", e))?;
+                Some(jwt)
+            }
+            None => self
+                .jwt_secret
+                .as_ref()
+                .map(|secret| JwtVerifier::from_secret(secret.as_bytes())),
+        };
+        Ok(jwt.map(|mut jwt| {
+            if let Some(issuer) = &self.jwt_issuer {
+                jwt = jwt.with_issuer(issuer);
+            }
+            if let Some(audience) = &self.jwt_audience {
+                jwt = jwt.with_audience(audience);
+            }
+            jwt
+        }))
+    }
+}
+
+/// 設定の誤りを全てまとめたもの
+#[derive(Debug, PartialEq)]
+pub struct ConfigError(pub Vec<String>);
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for e in &self.0 {
+            write!(f, "\n  - {}", e)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for ConfigError {}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Str,
+    Int,
+    Bool,
+    /// カンマ区切り
+    List,
+}
+
+/// 設定ファイルのキー、環境変数、コマンドライン引数の対応。秘密の値は引数では受け付けない
+const OVERRIDES: &[(&str, &str, Option<&str>, Kind)] = &[
+    (
+        "server.listen",
+        "LISTEN_ADDRESS",
+        Some("--listen"),
+        Kind::Str,
+    ),
+    ("server.workers", "WORKERS", Some("--workers"), Kind::Int),
+    ("server.mode", "APP_MODE", Some("--mode"), Kind::Str),
+    (
+        "server.shutdown_timeout_seconds",
+        "SHUTDOWN_TIMEOUT_SECONDS",
+        Some("--shutdown-timeout-seconds"),
+        Kind::Int,
+    ),
+    ("log.level", "LOG_LEVEL", Some("--log-level"), Kind::Str),
+    ("log.format", "LOG_FORMAT", Some("--log-format"), Kind::Str),
+    (
+        "log.otlp_endpoint",
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        Some("--otlp-endpoint"),
+        Kind::Str,
+    ),
+    ("database.url", "DATABASE_URL", None, Kind::Str),
+    (
+        "database.max_connections",
+        "DATABASE_MAX_CONNECTIONS",
+        Some("--database-max-connections"),
+        Kind::Int,
+    ),
+    (
+        "database.min_connections",
+        "DATABASE_MIN_CONNECTIONS",
+        Some("--database-min-connections"),
+        Kind::Int,
+    ),
+    (
+        "database.acquire_timeout_seconds",
+        "DATABASE_ACQUIRE_TIMEOUT_SECONDS",
+        Some("--database-acquire-timeout-seconds"),
+        Kind::Int,
+    ),
+    (
+        "database.idle_timeout_seconds",
+        "DATABASE_IDLE_TIMEOUT_SECONDS",
+        Some("--database-idle-timeout-seconds"),
+        Kind::Int,
+    ),
+    (
+        "database.max_lifetime_seconds",
+        "DATABASE_MAX_LIFETIME_SECONDS",
+        Some("--database-max-lifetime-seconds"),
+        Kind::Int,
+    ),
+    (
+        "database.migrate_on_startup",
+        "DATABASE_MIGRATE_ON_STARTUP",
+        Some("--database-migrate-on-startup"),
+        Kind::Bool,
+    ),
+    (
+        "slots.duration_minutes",
+        "SLOT_DURATION_MINUTES",
+        Some("--slot-duration-minutes"),
+        Kind::Int,
+    ),
+    (
+        "slots.buffer_before_minutes",
+        "BUFFER_BEFORE_MINUTES",
+        Some("--buffer-before-minutes"),
+        Kind::Int,
+    ),
+    (
+        "slots.buffer_after_minutes",
+        "BUFFER_AFTER_MINUTES",
+        Some("--buffer-after-minutes"),
+        Kind::Int,
+    ),
+    (
+        "slots.business_hours_start",
+        "BUSINESS_HOURS_START",
+        Some("--business-hours-start"),
+        Kind::Str,
+    ),
+    (
+        "slots.business_hours_end",
+        "BUSINESS_HOURS_END",
+        Some("--business-hours-end"),
+        Kind::Str,
+    ),
+    (
+        "features.test_data_routes",
+        "TEST_DATA_ROUTES",
+        Some("--test-data-routes"),
+        Kind::Bool,
+    ),
+    (
+        "features.webhook_delivery",
+        "WEBHOOK_DELIVERY",
+        Some("--webhook-delivery"),
+        Kind::Bool,
+    ),
+    (
+        "outbox.sinks",
+        "OUTBOX_SINKS",
+        Some("--outbox-sinks"),
+        Kind::List,
+    ),
+    (
+        "outbox.file",
+        "OUTBOX_FILE",
+        Some("--outbox-file"),
+        Kind::Str,
+    ),
+    ("smtp.host", "SMTP_HOST", Some("--smtp-host"), Kind::Str),
+    ("smtp.port", "SMTP_PORT", Some("--smtp-port"), Kind::Int),
+    ("smtp.username", "SMTP_USERNAME", None, Kind::Str),
+    ("smtp.password", "SMTP_PASSWORD", None, Kind::Str),
+    (
+        "smtp.starttls",
+        "SMTP_STARTTLS",
+        Some("--smtp-starttls"),
+        Kind::Bool,
+    ),
+    ("smtp.from", "SMTP_FROM", Some("--smtp-from"), Kind::Str),
+    (
+        "email.template_dir",
+        "EMAIL_TEMPLATE_DIR",
+        Some("--email-template-dir"),
+        Kind::Str,
+    ),
+    ("auth.api_keys", "API_KEYS", None, Kind::Str),
+    (
+        "auth.jwt_public_key_file",
+        "JWT_PUBLIC_KEY_FILE",
+        Some("--jwt-public-key-file"),
+        Kind::Str,
+    ),
+    ("auth.jwt_secret", "JWT_SECRET", None, Kind::Str),
+    (
+        "auth.jwt_issuer",
+        "JWT_ISSUER",
+        Some("--jwt-issuer"),
+        Kind::Str,
+    ),
+    (
+        "auth.jwt_audience",
+        "JWT_AUDIENCE",
+        Some("--jwt-audience"),
+        Kind::Str,
+    ),
+];
+
+/// 設定ファイルを指定する引数と環境変数
+const CONFIG_FLAG: &str = "--config";
+const CONFIG_ENV: &str = "CONFIG_FILE";
+/// 環境変数を書いたファイルを指定する引数と環境変数。既に設定されている環境変数は上書きしない
+const ENV_FILE_FLAG: &str = "--env-file";
+const ENV_FILE_ENV: &str = "ENV_FILE";
+
+impl Config {
+    /// サブコマンドを除いた引数、環境変数、設定ファイルから読む
+    pub fn load(args: Vec<String>) -> Result<Self, ConfigError> {
+        let args = parse_args(args.into_iter()).map_err(|e| ConfigError(vec![e]))?;
+        let find = |flag: &str, env: &str| {
+            args.iter()
+                .find(|(k, _)| k == flag)
+                .map(|(_, v)| v.clone())
+                .or_else(|| std::env::var(env).ok())
+        };
+        if let Some(path) = find(ENV_FILE_FLAG, ENV_FILE_ENV) {
+            dotenvy::from_filename(&path)
+                .map_err(|e| ConfigError(vec![format!("failed to load {}: {}", path, e)]))?;
+        }
+        let file = find(CONFIG_FLAG, CONFIG_ENV)
+            .map(|path| {
+                std::fs::read_to_string(&path)
+                    .map_err(|e| ConfigError(vec![format!("failed to read {}: {}", path, e)]))
+            })
+            .transpose()?;
+        Self::from_sources(file.as_deref(), |key| std::env::var(key).ok(), &args)
+    }
+
+    /// 設定ファイルの内容に環境変数、引数の順で上書きして検証する
+    pub fn from_sources(
+        file: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+        args: &[(String, String)],
+    ) -> Result<Self, ConfigError> {
+        let mut table = match file {
+            Some(text) => text
+                .parse::<toml::Table>()
+                .map_err(|e| ConfigError(vec![format!("config file: {}", e)]))?,
+            None => toml::Table::new(),
+        };
+        let mut errors = vec![];
+        for (flag, _) in args {
+            let known = [CONFIG_FLAG, ENV_FILE_FLAG].contains(&flag.as_str())
+                || OVERRIDES
+                    .iter()
+                    .any(|(_, _, f, _)| *f == Some(flag.as_str()));
+            if !known {
+                errors.push(format!("unknown argument: {}", flag));
+            }
+        }
+        for (key, env_name, flag, kind) in OVERRIDES {
+            let from_arg = flag.and_then(|f| {
+                args.iter()
+                    .rev()
+                    .find(|(k, _)| k == f)
+                    .map(|(_, v)| (f, v.clone()))
+            });
+            let Some((source, value)) = from_arg.or_else(|| env(env_name).map(|v| (*env_name, v)))
+            else {
+                continue;
+            };
+            match to_value(*kind, &value) {
+                Ok(value) => set(&mut table, key, value),
+                Err(e) => errors.push(format!("{}: {}", source, e)),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(ConfigError(errors));
+        }
+        let config = Config::deserialize(toml::Value::Table(table))
+            .map_err(|e| ConfigError(vec![format!("config file: {}", e.message())]))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// 起動してから失敗しないように、値の組み合わせを含めて確認する
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = vec![];
+        if self.database.url.is_none() {
+            errors.push("DATABASE_URL is required".to_string());
+        }
+        if self.server.listen.parse::<SocketAddr>().is_err() {
+            errors.push(format!("invalid listen address: {}", self.server.listen));
+        }
+        if !["json", "text"].contains(&self.log.format.as_str()) {
+            errors.push(format!("unknown LOG_FORMAT: {}", self.log.format));
+        }
+        if self.server.workers == Some(0) {
+            errors.push("WORKERS must be greater than 0".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("DATABASE_MAX_CONNECTIONS must be greater than 0".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            errors.push(format!(
+                "DATABASE_MIN_CONNECTIONS ({}) must not exceed DATABASE_MAX_CONNECTIONS ({})",
+                self.database.min_connections, self.database.max_connections
+            ));
+        }
+        if let Err(e) = Slot::validate_duration(self.slots.duration_minutes) {
+            errors.push(e.to_string());
+        }
+        if self.slots.buffer_before_minutes < 0 || self.slots.buffer_after_minutes < 0 {
+            errors.push("buffer minutes must not be negative".to_string());
+        }
+        if let Err(e) = self.slots.business_hours() {
+            errors.push(e);
+        }
+        match self.server.mode.as_str() {
+            "development" | "test" => {}
+            "production" if self.features.test_data_routes => {
+                errors.push("TEST_DATA_ROUTES must not be enabled in production".to_string())
+            }
+            "production" => {}
+            other => errors.push(format!("unknown APP_MODE: {}", other)),
+        }
+        for sink in &self.outbox.sinks {
+            if !["log", "file"].contains(&sink.as_str()) {
+                errors.push(format!("unknown outbox sink: {}", sink));
+            }
+        }
+        if let Err(e) = Authenticator::parse_api_keys(&self.auth.api_keys) {
+            errors.push(format!("API_KEYS: {}", e));
+        }
+        // ファイルの読み込みなど、起動時に作るものもここで確かめる
+        if let Err(e) = self.auth.jwt_verifier() {
+            errors.push(e);
+        }
+        if let Err(e) = self.email.templates() {
+            errors.push(e);
+        }
+        if let Err(e) = self.smtp.sender() {
+            errors.push(e);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError(errors))
+        }
+    }
+}
+
+/// "--name value"または"--name=value"の形の引数を読む
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Vec<(String, String)>, String> {
+    let mut args = args.peekable();
+    let mut parsed = vec![];
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("unexpected argument: {}", arg));
+        }
+        match arg.split_once('=') {
+            Some((name, value)) => parsed.push((name.to_string(), value.to_string())),
+            None => match args.next() {
+                Some(value) => parsed.push((arg, value)),
+                None => return Err(format!("missing value for {}", arg)),
+            },
+        }
+    }
+    Ok(parsed)
+}
+
+fn to_value(kind: Kind, value: &str) -> Result<toml::Value, String> {
+    match kind {
+        Kind::Str => Ok(toml::Value::String(value.to_string())),
+        Kind::Int => value
+            .parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| format!("invalid integer: {}", value)),
+        Kind::Bool => match value {
+            "true" => Ok(toml::Value::Boolean(true)),
+            "false" => Ok(toml::Value::Boolean(false)),
+            _ => Err(format!("expected true or false: {}", value)),
+        },
+        Kind::List => Ok(toml::Value::Array(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(|x| toml::Value::String(x.to_string()))
+                .collect(),
+        )),
+    }
+}
+
+/// "section.name"の形のキーに値を入れる
+fn set(table: &mut toml::Table, key: &str, value: toml::Value) {
+    let (section, name) = key.split_once('.').unwrap();
+    let section = table
+        .entry(section)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if let toml::Value::Table(section) = section {
+        section.insert(name.to_string(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn load(
+        file: Option<&str>,
+        env: &[(&str, &str)],
+        args: &[&str],
+    ) -> Result<Config, ConfigError> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let args = parse_args(args.iter().map(|x| x.to_string())).unwrap();
+        Config::from_sources(file, |key| env.get(key).cloned(), &args)
+    }
+
+    #[test]
+    fn test_precedence() {
+        let file = r#"
+            [server]
+            listen = "127.0.0.1:9000"
+            workers = 2
+
+            [database]
+            url = "mysql://file"
+            max_connections = 20
+
+            [outbox]
+            sinks = ["log"]
+        "#;
+        let env = [
+            ("WORKERS", "4"),
+            ("DATABASE_MAX_CONNECTIONS", "30"),
+            ("OUTBOX_SINKS", "log, file"),
+        ];
+        let config = load(Some(file), &env, &["--workers", "8"]).unwrap();
+        assert_eq!(config.server.listen, "127.0.0.1:9000");
+        assert_eq!(config.server.workers, Some(8));
+        assert_eq!(config.database.url.as_deref(), Some("mysql://file"));
+        assert_eq!(config.database.max_connections, 30);
+        assert_eq!(config.outbox.sinks, vec!["log", "file"]);
+        // 指定が無いものは既定値になる
+        assert_eq!(config.slots.duration_minutes, 30);
+        assert!(config.features.webhook_delivery);
+
+        let config = load(None, &[("DATABASE_URL", "mysql://env")], &[]).unwrap();
+        assert_eq!(config.server.listen, "0.0.0.0:8080");
+        assert_eq!(config.server.mode, "production");
+        assert_eq!(
+            config.slots.business_hours(),
+            Ok(Some(BusinessHours::parse("10:00", "20:00").unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_business_hours() {
+        let to_slot = |x: &str| {
+            Slot::new(chrono::NaiveDateTime::parse_from_str(x, "%Y-%m-%d %H:%M").unwrap())
+        };
+        // 既定の時間帯より広げると、その枠も返せる
+        let env = [
+            ("DATABASE_URL", "mysql://env"),
+            ("BUSINESS_HOURS_START", "09:00"),
+        ];
+        let hours = load(None, &env, &[])
+            .unwrap()
+            .slots
+            .business_hours()
+            .unwrap()
+            .unwrap();
+        assert!(hours.contains(&to_slot("2020-01-01 09:00")));
+        assert!(hours.contains(&to_slot("2020-01-01 19:30")));
+        assert!(!hours.contains(&to_slot("2020-01-01 20:00")));
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let env = [
+            ("WORKERS", "0"),
+            ("DATABASE_MIN_CONNECTIONS", "20"),
+            ("SLOT_DURATION_MINUTES", "45"),
+            ("BUSINESS_HOURS_END", "08:00"),
+            ("TEST_DATA_ROUTES", "true"),
+        ];
+        let ConfigError(errors) =
+            load(None, &env, &["--listen=localhost", "--outbox-sinks=kafka"]).unwrap_err();
+        assert_eq!(errors.len(), 8, "{:?}", errors);
+        assert!(errors.contains(&"DATABASE_URL is required".to_string()));
+        assert!(errors.contains(&"invalid listen address: localhost".to_string()));
+        assert!(errors.contains(&"unknown outbox sink: kafka".to_string()));
+
+        // 型の誤りと未知の引数・キーは、どこで指定したものかを返す
+        let ConfigError(errors) = load(
+            None,
+            &[("WORKERS", "many")],
+            &["--smtp-starttls=yes", "--port=1"],
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                "unknown argument: --port",
+                "WORKERS: invalid integer: many",
+                "--smtp-starttls: expected true or false: yes",
+            ]
+        );
+        let ConfigError(errors) = load(Some("[server]\nport = 1"), &[], &[]).unwrap_err();
+        assert!(errors[0].contains("unknown field `port`"), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_startup_errors() {
+        // 起動時に読むファイルや作るものの誤りも、パニックせずに設定の誤りとして返す
+        let env = [
+            ("DATABASE_URL", "mysql://env"),
+            ("JWT_PUBLIC_KEY_FILE", "/nonexistent/jwt.pem"),
+            ("SMTP_HOST", "localhost"),
+            ("SMTP_FROM", "not an address"),
+        ];
+        let ConfigError(errors) = load(None, &env, &[]).unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(
+            errors[0].starts_with("JWT_PUBLIC_KEY_FILE: failed to read /nonexistent/jwt.pem"),
+            "{:?}",
+            errors
+        );
+        assert!(errors[1].starts_with("SMTP: "), "{:?}", errors);
+    }
+}
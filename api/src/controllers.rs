@@ -1,4 +1,202 @@
+use actix_web::{middleware::DefaultHeaders, web};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+pub mod auth;
+pub mod calendars;
 pub mod data;
 pub mod error;
+pub mod health;
+pub mod holidays;
+pub mod idempotency;
+pub mod meetings;
+pub mod metrics;
+pub mod openapi;
+pub mod out_of_offices;
+pub mod resources;
 pub mod time_helper;
+pub mod trace;
 pub mod user_slots;
+pub mod v1;
+pub mod webhooks;
+
+/// 全てのハンドラを登録する。/v1の外は互換のために残している旧形式で、Deprecationヘッダを付けて返す。
+/// OpenAPIの定義とUIは/api-docs、死活監視は/health、メトリクスは/metricsで返す。それ以外はAPIキーかJWTで認証し、
+/// Idempotency-Keyを付けたPOSTは同じキーで再送されても一度しか処理しない。
+/// test_dataがtrueの場合だけ、全ての予約を見たり消したりできる/data以下を登録する
+pub fn routes(test_data: bool) -> impl Fn(&mut web::ServiceConfig) {
+    move |cfg| {
+        cfg.service(health::live)
+            .service(health::ready)
+            .service(metrics::index)
+            .service(
+                web::scope("/v1")
+                    .wrap(idempotency::Idempotency)
+                    .wrap_fn(auth::authenticate)
+                    .configure(|cfg| {
+                        if test_data {
+                            cfg.service(v1::data::index).service(data::clear);
+                        }
+                    })
+                    .service(v1::audit::index)
+                    .service(v1::user_slots::index)
+                    .service(v1::user_slots::post)
+                    .configure(shared_routes),
+            )
+            .service(
+                SwaggerUi::new("/api-docs/ui/{_:.*}")
+                    .url("/api-docs/openapi.json", openapi::ApiDoc::openapi()),
+            )
+            .service(
+                web::scope("")
+                    .wrap(idempotency::Idempotency)
+                    .wrap_fn(auth::authenticate)
+                    .wrap(
+                        DefaultHeaders::new()
+                            .add(("Deprecation", "true"))
+                            .add(("Link", "</v1>; rel=\"successor-version\"")),
+                    )
+                    .wrap_fn(error::legacy_body)
+                    .configure(|cfg| {
+                        if test_data {
+                            cfg.service(data::index).service(data::clear);
+                        }
+                    })
+                    .service(user_slots::index)
+                    .service(user_slots::post)
+                    .configure(shared_routes),
+            );
+    }
+}
+
+/// 旧形式と/v1で形式が変わらないハンドラ
+fn shared_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(user_slots::stream)
+        .service(holidays::import)
+        .service(out_of_offices::index)
+        .service(out_of_offices::post)
+        .service(resources::index)
+        .service(resources::post)
+        .service(meetings::ics)
+        .service(meetings::cancel)
+        .service(calendars::import)
+        .service(webhooks::post)
+        .service(webhooks::index)
+        .service(webhooks::deliveries);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+
+    use super::*;
+    use crate::{
+        domains::{
+            auth::Authenticator,
+            buffer::Buffer,
+            data_clients::{
+                user_slot_client::MockUserSlotClient, webhook_client::MockWebhookClient,
+            },
+        },
+        usecases::{user_slots::UserSlotUsecase, webhooks::WebhookUsecase},
+    };
+
+    #[actix_web::test]
+    async fn test_routes_require_credentials() {
+        let keys = Authenticator::parse_api_keys("test1@example.com:key1").unwrap();
+        // 認可で拒否するので、usecaseからは何も呼ばれない
+        let user_slots =
+            UserSlotUsecase::new(Arc::new(MockUserSlotClient::new()), Buffer::default());
+        let webhooks = WebhookUsecase::new(Arc::new(MockWebhookClient::new()));
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .app_data(web::Data::new(user_slots))
+                .app_data(web::Data::new(webhooks))
+                .configure(routes(true)),
+        )
+        .await;
+
+        let mut bodies = vec![];
+        for uri in ["/resources", "/v1/resources"] {
+            let res = call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(res.status(), 401, "{}", uri);
+            bodies.push(read_body(res).await);
+        }
+        // 旧形式のパスはエラーのメッセージだけを返し、/v1はリクエストのIDと合わせて返す
+        let body: serde_json::Value = serde_json::from_slice(&bodies[1]).unwrap();
+        assert_eq!(body["error"].as_str().unwrap().as_bytes(), bodies[0]);
+        assert!(body.as_object().unwrap().contains_key("requestId"));
+        let res = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/api-docs/openapi.json")
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+        let res = call_service(&app, TestRequest::get().uri("/health/live").to_request()).await;
+        assert_eq!(res.status(), 200);
+
+        // 管理者でなければWebhookは見られない
+        let req = TestRequest::get()
+            .uri("/v1/webhooks")
+            .insert_header((auth::API_KEY_HEADER, "key1"));
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 403);
+
+        // 本人が含まれない会議は確定できない
+        let req = TestRequest::post()
+            .uri("/v1/confirm")
+            .insert_header((auth::API_KEY_HEADER, "key1"))
+            .set_json(serde_json::json!({
+                "accounts": ["test2@example.com"],
+                "startTime": "2020-01-01T10:00:00",
+            }));
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_test_data_routes_are_disabled() {
+        let keys = Authenticator::parse_api_keys("admin@example.com:key:admin").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .configure(routes(false)),
+        )
+        .await;
+
+        for (method, uri) in [
+            ("GET", "/data/dump"),
+            ("POST", "/data/clear"),
+            ("GET", "/v1/data/dump"),
+            ("POST", "/v1/data/clear"),
+        ] {
+            let req = match method {
+                "GET" => TestRequest::get(),
+                _ => TestRequest::post(),
+            };
+            let req = req.uri(uri).insert_header((auth::API_KEY_HEADER, "key"));
+            let res = call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), 404, "{} {}", method, uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_legacy_routes_are_deprecated() {
+        let app = init_service(App::new().configure(routes(true))).await;
+
+        let res = call_service(&app, TestRequest::get().uri("/resources").to_request()).await;
+        assert_eq!(res.headers().get("Deprecation").unwrap(), "true");
+
+        let res = call_service(&app, TestRequest::get().uri("/v1/resources").to_request()).await;
+        assert_ne!(res.status(), 404);
+        assert!(res.headers().get("Deprecation").is_none());
+    }
+}
@@ -0,0 +1,142 @@
+use std::future::{ready, Future, Ready};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{Payload, Service, ServiceRequest, ServiceResponse},
+    web, FromRequest, HttpMessage, HttpRequest, ResponseError,
+};
+use futures::future::{Either, FutureExt};
+
+use crate::{
+    controllers::trace::{RequestId, REQUEST_ID_HEADER},
+    domains::{
+        audit::Actor,
+        auth::{Authenticator, Principal},
+        error::Error,
+    },
+};
+
+/// APIキーを受け取るヘッダ
+pub const API_KEY_HEADER: &str = "X-API-Key";
+/// テナントに紐付いていない管理者が、操作するテナントを選ぶヘッダ
+pub const TENANT_HEADER: &str = "X-Tenant";
+
+/// 呼び出し元を認証し、Principalとしてリクエストに持たせる。`wrap_fn`で使う
+pub fn authenticate<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<EitherBody<B>>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    let principal = match req.app_data::<web::Data<Authenticator>>() {
+        Some(authenticator) => authenticator.authenticate(
+            header(&req, API_KEY_HEADER),
+            header(&req, "Authorization"),
+            header(&req, TENANT_HEADER),
+        ),
+        None => Err(Error::Unauthorized(
+            "authentication is not configured".to_string(),
+        )),
+    };
+    match principal {
+        Ok(principal) => {
+            req.extensions_mut().insert(principal);
+            Either::Left(srv.call(req).map(|res| res.map(|x| x.map_into_left_body())))
+        }
+        Err(e) => {
+            let res = req.into_response(e.error_response()).map_into_right_body();
+            Either::Right(ready(Ok(res)))
+        }
+    }
+}
+
+fn header<'a>(req: &'a ServiceRequest, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|x| x.to_str().ok())
+}
+
+/// authenticateで認証済みの呼び出し元をハンドラで受け取る
+impl FromRequest for Principal {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticated(req))
+    }
+}
+
+/// authenticateで認証した呼び出し元
+fn authenticated(req: &HttpRequest) -> Result<Principal, actix_web::Error> {
+    req.extensions()
+        .get::<Principal>()
+        .cloned()
+        .ok_or_else(|| Error::Unauthorized("missing credentials".to_string()).into())
+}
+
+/// 予約を変更した呼び出し元を、監査ログに残すために受け取る
+impl FromRequest for Actor {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // traceで決めたIDを監査ログに残す。traceを通っていない場合はヘッダの値を使う
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(|x| x.0.clone())
+            .or_else(|| {
+                req.headers()
+                    .get(REQUEST_ID_HEADER)
+                    .and_then(|x| x.to_str().ok())
+                    .map(|x| x.to_string())
+            });
+        let actor = authenticated(req).map(|principal| Actor::new(principal.account, request_id));
+        ready(actor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        get,
+        test::{call_service, init_service, read_body, TestRequest},
+        App, HttpResponse,
+    };
+
+    use super::*;
+
+    #[get("/me")]
+    async fn me(principal: Principal) -> HttpResponse {
+        HttpResponse::Ok().body(principal.account)
+    }
+
+    #[actix_web::test]
+    async fn test_authenticate() {
+        let keys = Authenticator::parse_api_keys("test1@example.com:key1").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .service(web::scope("").wrap_fn(authenticate).service(me)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/me")
+            .insert_header((API_KEY_HEADER, "key1"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(read_body(res).await, "test1@example.com");
+
+        let req = TestRequest::get()
+            .uri("/me")
+            .insert_header((API_KEY_HEADER, "key2"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), 401);
+
+        let res = call_service(&app, TestRequest::get().uri("/me").to_request()).await;
+        assert_eq!(res.status(), 401);
+        assert_eq!(res.headers().get("WWW-Authenticate").unwrap(), "Bearer");
+    }
+}
@@ -0,0 +1,53 @@
+use actix_web::{post, web, HttpResponse};
+use itertools::Itertools;
+
+use crate::{domains::auth::Principal, usecases::calendars::CalendarUsecase};
+
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct SkippedEventResponse {
+    uid: Option<String>,
+    summary: String,
+    /// "allDay"、"unaligned"、"noEnd"または"unknownZone"
+    #[schema(example = "allDay")]
+    reason: &'static str,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct CalendarImportResponse {
+    free: usize,
+    busy: usize,
+    skipped: Vec<SkippedEventResponse>,
+}
+/// ICSファイルの内容をそのままbodyで受け取る
+#[utoipa::path(
+    operation_id = "importCalendar",
+    tag = "calendars",
+    params(("account" = String, Path, description = "アカウント")),
+    request_body(content = String, content_type = "text/calendar"),
+    responses(
+        (status = 200, body = CalendarImportResponse),
+        (status = 400, description = "ICSとして読めない"),
+    )
+)]
+#[post("/users/{account}/calendar.ics")]
+async fn import(
+    uc: web::Data<CalendarUsecase>,
+    account: web::Path<String>,
+    body: String,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_account(&account)?;
+    let import = uc.import_ics(principal.tenant(), &account, &body).await?;
+    Ok(HttpResponse::Ok().json(CalendarImportResponse {
+        free: import.free.len(),
+        busy: import.busy.len(),
+        skipped: import
+            .skipped
+            .into_iter()
+            .map(|x| SkippedEventResponse {
+                uid: x.uid,
+                summary: x.summary,
+                reason: x.reason.as_str(),
+            })
+            .collect_vec(),
+    }))
+}
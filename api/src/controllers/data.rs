@@ -1,13 +1,40 @@
 use actix_web::{get, post, web, HttpResponse};
 
-use crate::{domains::error::Error, usecases::data::DataUsecase};
+use crate::{
+    domains::{audit::Actor, auth::Principal, error::Error},
+    usecases::data::DataUsecase,
+};
 
+/// 確定済みの枠をアカウントごとに返す。動作確認用で、TEST_DATA_ROUTESを有効にした場合だけ登録する
+#[utoipa::path(
+    operation_id = "dumpData",
+    tag = "data",
+    responses(
+        (status = 200, description = "アカウントごとの確定済みの枠", body = HashMap<String, String>),
+    )
+)]
 #[get("/data/dump")]
-async fn index(uc: web::Data<DataUsecase>) -> Result<HttpResponse, Error> {
-    uc.dump().await.map(|map| HttpResponse::Ok().json(map))
+async fn index(uc: web::Data<DataUsecase>, principal: Principal) -> Result<HttpResponse, Error> {
+    principal.authorize_admin()?;
+    uc.dump(principal.tenant())
+        .await
+        .map(|map| HttpResponse::Ok().json(map))
 }
 
+/// 予約を全て消す。動作確認用で、TEST_DATA_ROUTESを有効にした場合だけ登録する
+#[utoipa::path(
+    operation_id = "clearData",
+    tag = "data",
+    responses((status = 200, description = "消した"))
+)]
 #[post("/data/clear")]
-async fn clear(uc: web::Data<DataUsecase>) -> Result<HttpResponse, Error> {
-    uc.clear().await.map(|_| HttpResponse::Ok().finish())
+async fn clear(
+    uc: web::Data<DataUsecase>,
+    principal: Principal,
+    actor: Actor,
+) -> Result<HttpResponse, Error> {
+    principal.authorize_admin()?;
+    uc.clear(principal.tenant(), &actor)
+        .await
+        .map(|_| HttpResponse::Ok().finish())
 }
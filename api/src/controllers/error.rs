@@ -1,21 +1,78 @@
+use std::future::Future;
+
 use actix_web::{
-    http::{header::ContentType, StatusCode},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{ContentType, WWW_AUTHENTICATE},
+        StatusCode,
+    },
     HttpResponse, ResponseError,
 };
 
-use crate::domains::error::Error;
+use crate::{controllers::trace::current_request_id, domains::error::Error, metrics::metrics};
+
+tokio::task_local! {
+    /// 旧形式のパスのリクエストを処理している間だけ設定する
+    static LEGACY: ();
+}
+
+/// /v1でエラーの場合に返すbody。旧形式のパスは互換のためにメッセージだけを返す
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    #[schema(example = "already slot exist.")]
+    error: String,
+    /// ログと突き合わせるためのリクエストのID。X-Request-Idヘッダと同じ
+    request_id: Option<String>,
+}
+
+/// 旧形式のパスでは、エラーのbodyを/v1を追加する前と同じメッセージだけにする。
+/// `wrap_fn`で旧形式のscopeの一番外側に使う
+pub fn legacy_body<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    // 認証の失敗などは呼び出した時点でレスポンスを作るので、その間も設定する
+    let fut = LEGACY.sync_scope((), || srv.call(req));
+    LEGACY.scope((), fut)
+}
 
 impl ResponseError for Error {
     fn error_response(&self) -> HttpResponse {
-        HttpResponse::build(self.status_code())
-            .insert_header(ContentType::json())
-            .body(self.to_string())
+        metrics().errors.with_label_values(&[self.kind()]).inc();
+        let mut res = HttpResponse::build(self.status_code());
+        if let Error::Unauthorized(_) = self {
+            res.insert_header((WWW_AUTHENTICATE, "Bearer"));
+        }
+        res.insert_header(ContentType::json());
+        if LEGACY.try_with(|_| ()).is_ok() {
+            return res.body(self.to_string());
+        }
+        // ログと突き合わせられるように、リクエストのIDを含める
+        let body = ErrorResponse {
+            error: self.to_string(),
+            request_id: current_request_id(),
+        };
+        res.body(serde_json::to_string(&body).unwrap())
     }
 
     fn status_code(&self) -> StatusCode {
         match *self {
             Error::Conflicts => StatusCode::CONFLICT,
             Error::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::LimitExceeded(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Unavailable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::PublishError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Error::Forbidden(_) => StatusCode::FORBIDDEN,
+            Error::KeyReused(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::InProgress(_) => StatusCode::CONFLICT,
+            Error::Deadlock(_) => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 }
@@ -0,0 +1,203 @@
+use actix_web::{get, web, HttpResponse};
+
+use crate::{
+    domains::health::{MigrationStatus, Readiness},
+    usecases::health::HealthUsecase,
+};
+
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct LivenessResponse {
+    #[schema(example = "ok")]
+    status: &'static str,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct ReadinessResponse {
+    /// "ok"または"unavailable"
+    #[schema(example = "ok")]
+    status: &'static str,
+    database: DatabaseCheck,
+    migrations: MigrationCheck,
+    pool: PoolCheck,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct DatabaseCheck {
+    #[schema(example = "ok")]
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct MigrationCheck {
+    /// "unmanaged", "applied", "pending", "dirty", "unknown"のいずれか。
+    /// unmanagedは以前のdb/ddl.sqlで作ったスキーマ、pendingはrequiredまで適用し終えていない
+    #[schema(example = "applied")]
+    status: &'static str,
+    /// 適用した最後のバージョン
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<i64>,
+    /// 起動したバイナリが必要とするバージョン
+    #[serde(skip_serializing_if = "Option::is_none")]
+    required: Option<i64>,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct PoolCheck {
+    size: u32,
+    idle: u32,
+    #[serde(rename = "maxConnections")]
+    max_connections: u32,
+    /// 最大接続数のうち使用中の割合
+    #[schema(example = 0.2)]
+    saturation: f64,
+}
+
+impl From<Readiness> for ReadinessResponse {
+    fn from(readiness: Readiness) -> Self {
+        let status = |ok| if ok { "ok" } else { "unavailable" };
+        let required = readiness.required_migration;
+        let (migration_status, version) = match &readiness.migrations {
+            None => ("unknown", None),
+            Some(MigrationStatus::Unmanaged) => ("unmanaged", None),
+            Some(MigrationStatus::Applied {
+                dirty: true,
+                version,
+            }) => ("dirty", Some(*version)),
+            Some(m @ MigrationStatus::Applied { version, .. }) => {
+                let current = m.is_ready(required);
+                (if current { "applied" } else { "pending" }, Some(*version))
+            }
+        };
+        let migrations = MigrationCheck {
+            status: migration_status,
+            version,
+            required,
+        };
+        Self {
+            status: status(readiness.is_ready()),
+            database: DatabaseCheck {
+                status: status(readiness.database.is_ok()),
+                error: readiness.database.clone().err(),
+            },
+            migrations,
+            pool: PoolCheck {
+                size: readiness.pool.size,
+                idle: readiness.pool.idle,
+                max_connections: readiness.pool.max_connections,
+                saturation: readiness.pool.saturation(),
+            },
+        }
+    }
+}
+
+/// プロセスが応答できるかどうか。DBには接続しない
+#[utoipa::path(
+    operation_id = "liveness",
+    tag = "health",
+    responses((status = 200, body = LivenessResponse))
+)]
+#[get("/health/live")]
+async fn live() -> HttpResponse {
+    HttpResponse::Ok().json(LivenessResponse { status: "ok" })
+}
+
+/// トラフィックを受けられるかどうか。DBに接続できない間と、スキーマの変更を適用し終えていない場合は503
+#[utoipa::path(
+    operation_id = "readiness",
+    tag = "health",
+    responses(
+        (status = 200, body = ReadinessResponse),
+        (status = 503, body = ReadinessResponse, description = "DBに接続できない、またはスキーマが古い"),
+    )
+)]
+#[get("/health/ready")]
+async fn ready(uc: web::Data<HealthUsecase>) -> HttpResponse {
+    let readiness = uc.readiness().await;
+    let mut res = if readiness.is_ready() {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    res.json(ReadinessResponse::from(readiness))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        test::{call_service, init_service, read_body_json, TestRequest},
+        App,
+    };
+
+    use super::*;
+    use crate::domains::{
+        data_clients::health_client::MockHealthClient, error::Error, health::PoolStatus,
+    };
+
+    #[actix_web::test]
+    async fn test_ready() {
+        let mut mock = MockHealthClient::new();
+        mock.expect_ping().times(1).returning(|| Ok(()));
+        mock.expect_ping()
+            .times(1)
+            .returning(|| Err(Error::DbError("connection refused".to_string())));
+        mock.expect_fetch_migration_status().times(1).returning(|| {
+            Ok(MigrationStatus::Applied {
+                version: 1,
+                dirty: false,
+            })
+        });
+        mock.expect_pool_status()
+            .returning(|| PoolStatus::new(5, 1, 10));
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(HealthUsecase::new(Arc::new(mock))))
+                .service(live)
+                .service(ready),
+        )
+        .await;
+
+        let res = call_service(&app, TestRequest::get().uri("/health/ready").to_request()).await;
+        assert_eq!(res.status(), 200);
+        let body: serde_json::Value = read_body_json(res).await;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": "ok",
+                "database": {"status": "ok"},
+                "migrations": {"status": "applied", "version": 1},
+                "pool": {"size": 5, "idle": 1, "maxConnections": 10, "saturation": 0.4},
+            })
+        );
+
+        // DBに接続できなくなったら外してもらう
+        let res = call_service(&app, TestRequest::get().uri("/health/ready").to_request()).await;
+        assert_eq!(res.status(), 503);
+        let body: serde_json::Value = read_body_json(res).await;
+        assert_eq!(body["database"]["error"], "DbError: connection refused");
+        assert_eq!(body["migrations"]["status"], "unknown");
+
+        // livenessはDBに関係なく返す
+        let res = call_service(&app, TestRequest::get().uri("/health/live").to_request()).await;
+        assert_eq!(res.status(), 200);
+    }
+
+    #[test]
+    fn test_pending_migrations() {
+        // 起動したバイナリが必要とするバージョンまで適用されるまでは外してもらう
+        let readiness = Readiness {
+            database: Ok(()),
+            migrations: Some(MigrationStatus::Applied {
+                version: 1,
+                dirty: false,
+            }),
+            required_migration: Some(2),
+            pool: PoolStatus::new(1, 1, 10),
+        };
+        let body = serde_json::to_value(ReadinessResponse::from(readiness)).unwrap();
+        assert_eq!(body["status"], "unavailable");
+        assert_eq!(
+            body["migrations"],
+            serde_json::json!({"status": "pending", "version": 1, "required": 2})
+        );
+    }
+}
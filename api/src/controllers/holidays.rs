@@ -0,0 +1,44 @@
+use actix_web::{post, web, HttpResponse};
+
+use crate::{domains::auth::Principal, usecases::holidays::HolidayUsecase};
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HolidayImportParams {
+    /// 未指定の場合は全社共通のカレンダーになる
+    region: Option<String>,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct HolidayImportResult {
+    imported: usize,
+}
+/// ICSファイルの内容でカレンダーの休日を置き換える
+#[utoipa::path(
+    operation_id = "importHolidays",
+    tag = "holidays",
+    params(("calendar" = String, Path, description = "カレンダーの名前"), HolidayImportParams),
+    request_body(content = String, content_type = "text/calendar"),
+    responses(
+        (status = 200, body = HolidayImportResult),
+        (status = 400, description = "ICSとして読めない"),
+    )
+)]
+#[post("/holidays/{calendar}")]
+async fn import(
+    uc: web::Data<HolidayUsecase>,
+    calendar: web::Path<String>,
+    query_params: web::Query<HolidayImportParams>,
+    body: String,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let imported = uc
+        .import(
+            principal.tenant(),
+            &calendar,
+            query_params.into_inner().region,
+            &body,
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(HolidayImportResult { imported }))
+}
@@ -0,0 +1,245 @@
+use std::{
+    future::{ready, Future, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+
+use actix_web::{
+    body::{self, BoxBody},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    error::PayloadError,
+    http::{header::CONTENT_TYPE, Method, StatusCode},
+    web, HttpMessage, HttpResponse, ResponseError,
+};
+
+use crate::{
+    domains::{
+        auth::Principal,
+        error::Error,
+        idempotency::{request_hash, StoredResponse},
+    },
+    usecases::idempotency::{IdempotencyUsecase, Reservation},
+};
+
+/// 再送しても一度しか処理しないためのキーを受け取るヘッダ
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+/// 保存したレスポンスを返したことを示すヘッダ
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// Idempotency-Keyを付けたPOSTのレスポンスを保存し、同じキーで再送された場合は保存したものを返す。
+/// キーは認証済みの呼び出し元ごとに管理するので、authenticateの内側で使う
+pub struct Idempotency;
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = actix_web::Error;
+    type Transform = IdempotencyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: body::MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let key = req
+            .headers()
+            .get(IDEMPOTENCY_KEY_HEADER)
+            .map(|x| x.to_str().unwrap_or_default().to_string());
+        let principal = req.extensions().get::<Principal>().cloned();
+        let (Some(key), Some(principal), &Method::POST) = (key, principal, req.method()) else {
+            return Box::pin(async move { Ok(service.call(req).await?.map_into_boxed_body()) });
+        };
+        Box::pin(async move {
+            let Some(uc) = req.app_data::<web::Data<IdempotencyUsecase>>().cloned() else {
+                let e = Error::InvalidInput(format!("{} is not supported", IDEMPOTENCY_KEY_HEADER));
+                return Ok(req.into_response(e.error_response()));
+            };
+            // ハッシュを取るために読んだbodyを、ハンドラが読めるように戻す
+            let body = req.extract::<web::Bytes>().await?;
+            let hash = request_hash(req.method().as_str(), &req.uri().to_string(), &body);
+            req.set_payload(bytes_to_payload(body));
+
+            let tenant = principal.tenant();
+            let account = &principal.account;
+            match uc.reserve(tenant, account, &key, &hash).await {
+                Err(e) => return Ok(req.into_response(e.error_response())),
+                Ok(Reservation::Replay(stored)) => {
+                    let mut res = HttpResponse::build(
+                        StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK),
+                    );
+                    if let Some(content_type) = stored.content_type {
+                        res.insert_header((CONTENT_TYPE, content_type));
+                    }
+                    let res = res
+                        .insert_header((REPLAYED_HEADER, "true"))
+                        .body(stored.body);
+                    return Ok(req.into_response(res));
+                }
+                Ok(Reservation::New) => {}
+            }
+
+            let res = match service.call(req).await {
+                Ok(res) => res,
+                Err(e) => {
+                    if let Err(e) = uc.finish(tenant, account, &key, &server_error()).await {
+                        log::error!("failed to release idempotency key: {}", e);
+                    }
+                    return Err(e);
+                }
+            };
+            let (http_req, res) = res.into_parts();
+            let content_type = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|x| x.to_str().ok())
+                .map(|x| x.to_string());
+            let status = res.status();
+            let (res, body) = res.into_parts();
+            let body = match body::to_bytes(body).await {
+                Ok(body) => body,
+                Err(e) => {
+                    if let Err(e) = uc.finish(tenant, account, &key, &server_error()).await {
+                        log::error!("failed to release idempotency key: {}", e);
+                    }
+                    return Err(actix_web::error::ErrorInternalServerError(e.into()));
+                }
+            };
+            let stored = StoredResponse::new(status.as_u16(), content_type, body.to_vec());
+            if let Err(e) = uc.finish(tenant, account, &key, &stored).await {
+                log::error!("failed to save idempotent response: {}", e);
+            }
+            Ok(ServiceResponse::new(http_req, res.set_body(body)).map_into_boxed_body())
+        })
+    }
+}
+
+/// 処理を終えられなかったキーは消して、再送できるようにする
+fn server_error() -> StoredResponse {
+    StoredResponse::new(StatusCode::INTERNAL_SERVER_ERROR.as_u16(), None, vec![])
+}
+
+fn bytes_to_payload(body: web::Bytes) -> Payload {
+    let stream = futures::stream::once(ready(Ok::<_, PayloadError>(body)));
+    Payload::from(Box::pin(stream) as Pin<Box<dyn futures::Stream<Item = _>>>)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use actix_web::{
+        post,
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+
+    use super::*;
+    use crate::{
+        controllers::auth::{authenticate, API_KEY_HEADER},
+        domains::{
+            auth::Authenticator, data_clients::idempotency_client::MockIdempotencyClient,
+            idempotency::IdempotencyRecord,
+        },
+    };
+
+    #[post("/confirm")]
+    async fn confirm(calls: web::Data<AtomicUsize>, body: String) -> HttpResponse {
+        let id = calls.fetch_add(1, Ordering::SeqCst) + 1;
+        HttpResponse::Created().body(format!("{}:{}", id, body))
+    }
+
+    #[actix_web::test]
+    async fn test_idempotency() {
+        // 保存したレスポンスを次のreserve_keyで返す
+        let saved: Arc<Mutex<Option<IdempotencyRecord>>> = Arc::new(Mutex::new(None));
+        let mut mock = MockIdempotencyClient::new();
+        let reserved = saved.clone();
+        mock.expect_reserve_key().returning(move |_, _, _, hash| {
+            let mut saved = reserved.lock().unwrap();
+            let record = saved.clone();
+            if record.is_none() {
+                *saved = Some(IdempotencyRecord::new(hash.to_string(), None));
+            }
+            Ok(record)
+        });
+        let responded = saved.clone();
+        mock.expect_save_response()
+            .times(1)
+            .returning(move |_, _, _, response| {
+                let mut saved = responded.lock().unwrap();
+                saved.as_mut().unwrap().response = Some(response.clone());
+                Ok(())
+            });
+        let keys = Authenticator::parse_api_keys("test1@example.com:key1").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .app_data(web::Data::new(IdempotencyUsecase::new(Arc::new(mock))))
+                .app_data(web::Data::new(AtomicUsize::new(0)))
+                .service(
+                    web::scope("")
+                        .wrap(Idempotency)
+                        .wrap_fn(authenticate)
+                        .service(confirm),
+                ),
+        )
+        .await;
+        let request = |key: Option<&str>, body: &str| {
+            let req = TestRequest::post()
+                .uri("/confirm")
+                .insert_header((API_KEY_HEADER, "key1"))
+                .set_payload(body.to_string());
+            match key {
+                Some(key) => req.insert_header((IDEMPOTENCY_KEY_HEADER, key)),
+                None => req,
+            }
+            .to_request()
+        };
+
+        let res = call_service(&app, request(Some("key-1"), "body")).await;
+        assert_eq!(res.status(), 201);
+        assert!(res.headers().get(REPLAYED_HEADER).is_none());
+        assert_eq!(read_body(res).await, "1:body");
+
+        // 再送しても処理せず、最初のレスポンスを返す
+        let res = call_service(&app, request(Some("key-1"), "body")).await;
+        assert_eq!(res.status(), 201);
+        assert_eq!(res.headers().get(REPLAYED_HEADER).unwrap(), "true");
+        assert_eq!(read_body(res).await, "1:body");
+
+        // 内容の違うリクエストにキーを使い回すことはできない
+        let res = call_service(&app, request(Some("key-1"), "other")).await;
+        assert_eq!(res.status(), 422);
+
+        // キーが無ければ毎回処理する
+        let res = call_service(&app, request(None, "body")).await;
+        assert_eq!(read_body(res).await, "2:body");
+    }
+}
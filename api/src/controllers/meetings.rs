@@ -0,0 +1,48 @@
+use actix_web::{get, post, web, HttpResponse};
+
+use crate::{
+    domains::{audit::Actor, auth::Principal},
+    usecases::meetings::MeetingUsecase,
+};
+
+/// カレンダーアプリから購読できるICSフィード
+#[utoipa::path(
+    operation_id = "exportMeetings",
+    tag = "meetings",
+    params(("account" = String, Path, description = "アカウント")),
+    responses((status = 200, content_type = "text/calendar", body = String))
+)]
+#[get("/users/{account}/meetings.ics")]
+async fn ics(
+    uc: web::Data<MeetingUsecase>,
+    account: web::Path<String>,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_account(&account)?;
+    let ics = uc.export_ics(principal.tenant(), &account).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(ics))
+}
+
+/// 会議を取り消す。取り消し済みの場合は何もしない
+#[utoipa::path(
+    operation_id = "cancelMeeting",
+    tag = "meetings",
+    params(("id" = u32, Path, description = "会議のid")),
+    responses(
+        (status = 200, description = "取り消した"),
+        (status = 403, description = "参加者でも管理者でもない"),
+        (status = 404, description = "会議が無い"),
+    )
+)]
+#[post("/meetings/{id}/cancel")]
+async fn cancel(
+    uc: web::Data<MeetingUsecase>,
+    id: web::Path<u32>,
+    principal: Principal,
+    actor: Actor,
+) -> Result<HttpResponse, actix_web::Error> {
+    uc.cancel(id.into_inner(), &principal, &actor).await?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,110 @@
+use std::{future::Future, time::Instant};
+
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse},
+    get, web, HttpResponse,
+};
+use futures::FutureExt;
+
+use crate::{metrics::metrics, usecases::health::HealthUsecase};
+
+/// メソッドとルートごとにリクエスト数と処理時間を記録する。`wrap_fn`で使う
+pub fn record<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    let started = Instant::now();
+    srv.call(req).map(move |res| {
+        if let Ok(res) = &res {
+            let method = res.request().method().to_string();
+            // パスそのものではラベルが増え続けるので、登録したパターンを使う
+            let route = res
+                .request()
+                .match_pattern()
+                .unwrap_or_else(|| "unmatched".to_string());
+            metrics()
+                .http_requests
+                .with_label_values(&[&method, &route, res.status().as_str()])
+                .inc();
+            metrics()
+                .http_request_duration
+                .with_label_values(&[&method, &route])
+                .observe(started.elapsed().as_secs_f64());
+        }
+        res
+    })
+}
+
+/// Prometheusのテキスト形式でメトリクスを返す
+#[utoipa::path(
+    operation_id = "metrics",
+    tag = "health",
+    responses((status = 200, content_type = "text/plain", body = String))
+)]
+#[get("/metrics")]
+async fn index(uc: web::Data<HealthUsecase>) -> HttpResponse {
+    metrics().set_pool_status(&uc.pool_status());
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics().encode())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+
+    use super::*;
+    use crate::domains::{
+        data_clients::health_client::MockHealthClient, error::Error, health::PoolStatus,
+    };
+
+    #[get("/metrics-test/{id}")]
+    async fn conflict() -> Result<HttpResponse, actix_web::Error> {
+        Err(Error::Conflicts.into())
+    }
+
+    #[actix_web::test]
+    async fn test_record() {
+        let mut mock = MockHealthClient::new();
+        mock.expect_pool_status()
+            .returning(|| PoolStatus::new(3, 1, 10));
+        let app = init_service(
+            App::new()
+                .wrap_fn(record)
+                .app_data(web::Data::new(HealthUsecase::new(Arc::new(mock))))
+                .service(conflict)
+                .service(index),
+        )
+        .await;
+        for id in 1..=2 {
+            let uri = format!("/metrics-test/{}", id);
+            let res = call_service(&app, TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(res.status(), 409);
+        }
+
+        let res = call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+        assert_eq!(res.status(), 200);
+        let body = String::from_utf8(read_body(res).await.to_vec()).unwrap();
+        assert!(
+            body.contains(
+                r#"http_requests_total{method="GET",route="/metrics-test/{id}",status="409"} 2"#
+            ),
+            "{}",
+            body
+        );
+        assert!(
+            body.contains(r#"errors_total{kind="conflicts"}"#),
+            "{}",
+            body
+        );
+        assert!(body.contains("db_pool_max_connections 10"), "{}", body);
+    }
+}
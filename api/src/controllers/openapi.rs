@@ -0,0 +1,356 @@
+use utoipa::{
+    openapi::{
+        self,
+        path::{ParameterBuilder, ParameterIn, PathItemType},
+        schema::{ObjectBuilder, Ref, SchemaType},
+        security::{
+            ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme,
+        },
+        ContentBuilder, Deprecated, RefOr, Required, ResponseBuilder,
+    },
+    Modify, OpenApi,
+};
+
+use crate::controllers::{
+    auth::{API_KEY_HEADER, TENANT_HEADER},
+    calendars, data, error, health, holidays,
+    idempotency::IDEMPOTENCY_KEY_HEADER,
+    meetings, metrics, out_of_offices, resources, user_slots, v1, webhooks,
+};
+
+/// ハンドラとDTOから生成するOpenAPIの定義。ハンドラを追加したらpathsにも追加する
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "actix-web-sample"),
+    modifiers(&Versioning, &ErrorBodies, &Security, &IdempotencyKey),
+    paths(
+        health::live,
+        health::ready,
+        metrics::index,
+        v1::audit::index,
+        v1::data::index,
+        v1::user_slots::index,
+        v1::user_slots::post,
+        data::index,
+        data::clear,
+        user_slots::index,
+        user_slots::stream,
+        user_slots::post,
+        holidays::import,
+        out_of_offices::index,
+        out_of_offices::post,
+        resources::index,
+        resources::post,
+        meetings::ics,
+        meetings::cancel,
+        calendars::import,
+        webhooks::post,
+        webhooks::index,
+        webhooks::deliveries,
+    ),
+    components(schemas(
+        error::ErrorResponse,
+        health::LivenessResponse,
+        health::ReadinessResponse,
+        health::DatabaseCheck,
+        health::MigrationCheck,
+        health::PoolCheck,
+        v1::audit::AuditEntryDto,
+        v1::data::UserSlotsDto,
+        v1::data::SlotRangeDto,
+        v1::user_slots::SlotDto,
+        v1::user_slots::ConfirmRequest,
+        v1::user_slots::MeetingDto,
+        user_slots::SlotsResponse,
+        user_slots::RoomSlotResponse,
+        user_slots::ConfirmSlotParam,
+        holidays::HolidayImportResult,
+        out_of_offices::OutOfOfficeParam,
+        out_of_offices::OutOfOfficeResponse,
+        resources::ResourceParam,
+        calendars::CalendarImportResponse,
+        calendars::SkippedEventResponse,
+        webhooks::WebhookParam,
+        webhooks::WebhookCreated,
+        webhooks::WebhookRes,
+        webhooks::DeliveryRes,
+    ))
+)]
+pub struct ApiDoc;
+
+/// /v1、/health、/metricsの外のパスを旧形式として非推奨にする。
+/// 形式が変わらないハンドラは/v1にも登録しているので、/v1のパスとしても載せる
+struct Versioning;
+
+impl Modify for Versioning {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let paths = &mut openapi.paths.paths;
+        let legacy = paths
+            .keys()
+            .filter(|path| !path.starts_with("/v1/") && !is_unversioned(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in legacy {
+            let mut item = paths.remove(&path).unwrap();
+            paths
+                .entry(format!("/v1{}", path))
+                .or_insert_with(|| item.clone());
+            for operation in item.operations.values_mut() {
+                operation.deprecated = Some(Deprecated::True);
+                // operationIdは/v1のものと重ならないようにする
+                operation.operation_id = operation.operation_id.as_ref().map(|id| {
+                    let mut chars = id.chars();
+                    let head = chars.next().map(|c| c.to_ascii_uppercase());
+                    format!(
+                        "legacy{}{}",
+                        head.into_iter().collect::<String>(),
+                        chars.as_str()
+                    )
+                });
+            }
+            paths.insert(path, item);
+        }
+    }
+}
+
+/// /v1のエラーのレスポンスにErrorResponseを載せ、どのパスでも返しうる401と500も載せる。
+/// 旧形式のパスのエラーは互換のためにメッセージだけを返すので、bodyは載せない
+struct ErrorBodies;
+
+impl Modify for ErrorBodies {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let content = ContentBuilder::new()
+            .schema(Ref::from_schema_name("ErrorResponse"))
+            .build();
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if !path.starts_with("/v1/") {
+                continue;
+            }
+            for operation in item.operations.values_mut() {
+                let responses = &mut operation.responses.responses;
+                for (status, description) in
+                    [("401", "認証されていない"), ("500", "DBのエラーなど")]
+                {
+                    responses.entry(status.to_string()).or_insert_with(|| {
+                        RefOr::T(ResponseBuilder::new().description(description).build())
+                    });
+                }
+                for (status, response) in responses.iter_mut() {
+                    let RefOr::T(response) = response else {
+                        continue;
+                    };
+                    if (status.starts_with('4') || status.starts_with('5'))
+                        && response.content.is_empty()
+                    {
+                        response
+                            .content
+                            .insert("application/json".to_string(), content.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 死活監視とメトリクスのパスは認証せず、バージョンも付けない
+fn is_unversioned(path: &str) -> bool {
+    path.starts_with("/health/") || path == "/metrics"
+}
+
+/// /healthと/metrics以外はAPIキーとJWTのどちらかで認証する
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "apiKey",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                API_KEY_HEADER.to_string(),
+                format!(
+                    "テナントに紐付いていない管理者は{}ヘッダで操作するテナントを選べる",
+                    TENANT_HEADER
+                ),
+            ))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        let no_scopes: [&str; 0] = [];
+        openapi.security = Some(vec![
+            SecurityRequirement::new("apiKey", no_scopes),
+            SecurityRequirement::new("bearer", no_scopes),
+        ]);
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if is_unversioned(path) {
+                for operation in item.operations.values_mut() {
+                    operation.security = Some(vec![]);
+                }
+            }
+        }
+    }
+}
+
+/// POSTにIdempotency-Keyヘッダを載せる
+struct IdempotencyKey;
+
+impl Modify for IdempotencyKey {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let parameter = ParameterBuilder::new()
+            .name(IDEMPOTENCY_KEY_HEADER)
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "同じキーで再送された場合は処理せずに最初のレスポンスを返す。\
+                 内容の違うリクエストに使い回した場合は422",
+            ))
+            .schema(Some(ObjectBuilder::new().schema_type(SchemaType::String)))
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            if let Some(operation) = item.operations.get_mut(&PathItemType::Post) {
+                operation
+                    .parameters
+                    .get_or_insert_with(Vec::new)
+                    .push(parameter.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+
+    use actix_web::{
+        dev::Service,
+        test::{call_service, init_service, TestRequest},
+        web, App,
+    };
+    use utoipa::openapi::PathItemType;
+
+    use super::*;
+    use crate::{controllers::routes, domains::auth::Authenticator};
+
+    const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// 生成した定義がコミットされているopenapi.jsonと一致すること。
+    /// ハンドラやDTOを変えた場合は`UPDATE_OPENAPI=1 cargo test`で更新する
+    #[test]
+    fn test_spec_is_up_to_date() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        if std::env::var("UPDATE_OPENAPI").is_ok() {
+            std::fs::write(SPEC_PATH, &spec).unwrap();
+        }
+        let committed = std::fs::read_to_string(SPEC_PATH).unwrap();
+        assert!(
+            committed == spec,
+            "openapi.json is out of date. run `UPDATE_OPENAPI=1 cargo test` to update it"
+        );
+    }
+
+    /// 定義にある全てのパスとメソッドにハンドラが登録されていること
+    #[actix_web::test]
+    async fn test_spec_matches_routes() {
+        let keys = Authenticator::parse_api_keys("admin@example.com:key:admin").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .configure(routes(true)),
+        )
+        .await;
+        for (path, item) in ApiDoc::openapi().paths.paths {
+            let uri = to_uri(&path);
+            for method in item.operations.keys() {
+                let (name, req) = match method {
+                    PathItemType::Get => ("GET", TestRequest::get()),
+                    PathItemType::Post => ("POST", TestRequest::post()),
+                    _ => panic!("unexpected method for {}", path),
+                };
+                let req = req.uri(&uri).insert_header((API_KEY_HEADER, "key"));
+                let res = call_service(&app, req.to_request()).await;
+                // ハンドラがあればusecaseが無いことによる500などになる
+                assert_ne!(res.status(), 404, "{} {} is not routed", name, path);
+                assert_ne!(res.status(), 405, "{} {} is not routed", name, path);
+            }
+        }
+    }
+
+    /// 登録した全てのパスとメソッドが定義にあること。/api-docsはUIのためのものなので除く
+    #[actix_web::test]
+    async fn test_routes_are_in_spec() {
+        let keys = Authenticator::parse_api_keys("admin@example.com:key:admin").unwrap();
+        // actix-webは登録したパスの一覧を返さないので、ResourceMapのDebug出力から集める
+        let resource_map = Rc::new(RefCell::new(String::new()));
+        let captured = resource_map.clone();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .wrap_fn(move |req, srv| {
+                    *captured.borrow_mut() = format!("{:?}", req.request().resource_map());
+                    srv.call(req)
+                })
+                .configure(routes(true)),
+        )
+        .await;
+        call_service(&app, TestRequest::get().uri("/health/live").to_request()).await;
+        let (scopes, resources) = registered_patterns(&resource_map.borrow());
+        assert!(scopes.contains("/v1"));
+
+        let spec = ApiDoc::openapi().paths.paths;
+        for path in scopes
+            .iter()
+            .flat_map(|scope| resources.iter().map(move |x| format!("{}{}", scope, x)))
+            .filter(|path| !path.starts_with("/api-docs"))
+        {
+            for (method, item_type) in [("GET", PathItemType::Get), ("POST", PathItemType::Post)] {
+                let req = match item_type {
+                    PathItemType::Get => TestRequest::get(),
+                    _ => TestRequest::post(),
+                };
+                let req = req
+                    .uri(&to_uri(&path))
+                    .insert_header((API_KEY_HEADER, "key"));
+                let res = call_service(&app, req.to_request()).await;
+                // 別のscopeのパスや、別のメソッドのハンドラの場合は404か405になる
+                if res.status() == 404 || res.status() == 405 {
+                    continue;
+                }
+                assert!(
+                    matches!(spec.get(&path), Some(item) if item.operations.contains_key(&item_type)),
+                    "{} {} is not in the spec",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+
+    /// パスのパラメータを適当な値で埋める
+    fn to_uri(path: &str) -> String {
+        path.split('/')
+            .map(|s| if s.starts_with('{') { "1" } else { s })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// ResourceMapのDebug出力から、scopeのパターンとハンドラのパターンを分けて返す
+    fn registered_patterns(resource_map: &str) -> (BTreeSet<String>, BTreeSet<String>) {
+        let mut scopes = BTreeSet::new();
+        let mut resources = BTreeSet::new();
+        for def in resource_map.split("patterns: Single(\"").skip(1) {
+            let (pattern, rest) = def.split_once("\")").unwrap();
+            if rest.starts_with(", is_prefix: true") {
+                scopes.insert(pattern.to_string());
+            } else {
+                resources.insert(pattern.to_string());
+            }
+        }
+        (scopes, resources)
+    }
+}
@@ -0,0 +1,88 @@
+use actix_web::{get, post, web, HttpResponse};
+use itertools::Itertools;
+
+use crate::{
+    controllers::time_helper::{self, TimeFormat, TimeFormatParam},
+    domains::{auth::Principal, out_of_office::OutOfOffice},
+    usecases::out_of_offices::OutOfOfficeUsecase,
+};
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct OutOfOfficeParam {
+    /// 旧形式、RFC 3339、ISO 8601のいずれか
+    #[serde(rename = "startTime")]
+    #[schema(example = "2020/01/01 10:00")]
+    start_time: String,
+    #[serde(rename = "endTime")]
+    #[schema(example = "2020/01/03 10:00")]
+    end_time: String,
+    #[serde(default)]
+    reason: String,
+}
+/// 不在期間を登録する
+#[utoipa::path(
+    operation_id = "registerOutOfOffice",
+    tag = "out_of_offices",
+    params(("account" = String, Path, description = "アカウント")),
+    request_body = OutOfOfficeParam,
+    responses(
+        (status = 201, description = "登録した"),
+        (status = 400, description = "期間が正しくない"),
+    )
+)]
+#[post("/users/{account}/ooo")]
+async fn post(
+    uc: web::Data<OutOfOfficeUsecase>,
+    account: web::Path<String>,
+    params: web::Json<OutOfOfficeParam>,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_account(&account)?;
+    let params = params.into_inner();
+    let out_of_office = OutOfOffice::new(
+        time_helper::parse_datetime(params.start_time.as_str())?,
+        time_helper::parse_datetime(params.end_time.as_str())?,
+        params.reason,
+    );
+    uc.register(principal.tenant(), &account, out_of_office)
+        .await?;
+    Ok(HttpResponse::Created().finish())
+}
+
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct OutOfOfficeResponse {
+    #[serde(rename = "startTime")]
+    #[schema(example = "2020/01/01 10:00")]
+    start_time: String,
+    #[serde(rename = "endTime")]
+    #[schema(example = "2020/01/03 10:00")]
+    end_time: String,
+    reason: String,
+}
+/// これから先の不在期間を返す
+#[utoipa::path(
+    operation_id = "listOutOfOffices",
+    tag = "out_of_offices",
+    params(("account" = String, Path, description = "アカウント"), TimeFormatParam),
+    responses((status = 200, body = [OutOfOfficeResponse]))
+)]
+#[get("/users/{account}/ooo")]
+async fn index(
+    uc: web::Data<OutOfOfficeUsecase>,
+    account: web::Path<String>,
+    format: TimeFormat,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_account(&account)?;
+    let periods = uc
+        .fetch_upcoming(principal.tenant(), &account)
+        .await?
+        .into_iter()
+        .map(|ooo| OutOfOfficeResponse {
+            start_time: format.format(&ooo.start),
+            end_time: format.format(&ooo.end),
+            reason: ooo.reason,
+        })
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(periods))
+}
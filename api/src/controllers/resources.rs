@@ -0,0 +1,133 @@
+use actix_web::{get, post, web, HttpResponse};
+use itertools::Itertools;
+
+use crate::{
+    domains::{
+        auth::Principal,
+        resource::{Resource, ResourceKind},
+    },
+    usecases::resources::ResourceUsecase,
+};
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct ResourceParam {
+    name: String,
+    /// "room"または"equipment"
+    #[schema(example = "room")]
+    kind: String,
+    capacity: u32,
+    #[serde(default)]
+    attributes: Vec<String>,
+}
+/// 会議室や備品を登録する
+#[utoipa::path(
+    operation_id = "createResource",
+    tag = "resources",
+    request_body = ResourceParam,
+    responses(
+        (status = 201, description = "登録した"),
+        (status = 400, description = "kindまたはcapacityが正しくない"),
+        (status = 409, description = "同じ名前のリソースが登録済み"),
+    )
+)]
+#[post("/resources")]
+async fn post(
+    uc: web::Data<ResourceUsecase>,
+    params: web::Json<ResourceParam>,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let params = params.into_inner();
+    let resource = Resource::new(
+        params.name,
+        params.kind.parse::<ResourceKind>()?,
+        params.capacity,
+        params.attributes,
+    );
+    uc.create(principal.tenant(), resource).await?;
+    Ok(HttpResponse::Created().finish())
+}
+
+#[utoipa::path(
+    operation_id = "listResources",
+    tag = "resources",
+    responses((status = 200, body = [ResourceParam]))
+)]
+#[get("/resources")]
+async fn index(
+    uc: web::Data<ResourceUsecase>,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    let resources = uc
+        .fetch_all(principal.tenant())
+        .await?
+        .into_iter()
+        .map(|r| ResourceParam {
+            name: r.name,
+            kind: r.kind.as_str().to_string(),
+            capacity: r.capacity,
+            attributes: r.attributes,
+        })
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(resources))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+
+    use super::*;
+    use crate::{
+        controllers::auth::{authenticate, API_KEY_HEADER},
+        domains::{
+            auth::Authenticator, data_clients::resource_client::MockResourceClient, error::Error,
+        },
+    };
+
+    #[actix_web::test]
+    async fn test_post_duplicate() {
+        let mut mock = MockResourceClient::new();
+        mock.expect_create_resource()
+            .withf(|tenant, resource| tenant == "default" && resource.name == "room-a")
+            .times(2)
+            .returning({
+                let mut created = false;
+                move |_, _| {
+                    if created {
+                        return Err(Error::Conflicts);
+                    }
+                    created = true;
+                    Ok(())
+                }
+            });
+        let keys = Authenticator::parse_api_keys("admin@example.com:admin-key:admin").unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .app_data(web::Data::new(ResourceUsecase::new(Arc::new(mock))))
+                .service(web::scope("/v1").wrap_fn(authenticate).service(post)),
+        )
+        .await;
+
+        let body = serde_json::json!({"name": "room-a", "kind": "room", "capacity": 4});
+        let req = TestRequest::post()
+            .uri("/v1/resources")
+            .insert_header((API_KEY_HEADER, "admin-key"))
+            .set_json(&body);
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 201);
+
+        // 同じ名前は登録できない
+        let req = TestRequest::post()
+            .uri("/v1/resources")
+            .insert_header((API_KEY_HEADER, "admin-key"))
+            .set_json(&body);
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 409);
+    }
+}
@@ -1,10 +1,114 @@
-use chrono::NaiveDateTime;
+use std::future::{ready, Ready};
 
-pub fn to_naive_datetime(date: &str) -> NaiveDateTime {
-    NaiveDateTime::parse_from_str(date, "%Y/%m/%d %H:%M").unwrap()
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use chrono::{DateTime, Local, NaiveDateTime, SecondsFormat, TimeZone};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::domains::error::Error;
+
+/// 旧形式の`%Y/%m/%d %H:%M`
+const LEGACY_FORMAT: &str = "%Y/%m/%d %H:%M";
+/// オフセットの無いISO 8601の形式
+const ISO8601_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// 旧形式、RFC 3339、ISO 8601のいずれかの日時を読む。
+/// オフセット付きの場合はサーバのタイムゾーンの日時に変換する。オフセットが無い場合はそのまま使う
+pub fn parse_datetime(date: &str) -> Result<NaiveDateTime, Error> {
+    if let Ok(date) = NaiveDateTime::parse_from_str(date, LEGACY_FORMAT) {
+        return Ok(date);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(date)
+        .or_else(|_| DateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f%z"))
+    {
+        return Ok(date.with_timezone(&Local).naive_local());
+    }
+    ISO8601_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+        .ok_or_else(|| Error::InvalidInput(format!("invalid datetime: {}", date)))
 }
 pub fn to_ymdhm_str(date: &NaiveDateTime) -> String {
-    date.format("%Y/%m/%d %H:%M").to_string()
+    date.format(LEGACY_FORMAT).to_string()
+}
+
+/// レスポンスの日時の形式。リクエストの`timeFormat`で選び、未指定の場合は旧形式にする
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// `2020/01/01 10:00`
+    #[default]
+    Legacy,
+    /// `2020-01-01T10:00:00`
+    Iso8601,
+    /// `2020-01-01T10:00:00+09:00`
+    Rfc3339,
+}
+
+impl TimeFormat {
+    pub fn format(&self, date: &NaiveDateTime) -> String {
+        match self {
+            Self::Legacy => to_ymdhm_str(date),
+            Self::Iso8601 => date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            // 夏時間の切り替えで存在しない日時はオフセットを付けられないので、ISO 8601にする
+            Self::Rfc3339 => match Local.from_local_datetime(date).earliest() {
+                Some(date) => date.to_rfc3339_opts(SecondsFormat::Secs, false),
+                None => Self::Iso8601.format(date),
+            },
+        }
+    }
+
+    pub fn timestamp(&self, date: NaiveDateTime) -> Timestamp {
+        Timestamp {
+            value: date,
+            format: *self,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TimeFormatParam {
+    /// レスポンスの日時の形式。未指定の場合はlegacy
+    #[serde(rename = "timeFormat")]
+    #[param(inline)]
+    #[allow(dead_code)]
+    time_format: Option<TimeFormat>,
+}
+
+impl FromRequest for TimeFormat {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let format = web::Query::<TimeFormatParam>::from_query(req.query_string())
+            .map(|q| q.into_inner().time_format.unwrap_or_default())
+            .map_err(|e| Error::InvalidInput(format!("timeFormat: {}", e)).into());
+        ready(format)
+    }
+}
+
+/// レスポンスの日時。リクエストで選ばれた形式で書き出す
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timestamp {
+    value: NaiveDateTime,
+    format: TimeFormat,
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.format.format(&self.value))
+    }
+}
+
+/// DTOの日時をparse_datetimeが読める形式で受け取る。`#[serde(deserialize_with = "deserialize")]`で使う
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDateTime, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    parse_datetime(&text).map_err(serde::de::Error::custom)
 }
 
 #[cfg(test)]
@@ -13,8 +117,8 @@ mod test {
     use chrono::NaiveDate;
 
     #[test]
-    fn test_to_naive_datetime() {
-        let date = to_naive_datetime("2020/01/01 10:00");
+    fn test_parse_legacy_datetime() {
+        let date = parse_datetime("2020/01/01 10:00").unwrap();
         assert_eq!(
             date,
             NaiveDate::from_ymd_opt(2020, 1, 1)
@@ -29,4 +133,56 @@ mod test {
             .unwrap();
         assert_eq!(to_ymdhm_str(&date), "2020/01/01 10:00");
     }
+    #[test]
+    fn test_parse_datetime() {
+        let expected = parse_datetime("2020/01/01 10:00").unwrap();
+        assert_eq!(parse_datetime("2020-01-01T10:00:00").unwrap(), expected);
+        assert_eq!(parse_datetime("2020-01-01T10:00").unwrap(), expected);
+        assert_eq!(parse_datetime("2020-01-01 10:00:00.000").unwrap(), expected);
+
+        // オフセット付きはサーバのタイムゾーンに変換する
+        let utc = chrono::Utc
+            .with_ymd_and_hms(2020, 1, 1, 1, 0, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .naive_local();
+        assert_eq!(parse_datetime("2020-01-01T10:00:00+09:00").unwrap(), utc);
+        assert_eq!(parse_datetime("2020-01-01T01:00:00Z").unwrap(), utc);
+        assert_eq!(parse_datetime("2020-01-01T10:00:00+0900").unwrap(), utc);
+
+        assert!(matches!(
+            parse_datetime("2020/01/01"),
+            Err(Error::InvalidInput(_))
+        ));
+    }
+    #[test]
+    fn test_time_format() {
+        let date = parse_datetime("2020/01/01 10:00").unwrap();
+        assert_eq!(TimeFormat::Legacy.format(&date), "2020/01/01 10:00");
+        assert_eq!(TimeFormat::Iso8601.format(&date), "2020-01-01T10:00:00");
+        let rfc3339 = TimeFormat::Rfc3339.format(&date);
+        assert_eq!(parse_datetime(&rfc3339).unwrap(), date);
+        assert_eq!(
+            serde_json::to_string(&TimeFormat::Iso8601.timestamp(date)).unwrap(),
+            r#""2020-01-01T10:00:00""#
+        );
+    }
+    #[actix_web::test]
+    async fn test_time_format_from_request() {
+        use actix_web::test::TestRequest;
+
+        let req = TestRequest::get()
+            .uri("/slots?accounts=a&timeFormat=rfc3339")
+            .to_http_request();
+        let format = TimeFormat::extract(&req).await.unwrap();
+        assert_eq!(format, TimeFormat::Rfc3339);
+
+        let req = TestRequest::get().uri("/slots").to_http_request();
+        assert_eq!(TimeFormat::extract(&req).await.unwrap(), TimeFormat::Legacy);
+
+        let req = TestRequest::get()
+            .uri("/slots?timeFormat=unix")
+            .to_http_request();
+        assert!(TimeFormat::extract(&req).await.is_err());
+    }
 }
@@ -0,0 +1,127 @@
+use std::{future::Future, time::Instant};
+
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    HttpMessage,
+};
+use tracing::{field::Empty, Instrument};
+
+/// リクエストを識別するヘッダ。呼び出し元が付けていなければ生成し、レスポンスにも付ける
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+/// 呼び出し元が付けたリクエストIDの最大長。長すぎるものは使わずに生成し直す
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    /// 処理中のリクエストのID
+    static REQUEST_ID: String;
+}
+
+/// 処理中のリクエストのID。エラーのbodyに含める。リクエストの外ではNone
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// リクエストごとにIDを決め、spanを作ってその中で処理する。`wrap_fn`で一番外側に使う
+pub fn trace<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|x| x.to_str().ok())
+        .filter(|x| !x.is_empty() && x.len() <= MAX_REQUEST_ID_LENGTH)
+        .map(|x| x.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+        route = Empty,
+        status = Empty,
+    );
+    let started = Instant::now();
+    // 認証の失敗などは呼び出した時点でレスポンスを作るので、その間もIDを参照できるようにする
+    let fut = span.in_scope(|| REQUEST_ID.sync_scope(request_id.clone(), || srv.call(req)));
+    let id = request_id.clone();
+    REQUEST_ID
+        .scope(request_id, async move {
+            let mut res = match fut.await {
+                Ok(res) => res,
+                Err(e) => {
+                    tracing::error!(error = %e, "request failed");
+                    return Err(e);
+                }
+            };
+            let span = tracing::Span::current();
+            if let Some(route) = res.request().match_pattern() {
+                span.record("route", route.as_str());
+            }
+            span.record("status", res.status().as_u16());
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static("x-request-id"), value);
+            }
+            tracing::info!(
+                elapsed_ms = started.elapsed().as_millis() as u64,
+                "{} {}",
+                res.request().method(),
+                res.status()
+            );
+            Ok(res)
+        })
+        .instrument(span)
+}
+
+/// traceで決めたリクエストID
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        get,
+        test::{call_service, init_service, read_body_json, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::*;
+    use crate::domains::error::Error;
+
+    #[get("/fail")]
+    async fn fail(id: web::ReqData<RequestId>) -> Result<HttpResponse, actix_web::Error> {
+        assert_eq!(current_request_id(), Some(id.0.clone()));
+        Err(Error::Conflicts.into())
+    }
+
+    #[actix_web::test]
+    async fn test_trace() {
+        let app = init_service(App::new().wrap_fn(trace).service(fail)).await;
+
+        // 呼び出し元が付けたIDをそのまま返し、エラーのbodyにも含める
+        let req = TestRequest::get()
+            .uri("/fail")
+            .insert_header((REQUEST_ID_HEADER, "req-1"));
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 409);
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "req-1");
+        let body: serde_json::Value = read_body_json(res).await;
+        assert_eq!(
+            body,
+            serde_json::json!({"error": "already slot exist.", "requestId": "req-1"})
+        );
+
+        // 付いていなければ生成する
+        let res = call_service(&app, TestRequest::get().uri("/fail").to_request()).await;
+        let id = res.headers().get(REQUEST_ID_HEADER).unwrap().clone();
+        let body: serde_json::Value = read_body_json(res).await;
+        assert_eq!(body["requestId"], id.to_str().unwrap());
+        assert_eq!(id.len(), 36);
+    }
+}
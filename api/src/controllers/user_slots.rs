@@ -1,50 +1,228 @@
 use actix_web::{get, post, web, HttpResponse};
+use futures::StreamExt;
 use itertools::Itertools;
 
-use crate::{controllers::time_helper, usecases::user_slots::UserSlotUsecase};
+use crate::{
+    controllers::time_helper::{self, TimeFormat, TimeFormatParam},
+    domains::{
+        audit::Actor,
+        auth::Principal,
+        resource::RoomRequirement,
+        slot_events::{SlotEvents, SlotWatch},
+        slot_range::SlotRange,
+    },
+    shutdown::Shutdown,
+    usecases::user_slots::{SlotUpdate, UserSlotUsecase},
+};
 
-#[derive(Debug, serde::Deserialize)]
-struct UserSlotParams {
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSlotParams {
+    /// カンマ区切りのアカウント
+    #[param(example = "test1@example.com,test2@example.com")]
     accounts: String,
+    /// 旧形式、RFC 3339、ISO 8601のいずれか
     #[serde(rename = "startTime")]
+    #[param(example = "2020/01/01 10:00")]
     start_time: String,
     #[serde(rename = "endTime")]
+    #[param(example = "2020/01/01 20:00")]
     end_time: String,
+    /// 指定した場合は、この人数以上入れる会議室が空いている枠だけを会議室と合わせて返す
+    #[serde(rename = "roomCapacity")]
+    room_capacity: Option<u32>,
+    /// 会議室に必要な設備をカンマ区切りで指定する
+    #[serde(rename = "roomAttributes")]
+    room_attributes: Option<String>,
 }
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct RoomSlotResponse {
+    #[serde(rename = "startTime")]
+    #[schema(example = "2020/01/01 10:00")]
+    start_time: String,
+    room: String,
+}
+/// 会議室の条件が無い場合は開始日時の一覧、ある場合は会議室と合わせた一覧
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+#[serde(untagged)]
+pub enum SlotsResponse {
+    #[schema(example = json!(["2020/01/01 10:00"]))]
+    Slots(Vec<String>),
+    RoomSlots(Vec<RoomSlotResponse>),
+}
+/// 全員が確定可能な枠の開始日時を返す。会議室の条件を指定した場合は会議室と合わせて返す
+#[utoipa::path(
+    operation_id = "listSlots",
+    tag = "slots",
+    params(UserSlotParams, TimeFormatParam),
+    responses(
+        (status = 200, body = SlotsResponse),
+        (status = 400, description = "日時が正しくない"),
+    )
+)]
 #[get("/slots")]
 async fn index(
     uc: web::Data<UserSlotUsecase>,
     query_params: web::Query<UserSlotParams>,
+    format: TimeFormat,
+    principal: Principal,
 ) -> Result<HttpResponse, actix_web::Error> {
-    // MEMO: 日付に変換できない場合は考慮してない
     let accounts = query_params
         .accounts
         .split(',')
         .map(|x| x.to_string())
         .collect_vec();
-    let start_date = time_helper::to_naive_datetime(query_params.start_time.as_str());
-    let end_date = time_helper::to_naive_datetime(query_params.end_time.as_str());
+    let start_date = time_helper::parse_datetime(query_params.start_time.as_str())?;
+    let end_date = time_helper::parse_datetime(query_params.end_time.as_str())?;
+    if query_params.room_capacity.is_some() || query_params.room_attributes.is_some() {
+        // 人数の指定が無い場合は参加者全員が入れる会議室にする
+        let requirement = RoomRequirement::new(
+            query_params.room_capacity.unwrap_or(accounts.len() as u32),
+            query_params
+                .room_attributes
+                .iter()
+                .flat_map(|x| x.split(','))
+                .map(|x| x.to_string())
+                .collect_vec(),
+        );
+        let room_slots = uc
+            .fetch_confirmable_room_slots(
+                principal.tenant(),
+                &accounts,
+                start_date,
+                end_date,
+                &requirement,
+            )
+            .await?
+            .into_iter()
+            .map(|x| RoomSlotResponse {
+                start_time: format.format(&x.slot.start_date),
+                room: x.room.name,
+            })
+            .collect_vec();
+        return Ok(HttpResponse::Ok().json(SlotsResponse::RoomSlots(room_slots)));
+    }
     let slots = uc
-        .fetch_confirmable_slots(&accounts, start_date, end_date)
+        .fetch_confirmable_slots(principal.tenant(), &accounts, start_date, end_date)
         .await?
         .iter()
-        .map(|x| time_helper::to_ymdhm_str(&x.start_date))
+        .map(|x| format.format(&x.start_date))
         .collect::<Vec<_>>();
-    Ok(HttpResponse::Ok().json(slots))
+    Ok(HttpResponse::Ok().json(SlotsResponse::Slots(slots)))
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SlotStreamParams {
+    /// カンマ区切りのアカウント
+    #[param(example = "test1@example.com,test2@example.com")]
+    accounts: String,
+    /// 旧形式、RFC 3339、ISO 8601のいずれか
+    #[serde(rename = "startTime")]
+    #[param(example = "2020/01/01 10:00")]
+    start_time: String,
+    #[serde(rename = "endTime")]
+    #[param(example = "2020/01/01 20:00")]
+    end_time: String,
+}
+/// 確定可能な枠をServer-Sent Eventsで送り続ける。
+/// 接続直後と、確定・取り消しで枠が変わるたびに`slots`イベントで/slotsと同じ形式の一覧を送る
+#[utoipa::path(
+    operation_id = "streamSlots",
+    tag = "slots",
+    params(SlotStreamParams, TimeFormatParam),
+    responses(
+        (status = 200, description = "`slots`イベントのdataは/slotsと同じ形式", content_type = "text/event-stream", body = String),
+    )
+)]
+#[get("/slots/stream")]
+async fn stream(
+    uc: web::Data<UserSlotUsecase>,
+    events: web::Data<SlotEvents>,
+    shutdown: web::Data<Shutdown>,
+    query_params: web::Query<SlotStreamParams>,
+    format: TimeFormat,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    let accounts = query_params
+        .accounts
+        .split(',')
+        .map(|x| x.to_string())
+        .collect_vec();
+    let watch = SlotWatch::new(
+        principal.tenant().to_string(),
+        accounts,
+        SlotRange::new(
+            time_helper::parse_datetime(query_params.start_time.as_str())?,
+            time_helper::parse_datetime(query_params.end_time.as_str())?,
+        ),
+    );
+    let body = uc
+        .into_inner()
+        .watch_confirmable_slots(&events, watch, Shutdown::clone(&shutdown))
+        .map(move |update| {
+            let message = match update {
+                Ok(SlotUpdate::Slots(slots)) => {
+                    let slots = slots
+                        .iter()
+                        .map(|x| format.format(&x.start_date))
+                        .collect_vec();
+                    format!(
+                        "event: slots\ndata: {}\n\n",
+                        serde_json::to_string(&slots).unwrap()
+                    )
+                }
+                Ok(SlotUpdate::KeepAlive) => ": keep-alive\n\n".to_string(),
+                Err(e) => format!("event: error\ndata: {}\n\n", e),
+            };
+            Ok::<_, actix_web::Error>(web::Bytes::from(message))
+        });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body))
 }
 
-#[derive(Debug, serde::Deserialize)]
-struct ConfirmSlotParam {
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct ConfirmSlotParam {
+    #[schema(example = json!(["test1@example.com", "test2@example.com"]))]
     accounts: Vec<String>,
+    /// 旧形式、RFC 3339、ISO 8601のいずれか
     #[serde(rename = "startTime")]
+    #[schema(example = "2020/01/01 10:00")]
     start_time: String,
+    /// 一緒に予約する会議室
+    room: Option<String>,
 }
+/// 参加者全員の枠を確定する
+#[utoipa::path(
+    operation_id = "confirmSlot",
+    tag = "slots",
+    request_body = ConfirmSlotParam,
+    responses(
+        (status = 201, description = "確定した"),
+        (status = 400, description = "日時が正しくない"),
+        (status = 403, description = "参加者に本人が含まれていない"),
+        (status = 409, description = "既に予定がある"),
+        (status = 422, description = "休日・不在期間、上限に達している、または会議室に入りきらない"),
+    )
+)]
 #[post("/confirm")]
 async fn post(
     uc: web::Data<UserSlotUsecase>,
     params: web::Json<ConfirmSlotParam>,
+    principal: Principal,
+    actor: Actor,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let start_time = time_helper::to_naive_datetime(params.start_time.as_str());
-    uc.confirm_users_slot(&params.accounts, start_time).await?;
+    principal.authorize_attendees(&params.accounts)?;
+    let start_time = time_helper::parse_datetime(params.start_time.as_str())?;
+    uc.confirm_users_slot(
+        principal.tenant(),
+        &actor,
+        &params.accounts,
+        start_time,
+        params.room.clone(),
+    )
+    .await?;
     Ok(HttpResponse::Created().finish())
 }
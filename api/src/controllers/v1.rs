@@ -0,0 +1,3 @@
+pub mod audit;
+pub mod data;
+pub mod user_slots;
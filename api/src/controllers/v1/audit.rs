@@ -0,0 +1,196 @@
+use actix_web::{get, web, HttpResponse};
+use itertools::Itertools;
+
+use crate::{
+    controllers::time_helper::{self, TimeFormat, TimeFormatParam, Timestamp},
+    domains::{
+        audit::{AuditAction, AuditFilter, DEFAULT_AUDIT_LIMIT},
+        auth::Principal,
+    },
+    usecases::audit::AuditUsecase,
+};
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// 変更を行ったアカウント
+    actor: Option<String>,
+    /// 予定が変わったアカウント
+    #[param(example = "test1@example.com")]
+    account: Option<String>,
+    #[param(example = "cancel")]
+    action: Option<String>,
+    /// この日時以降の記録だけを返す。旧形式、RFC 3339、ISO 8601のいずれか
+    #[param(example = "2020-01-01T00:00:00+09:00")]
+    from: Option<String>,
+    /// この日時より前の記録だけを返す
+    #[param(example = "2020-01-02T00:00:00+09:00")]
+    to: Option<String>,
+    /// 最大1000件。既定は100件
+    limit: Option<u32>,
+}
+/// 監査ログの1件
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct AuditEntryDto {
+    id: u64,
+    actor: String,
+    #[schema(example = "confirm")]
+    action: &'static str,
+    #[serde(rename = "requestId")]
+    request_id: Option<String>,
+    accounts: Vec<String>,
+    /// 変更前の状態
+    #[schema(value_type = Option<Object>)]
+    before: Option<serde_json::Value>,
+    /// 変更後の状態
+    #[schema(value_type = Option<Object>)]
+    after: Option<serde_json::Value>,
+    #[serde(rename = "createdAt")]
+    #[schema(value_type = String, example = "2020/01/01 10:00")]
+    created_at: Timestamp,
+}
+/// 予約の確定・取り消し・全消去の記録を新しい順に返す
+#[utoipa::path(
+    context_path = "/v1",
+    operation_id = "listAuditEntries",
+    tag = "audit",
+    params(AuditQuery, TimeFormatParam),
+    responses(
+        (status = 200, body = [AuditEntryDto]),
+        (status = 400, description = "条件が正しくない"),
+    )
+)]
+#[get("/audit")]
+async fn index(
+    uc: web::Data<AuditUsecase>,
+    query_params: web::Query<AuditQuery>,
+    format: TimeFormat,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let query_params = query_params.into_inner();
+    let filter = AuditFilter::new(
+        query_params.actor,
+        query_params.account,
+        query_params
+            .action
+            .map(|x| x.parse::<AuditAction>())
+            .transpose()?,
+        query_params
+            .from
+            .map(|x| time_helper::parse_datetime(&x))
+            .transpose()?,
+        query_params
+            .to
+            .map(|x| time_helper::parse_datetime(&x))
+            .transpose()?,
+        query_params.limit.unwrap_or(DEFAULT_AUDIT_LIMIT),
+    );
+    let entries = uc
+        .search(principal.tenant(), filter)
+        .await?
+        .into_iter()
+        .map(|x| AuditEntryDto {
+            id: x.id,
+            actor: x.actor,
+            action: x.action.as_str(),
+            request_id: x.request_id,
+            accounts: x.accounts,
+            before: x.before,
+            after: x.after,
+            created_at: format.timestamp(x.created_at),
+        })
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        test::{call_service, init_service, read_body_json, TestRequest},
+        App,
+    };
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::{
+        controllers::auth::{authenticate, API_KEY_HEADER},
+        domains::{
+            audit::AuditEntry, auth::Authenticator, data_clients::audit_client::MockAuditClient,
+        },
+    };
+
+    #[actix_web::test]
+    async fn test_index() {
+        let mut mock = MockAuditClient::new();
+        mock.expect_fetch_audit_entries()
+            .withf(|tenant, filter| {
+                tenant == "default"
+                    && filter.action == Some(AuditAction::Cancel)
+                    && filter.limit == DEFAULT_AUDIT_LIMIT
+            })
+            .times(1)
+            .returning(|_, _| {
+                Ok(vec![AuditEntry {
+                    id: 1,
+                    actor: "test1@example.com".to_string(),
+                    action: AuditAction::Cancel,
+                    request_id: Some("req-1".to_string()),
+                    accounts: vec!["test1@example.com".to_string()],
+                    before: Some(serde_json::json!({"status": "confirmed"})),
+                    after: Some(serde_json::json!({"status": "cancelled"})),
+                    created_at: NaiveDateTime::parse_from_str(
+                        "2020-01-01 10:00:00",
+                        "%Y-%m-%d %H:%M:%S",
+                    )
+                    .unwrap(),
+                }])
+            });
+        let keys = Authenticator::parse_api_keys(
+            "admin@example.com:admin-key:admin,test1@example.com:key1",
+        )
+        .unwrap();
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Authenticator::new(keys, None)))
+                .app_data(web::Data::new(AuditUsecase::new(Arc::new(mock))))
+                .service(web::scope("/v1").wrap_fn(authenticate).service(index)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/v1/audit?action=cancel")
+            .insert_header((API_KEY_HEADER, "admin-key"));
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 200);
+        let body: serde_json::Value = read_body_json(res).await;
+        assert_eq!(
+            body,
+            serde_json::json!([{
+                "id": 1,
+                "actor": "test1@example.com",
+                "action": "cancel",
+                "requestId": "req-1",
+                "accounts": ["test1@example.com"],
+                "before": {"status": "confirmed"},
+                "after": {"status": "cancelled"},
+                "createdAt": "2020/01/01 10:00",
+            }])
+        );
+
+        let req = TestRequest::get()
+            .uri("/v1/audit?action=hold")
+            .insert_header((API_KEY_HEADER, "admin-key"));
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 400);
+
+        // 管理者しか見られない
+        let req = TestRequest::get()
+            .uri("/v1/audit")
+            .insert_header((API_KEY_HEADER, "key1"));
+        let res = call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), 403);
+    }
+}
@@ -0,0 +1,55 @@
+use actix_web::{get, web, HttpResponse};
+use itertools::Itertools;
+
+use crate::{
+    controllers::time_helper::{TimeFormat, TimeFormatParam, Timestamp},
+    domains::auth::Principal,
+    usecases::{data::DataUsecase, user_slots::UserSlotUsecase},
+};
+
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct SlotRangeDto {
+    #[schema(value_type = String, example = "2020/01/01 10:00")]
+    start: Timestamp,
+    #[schema(value_type = String, example = "2020/01/01 10:30")]
+    end: Timestamp,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct UserSlotsDto {
+    account: String,
+    slots: Vec<SlotRangeDto>,
+}
+/// 全ユーザの枠をアカウントごとに返す。動作確認用で、TEST_DATA_ROUTESを有効にした場合だけ登録する
+#[utoipa::path(
+    context_path = "/v1",
+    operation_id = "dumpData",
+    tag = "data",
+    params(TimeFormatParam),
+    responses((status = 200, body = [UserSlotsDto]))
+)]
+#[get("/data/dump")]
+async fn index(
+    uc: web::Data<DataUsecase>,
+    slot_uc: web::Data<UserSlotUsecase>,
+    format: TimeFormat,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let users = uc
+        .dump_user_slots(principal.tenant())
+        .await?
+        .into_iter()
+        .map(|us| UserSlotsDto {
+            account: us.account,
+            slots: us
+                .slots
+                .into_iter()
+                .map(|start| SlotRangeDto {
+                    start: format.timestamp(start),
+                    end: format.timestamp(slot_uc.slot(start).end_date()),
+                })
+                .collect_vec(),
+        })
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(users))
+}
@@ -0,0 +1,175 @@
+use actix_web::{get, post, web, HttpResponse};
+use chrono::NaiveDateTime;
+use itertools::Itertools;
+
+use crate::{
+    controllers::time_helper::{self, TimeFormat, TimeFormatParam, Timestamp},
+    domains::{audit::Actor, auth::Principal, resource::RoomRequirement, slot::Slot},
+    usecases::user_slots::UserSlotUsecase,
+};
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SlotQuery {
+    /// カンマ区切りのアカウント
+    #[param(example = "test1@example.com,test2@example.com")]
+    accounts: String,
+    #[serde(rename = "startTime", deserialize_with = "time_helper::deserialize")]
+    #[param(value_type = String, example = "2020/01/01 10:00")]
+    /// 旧形式、RFC 3339、ISO 8601のいずれか
+    start_time: NaiveDateTime,
+    #[serde(rename = "endTime", deserialize_with = "time_helper::deserialize")]
+    #[param(value_type = String, example = "2020/01/01 20:00")]
+    end_time: NaiveDateTime,
+    /// 指定した場合は、この人数以上入れる会議室が空いている枠だけを会議室と合わせて返す
+    #[serde(rename = "roomCapacity")]
+    room_capacity: Option<u32>,
+    /// 会議室に必要な設備をカンマ区切りで指定する
+    #[serde(rename = "roomAttributes")]
+    room_attributes: Option<String>,
+}
+/// 確定可能な枠
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct SlotDto {
+    #[schema(value_type = String, example = "2020/01/01 10:00")]
+    start: Timestamp,
+    #[schema(value_type = String, example = "2020/01/01 10:30")]
+    end: Timestamp,
+    attendees: Vec<String>,
+    /// 会議室の条件を指定した場合だけ返す
+    #[serde(skip_serializing_if = "Option::is_none")]
+    room: Option<String>,
+}
+impl SlotDto {
+    fn new(slot: &Slot, attendees: &[String], room: Option<String>, format: TimeFormat) -> Self {
+        Self {
+            start: format.timestamp(slot.start_date),
+            end: format.timestamp(slot.end_date()),
+            attendees: attendees.to_vec(),
+            room,
+        }
+    }
+}
+/// 全員が確定可能な枠を返す。会議室の条件を指定した場合は空いている会議室と合わせて返す
+#[utoipa::path(
+    context_path = "/v1",
+    operation_id = "listSlots",
+    tag = "slots",
+    params(SlotQuery, TimeFormatParam),
+    responses(
+        (status = 200, body = [SlotDto]),
+        (status = 400, description = "日時が正しくない"),
+    )
+)]
+#[get("/slots")]
+async fn index(
+    uc: web::Data<UserSlotUsecase>,
+    query_params: web::Query<SlotQuery>,
+    format: TimeFormat,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    let accounts = query_params
+        .accounts
+        .split(',')
+        .map(|x| x.to_string())
+        .collect_vec();
+    let (start_time, end_time) = (query_params.start_time, query_params.end_time);
+    if query_params.room_capacity.is_some() || query_params.room_attributes.is_some() {
+        // 人数の指定が無い場合は参加者全員が入れる会議室にする
+        let requirement = RoomRequirement::new(
+            query_params.room_capacity.unwrap_or(accounts.len() as u32),
+            query_params
+                .room_attributes
+                .iter()
+                .flat_map(|x| x.split(','))
+                .map(|x| x.to_string())
+                .collect_vec(),
+        );
+        let slots = uc
+            .fetch_confirmable_room_slots(
+                principal.tenant(),
+                &accounts,
+                start_time,
+                end_time,
+                &requirement,
+            )
+            .await?
+            .into_iter()
+            .map(|x| SlotDto::new(&x.slot, &accounts, Some(x.room.name), format))
+            .collect_vec();
+        return Ok(HttpResponse::Ok().json(slots));
+    }
+    let slots = uc
+        .fetch_confirmable_slots(principal.tenant(), &accounts, start_time, end_time)
+        .await?
+        .iter()
+        .map(|x| SlotDto::new(x, &accounts, None, format))
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(slots))
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct ConfirmRequest {
+    #[schema(example = json!(["test1@example.com", "test2@example.com"]))]
+    accounts: Vec<String>,
+    #[serde(rename = "startTime", deserialize_with = "time_helper::deserialize")]
+    /// 旧形式、RFC 3339、ISO 8601のいずれか
+    #[schema(value_type = String, example = "2020-01-01T10:00:00+09:00")]
+    start_time: NaiveDateTime,
+    /// 一緒に予約する会議室
+    room: Option<String>,
+}
+/// 確定した会議
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct MeetingDto {
+    id: u32,
+    #[schema(value_type = String, example = "2020/01/01 10:00")]
+    start: Timestamp,
+    #[schema(value_type = String, example = "2020/01/01 10:30")]
+    end: Timestamp,
+    attendees: Vec<String>,
+    room: Option<String>,
+}
+/// 参加者全員の枠を確定し、確定した会議を返す
+#[utoipa::path(
+    context_path = "/v1",
+    operation_id = "confirmSlot",
+    tag = "slots",
+    params(TimeFormatParam),
+    request_body = ConfirmRequest,
+    responses(
+        (status = 201, body = MeetingDto),
+        (status = 400, description = "日時が正しくない"),
+        (status = 403, description = "参加者に本人が含まれていない"),
+        (status = 409, description = "既に予定がある"),
+        (status = 422, description = "休日・不在期間、上限に達している、または会議室に入りきらない"),
+    )
+)]
+#[post("/confirm")]
+async fn post(
+    uc: web::Data<UserSlotUsecase>,
+    params: web::Json<ConfirmRequest>,
+    format: TimeFormat,
+    principal: Principal,
+    actor: Actor,
+) -> Result<HttpResponse, actix_web::Error> {
+    let params = params.into_inner();
+    principal.authorize_attendees(&params.accounts)?;
+    let id = uc
+        .confirm_users_slot(
+            principal.tenant(),
+            &actor,
+            &params.accounts,
+            params.start_time,
+            params.room.clone(),
+        )
+        .await?;
+    let slot = uc.slot(params.start_time);
+    Ok(HttpResponse::Created().json(MeetingDto {
+        id,
+        start: format.timestamp(slot.start_date),
+        end: format.timestamp(slot.end_date()),
+        attendees: params.accounts,
+        room: params.room,
+    }))
+}
@@ -0,0 +1,130 @@
+use actix_web::{get, post, web, HttpResponse};
+use itertools::Itertools;
+
+use crate::{
+    controllers::time_helper::{TimeFormat, TimeFormatParam},
+    domains::auth::Principal,
+    usecases::webhooks::WebhookUsecase,
+};
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct WebhookParam {
+    #[schema(example = "https://example.com/hook")]
+    url: String,
+    /// 署名に使う共有鍵
+    secret: String,
+    /// 空の場合は全てのイベントを受け取る
+    #[serde(default)]
+    #[schema(example = json!(["meeting.confirmed", "meeting.cancelled"]))]
+    events: Vec<String>,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct WebhookRes {
+    id: u32,
+    url: String,
+    events: Vec<String>,
+    active: bool,
+}
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct WebhookCreated {
+    id: u32,
+}
+/// 送信先を登録する。bodyの署名はX-Webhook-Signatureで送る
+#[utoipa::path(
+    operation_id = "createWebhook",
+    tag = "webhooks",
+    request_body = WebhookParam,
+    responses(
+        (status = 201, body = WebhookCreated),
+        (status = 400, description = "urlまたはeventsが正しくない"),
+    )
+)]
+#[post("/webhooks")]
+async fn post(
+    uc: web::Data<WebhookUsecase>,
+    params: web::Json<WebhookParam>,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let id = uc
+        .register(
+            principal.tenant(),
+            &params.url,
+            &params.secret,
+            &params.events,
+        )
+        .await?;
+    Ok(HttpResponse::Created().json(WebhookCreated { id }))
+}
+
+#[utoipa::path(
+    operation_id = "listWebhooks",
+    tag = "webhooks",
+    responses((status = 200, body = [WebhookRes]))
+)]
+#[get("/webhooks")]
+async fn index(
+    uc: web::Data<WebhookUsecase>,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let webhooks = uc
+        .fetch_all(principal.tenant())
+        .await?
+        .into_iter()
+        .map(|w| WebhookRes {
+            id: w.id,
+            url: w.url,
+            events: w.events,
+            active: w.active,
+        })
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(webhooks))
+}
+
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct DeliveryRes {
+    id: u32,
+    #[serde(rename = "eventType")]
+    event_type: String,
+    status: String,
+    attempts: u32,
+    #[serde(rename = "lastStatusCode")]
+    last_status_code: Option<u16>,
+    #[serde(rename = "lastError")]
+    last_error: Option<String>,
+    #[serde(rename = "createdAt")]
+    #[schema(example = "2020/01/01 10:00")]
+    created_at: String,
+}
+/// 新しいものから100件の配信履歴を返す
+#[utoipa::path(
+    operation_id = "listWebhookDeliveries",
+    tag = "webhooks",
+    params(("id" = u32, Path, description = "Webhookのid"), TimeFormatParam),
+    responses((status = 200, body = [DeliveryRes]))
+)]
+#[get("/webhooks/{id}/deliveries")]
+async fn deliveries(
+    uc: web::Data<WebhookUsecase>,
+    id: web::Path<u32>,
+    format: TimeFormat,
+    principal: Principal,
+) -> Result<HttpResponse, actix_web::Error> {
+    principal.authorize_admin()?;
+    let deliveries = uc
+        .fetch_deliveries(principal.tenant(), id.into_inner())
+        .await?
+        .into_iter()
+        .map(|d| DeliveryRes {
+            id: d.id,
+            event_type: d.event_type,
+            status: d.status,
+            attempts: d.attempts,
+            last_status_code: d.last_status_code,
+            last_error: d.last_error,
+            created_at: format.format(&d.created_at),
+        })
+        .collect_vec();
+    Ok(HttpResponse::Ok().json(deliveries))
+}
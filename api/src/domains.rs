@@ -1,4 +1,21 @@
+pub mod audit;
+pub mod auth;
+pub mod buffer;
+pub mod business_hours;
+pub mod calendar_import;
 pub mod data_clients;
+pub mod email;
 pub mod error;
+pub mod health;
+pub mod holiday;
+pub mod ics;
+pub mod idempotency;
+pub mod meeting;
+pub mod meeting_cap;
+pub mod out_of_office;
+pub mod outbox;
+pub mod resource;
 pub mod slot;
+pub mod slot_events;
 pub mod slot_range;
+pub mod webhook;
@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use derive_new::new;
+use serde_json::{json, Value};
+
+use super::{error::Error, meeting::MeetingStatus, slot::Slot};
+
+/// 一度に返す監査ログの既定の件数
+pub const DEFAULT_AUDIT_LIMIT: u32 = 100;
+/// 一度に返す監査ログの最大件数
+pub const MAX_AUDIT_LIMIT: u32 = 1000;
+
+/// 監査ログに残す予約の変更
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    Confirm,
+    Cancel,
+    Clear,
+}
+
+impl FromStr for AuditAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "confirm" => Ok(Self::Confirm),
+            "cancel" => Ok(Self::Cancel),
+            "clear" => Ok(Self::Clear),
+            _ => Err(Error::InvalidInput(format!("unknown audit action: {}", s))),
+        }
+    }
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Confirm => "confirm",
+            Self::Cancel => "cancel",
+            Self::Clear => "clear",
+        }
+    }
+}
+
+/// 変更を行った呼び出し元
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Actor {
+    pub account: String,
+    /// X-Request-Idで返したリクエストのID
+    pub request_id: Option<String>,
+}
+
+/// 監査ログの1件。追記するだけで更新も削除もしない
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub actor: String,
+    pub action: AuditAction,
+    pub request_id: Option<String>,
+    /// 変更で予定が変わったアカウント
+    pub accounts: Vec<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: NaiveDateTime,
+}
+
+/// 監査ログの絞り込み条件。指定しなかった条件では絞り込まない
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    /// 予定が変わったアカウントに含まれる
+    pub account: Option<String>,
+    pub action: Option<AuditAction>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub limit: u32,
+}
+
+impl AuditFilter {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.limit == 0 || self.limit > MAX_AUDIT_LIMIT {
+            return Err(Error::InvalidInput(format!(
+                "limit must be between 1 and {}",
+                MAX_AUDIT_LIMIT
+            )));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(Error::InvalidInput("from must be before to".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 監査ログのbefore, afterに残す会議の状態
+pub fn meeting_state(id: u32, slot: &Slot, status: MeetingStatus, room: Option<&str>) -> Value {
+    json!({
+        "meetingId": id,
+        "start": slot.start_date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        "end": slot.end_date().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        "status": status.as_str(),
+        "room": room,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let filter = |limit, from, to| AuditFilter::new(None, None, None, from, to, limit);
+        assert!(filter(DEFAULT_AUDIT_LIMIT, None, None).validate().is_ok());
+        assert!(filter(0, None, None).validate().is_err());
+        assert!(filter(MAX_AUDIT_LIMIT + 1, None, None).validate().is_err());
+        let (from, to) = (
+            to_date("2020-01-02 00:00:00"),
+            to_date("2020-01-01 00:00:00"),
+        );
+        assert!(matches!(
+            filter(DEFAULT_AUDIT_LIMIT, Some(from), Some(to)).validate(),
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(filter(DEFAULT_AUDIT_LIMIT, Some(to), Some(from))
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_meeting_state() {
+        let slot = Slot::new(to_date("2020-01-01 10:00:00"));
+        let state = meeting_state(1, &slot, MeetingStatus::Confirmed, Some("room1"));
+        assert_eq!(
+            state,
+            json!({
+                "meetingId": 1,
+                "start": "2020-01-01T10:00:00",
+                "end": "2020-01-01T10:30:00",
+                "status": "confirmed",
+                "room": "room1",
+            })
+        );
+        assert_eq!(
+            "cancel".parse::<AuditAction>().unwrap(),
+            AuditAction::Cancel
+        );
+        assert!("hold".parse::<AuditAction>().is_err());
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+
+use derive_new::new;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use sha2::{Digest, Sha256};
+
+use super::error::Error;
+
+/// テナント内の全てのアカウントの操作と、管理用のAPIを許可するスコープ
+pub const ADMIN_SCOPE: &str = "admin";
+
+/// テナントに紐付いていない認証情報で使うテナント
+pub const DEFAULT_TENANT: &str = "default";
+
+/// 認証した呼び出し元
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Principal {
+    pub account: String,
+    pub scopes: Vec<String>,
+    /// 認証情報に紐付いたテナント。認証後は操作の対象のテナントになる
+    #[new(default)]
+    tenant: Option<String>,
+}
+
+impl Principal {
+    pub fn with_tenant(mut self, tenant: Option<String>) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    pub fn tenant(&self) -> &str {
+        self.tenant.as_deref().unwrap_or(DEFAULT_TENANT)
+    }
+
+    /// 操作の対象のテナントを決める。テナントに紐付いた認証情報は他のテナントを選べない。
+    /// 紐付いていない場合は既定のテナントになり、管理者だけがrequestedで任意のテナントを選べる
+    pub fn select_tenant(self, requested: Option<&str>) -> Result<Self, Error> {
+        let tenant = match (self.tenant.as_deref(), requested) {
+            (Some(tenant), None) => tenant.to_string(),
+            (Some(tenant), Some(requested)) if tenant == requested => tenant.to_string(),
+            (None, None) => DEFAULT_TENANT.to_string(),
+            (None, Some(requested)) if self.is_admin() || requested == DEFAULT_TENANT => {
+                requested.to_string()
+            }
+            (_, Some(requested)) => {
+                return Err(Error::Forbidden(format!(
+                    "{} cannot access tenant {}",
+                    self.account, requested
+                )))
+            }
+        };
+        Ok(self.with_tenant(Some(tenant)))
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.scopes.iter().any(|x| x == ADMIN_SCOPE)
+    }
+
+    /// 管理者でなければ拒否する
+    pub fn authorize_admin(&self) -> Result<(), Error> {
+        if self.is_admin() {
+            return Ok(());
+        }
+        Err(Error::Forbidden(format!(
+            "{} requires {} scope",
+            self.account, ADMIN_SCOPE
+        )))
+    }
+
+    /// 本人か管理者でなければ拒否する
+    pub fn authorize_account(&self, account: &str) -> Result<(), Error> {
+        if self.is_admin() || self.account == account {
+            return Ok(());
+        }
+        Err(Error::Forbidden(format!(
+            "{} cannot act for {}",
+            self.account, account
+        )))
+    }
+
+    /// 参加者に本人が含まれているか、管理者でなければ拒否する
+    pub fn authorize_attendees(&self, accounts: &[String]) -> Result<(), Error> {
+        if self.is_admin() || accounts.contains(&self.account) {
+            return Ok(());
+        }
+        Err(Error::Forbidden(format!(
+            "{} is not an attendee",
+            self.account
+        )))
+    }
+}
+
+/// JWTのclaims。scopeはOAuthと同じ空白区切り
+#[derive(Debug, serde::Deserialize)]
+struct Claims {
+    sub: String,
+    #[serde(default)]
+    scope: String,
+    tenant: Option<String>,
+}
+
+/// ローカルに設定した鍵でJWTを検証する
+pub struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtVerifier {
+    /// HS256の共有鍵で検証する
+    pub fn from_secret(secret: &[u8]) -> Self {
+        Self::new(DecodingKey::from_secret(secret), Algorithm::HS256)
+    }
+
+    /// RS256の公開鍵(PEM)で検証する
+    pub fn from_rsa_pem(pem: &[u8]) -> Result<Self, Error> {
+        let key = DecodingKey::from_rsa_pem(pem)
+            .map_err(|e| Error::InvalidInput(format!("invalid jwt public key: {}", e)))?;
+        Ok(Self::new(key, Algorithm::RS256))
+    }
+
+    fn new(key: DecodingKey, algorithm: Algorithm) -> Self {
+        // expは必須にする
+        let validation = Validation::new(algorithm);
+        Self { key, validation }
+    }
+
+    /// issとaudを指定した場合は一致するものだけ受け付ける
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.validation.set_issuer(&[issuer]);
+        self
+    }
+
+    pub fn with_audience(mut self, audience: &str) -> Self {
+        self.validation.set_audience(&[audience]);
+        self
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Principal, Error> {
+        let claims = decode::<Claims>(token, &self.key, &self.validation)
+            .map_err(|e| Error::Unauthorized(format!("invalid token: {}", e)))?
+            .claims;
+        let scopes = claims
+            .scope
+            .split_whitespace()
+            .map(|x| x.to_string())
+            .collect();
+        Ok(Principal::new(claims.sub, scopes).with_tenant(claims.tenant))
+    }
+}
+
+/// APIキーかJWTで呼び出し元を認証する
+pub struct Authenticator {
+    /// APIキーのSHA-256をキーにする
+    api_keys: HashMap<String, Principal>,
+    jwt: Option<JwtVerifier>,
+}
+
+impl Authenticator {
+    pub fn new(api_keys: Vec<(String, Principal)>, jwt: Option<JwtVerifier>) -> Self {
+        let api_keys = api_keys
+            .into_iter()
+            .map(|(key, principal)| (digest(&key), principal))
+            .collect();
+        Self { api_keys, jwt }
+    }
+
+    /// `[tenant/]account:key[:scope scope]`をカンマ区切りで並べたAPIキーの設定を読む
+    pub fn parse_api_keys(text: &str) -> Result<Vec<(String, Principal)>, Error> {
+        text.split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(|entry| {
+                let mut fields = entry.splitn(3, ':');
+                let owner = fields.next().unwrap_or_default();
+                let (tenant, account) = match owner.split_once('/') {
+                    Some((tenant, account)) => (Some(tenant.to_string()), account),
+                    None => (None, owner),
+                };
+                let key = fields.next().unwrap_or_default();
+                if account.is_empty() || key.is_empty() {
+                    return Err(Error::InvalidInput(format!(
+                        "invalid api key entry for {}",
+                        account
+                    )));
+                }
+                let scopes = fields
+                    .next()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|x| x.to_string())
+                    .collect();
+                let principal = Principal::new(account.to_string(), scopes).with_tenant(tenant);
+                Ok((key.to_string(), principal))
+            })
+            .collect()
+    }
+
+    /// X-API-KeyヘッダのAPIキーか、Authorizationヘッダのbearerトークンを検証し、
+    /// X-Tenantヘッダで指定されたテナントを選ぶ
+    pub fn authenticate(
+        &self,
+        api_key: Option<&str>,
+        authorization: Option<&str>,
+        tenant: Option<&str>,
+    ) -> Result<Principal, Error> {
+        self.verify(api_key, authorization)?.select_tenant(tenant)
+    }
+
+    fn verify(
+        &self,
+        api_key: Option<&str>,
+        authorization: Option<&str>,
+    ) -> Result<Principal, Error> {
+        if let Some(api_key) = api_key {
+            return self
+                .api_keys
+                .get(&digest(api_key))
+                .cloned()
+                .ok_or_else(|| Error::Unauthorized("unknown api key".to_string()));
+        }
+        let Some(authorization) = authorization else {
+            return Err(Error::Unauthorized("missing credentials".to_string()));
+        };
+        let Some(token) = authorization
+            .strip_prefix("Bearer ")
+            .or_else(|| authorization.strip_prefix("bearer "))
+        else {
+            return Err(Error::Unauthorized("unsupported authorization".to_string()));
+        };
+        match &self.jwt {
+            Some(jwt) => jwt.verify(token.trim()),
+            None => Err(Error::Unauthorized("jwt is not enabled".to_string())),
+        }
+    }
+}
+
+/// 長さや一致した位置で比較時間が変わらないよう、APIキーはハッシュで照合する
+fn digest(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    use super::*;
+
+    fn token(secret: &[u8], claims: serde_json::Value) -> String {
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_authorize() {
+        let user = Principal::new("test1@example.com".to_string(), vec![]);
+        let admin = Principal::new("admin@example.com".to_string(), vec!["admin".to_string()]);
+        let attendees = vec![
+            "test1@example.com".to_string(),
+            "test2@example.com".to_string(),
+        ];
+        assert!(user.authorize_attendees(&attendees).is_ok());
+        assert!(admin.authorize_attendees(&attendees).is_ok());
+        assert!(matches!(
+            user.authorize_attendees(&attendees[1..]),
+            Err(Error::Forbidden(_))
+        ));
+        assert!(user.authorize_account("test1@example.com").is_ok());
+        assert!(user.authorize_account("test2@example.com").is_err());
+        assert!(user.authorize_admin().is_err());
+        assert!(admin.authorize_admin().is_ok());
+    }
+
+    #[test]
+    fn test_authenticate_api_key() {
+        let keys =
+            Authenticator::parse_api_keys("test1@example.com:key1, admin@example.com:key2:admin")
+                .unwrap();
+        let auth = Authenticator::new(keys, None);
+        let principal = auth.authenticate(Some("key1"), None, None).unwrap();
+        assert_eq!(principal.account, "test1@example.com");
+        assert_eq!(principal.tenant(), DEFAULT_TENANT);
+        assert!(auth
+            .authenticate(Some("key2"), None, None)
+            .unwrap()
+            .is_admin());
+        assert!(matches!(
+            auth.authenticate(Some("key3"), None, None),
+            Err(Error::Unauthorized(_))
+        ));
+        assert!(matches!(
+            auth.authenticate(None, None, None),
+            Err(Error::Unauthorized(_))
+        ));
+        assert!(Authenticator::parse_api_keys("test1@example.com").is_err());
+    }
+
+    #[test]
+    fn test_select_tenant() {
+        let keys = Authenticator::parse_api_keys(
+            "acme/test1@example.com:key1, test2@example.com:key2, admin@example.com:key3:admin",
+        )
+        .unwrap();
+        let auth = Authenticator::new(keys, None);
+
+        // 紐付いたテナントだけを使える
+        let principal = auth.authenticate(Some("key1"), None, None).unwrap();
+        assert_eq!(principal.account, "test1@example.com");
+        assert_eq!(principal.tenant(), "acme");
+        assert!(auth.authenticate(Some("key1"), None, Some("acme")).is_ok());
+        assert!(matches!(
+            auth.authenticate(Some("key1"), None, Some("default")),
+            Err(Error::Forbidden(_))
+        ));
+
+        // 紐付いていない場合は管理者だけがテナントを選べる
+        assert!(matches!(
+            auth.authenticate(Some("key2"), None, Some("acme")),
+            Err(Error::Forbidden(_))
+        ));
+        let principal = auth.authenticate(Some("key3"), None, Some("acme")).unwrap();
+        assert_eq!(principal.tenant(), "acme");
+    }
+
+    #[test]
+    fn test_authenticate_jwt() {
+        let auth = Authenticator::new(
+            vec![],
+            Some(JwtVerifier::from_secret(b"secret").with_issuer("suzuki")),
+        );
+        let exp = chrono::Utc::now().timestamp() + 60;
+        let valid = token(
+            b"secret",
+            serde_json::json!({"sub": "test1@example.com", "scope": "read admin", "tenant": "acme", "iss": "suzuki", "exp": exp}),
+        );
+        let principal = auth
+            .authenticate(None, Some(&format!("Bearer {}", valid)), None)
+            .unwrap();
+        assert_eq!(principal.account, "test1@example.com");
+        assert!(principal.is_admin());
+        assert_eq!(principal.tenant(), "acme");
+
+        let forged = token(
+            b"other",
+            serde_json::json!({"sub": "test1@example.com", "iss": "suzuki", "exp": exp}),
+        );
+        let expired = token(
+            b"secret",
+            serde_json::json!({"sub": "test1@example.com", "iss": "suzuki", "exp": exp - 3600}),
+        );
+        let other_issuer = token(
+            b"secret",
+            serde_json::json!({"sub": "test1@example.com", "iss": "other", "exp": exp}),
+        );
+        for token in [forged, expired, other_issuer] {
+            assert!(matches!(
+                auth.authenticate(None, Some(&format!("Bearer {}", token)), None),
+                Err(Error::Unauthorized(_))
+            ));
+        }
+        assert!(auth
+            .authenticate(None, Some("Basic dXNlcjpwYXNz"), None)
+            .is_err());
+    }
+}
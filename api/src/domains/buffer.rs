@@ -0,0 +1,50 @@
+use chrono::Duration;
+use derive_new::new;
+
+use super::{slot::Slot, slot_range::SlotRange};
+
+/// 会議の前後に確保する移動・準備時間
+#[derive(Debug, Clone, Copy, new, PartialEq)]
+pub struct Buffer {
+    pub before: Duration,
+    pub after: Duration,
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::new(Duration::zero(), Duration::zero())
+    }
+}
+
+impl Buffer {
+    pub fn from_minutes(before: i64, after: i64) -> Self {
+        Self::new(Duration::minutes(before), Duration::minutes(after))
+    }
+    /// 前後のバッファを含めた、slotが占有する時間帯を返す
+    pub fn around(&self, slot: &Slot) -> SlotRange {
+        SlotRange::new(slot.start_date - self.before, slot.end_date() + self.after)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_around() {
+        let slot = Slot::new(to_date("2020-01-01 10:00:00"));
+        assert_eq!(
+            Buffer::from_minutes(10, 15).around(&slot),
+            SlotRange::new(
+                to_date("2020-01-01 09:50:00"),
+                to_date("2020-01-01 10:45:00")
+            )
+        );
+        assert_eq!(Buffer::default().around(&slot), SlotRange::from(slot));
+    }
+}
@@ -0,0 +1,65 @@
+use chrono::NaiveTime;
+use derive_new::new;
+
+use super::{error::Error, slot::Slot};
+
+/// 会議を入れられる時間帯。日をまたぐ時間帯は扱わない
+#[derive(Debug, Clone, Copy, new, PartialEq)]
+pub struct BusinessHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl BusinessHours {
+    /// "09:00"のような形式の始業・終業時刻を読む
+    pub fn parse(start: &str, end: &str) -> Result<Self, Error> {
+        let parse = |x: &str| {
+            NaiveTime::parse_from_str(x, "%H:%M")
+                .map_err(|_| Error::InvalidInput(format!("invalid time: {}", x)))
+        };
+        let hours = Self::new(parse(start)?, parse(end)?);
+        if hours.start >= hours.end {
+            return Err(Error::InvalidInput(format!(
+                "business hours must start before they end: {}-{}",
+                start, end
+            )));
+        }
+        Ok(hours)
+    }
+    /// 枠が始業から終業までに収まっているかどうか
+    pub fn contains(&self, slot: &Slot) -> bool {
+        let end = slot.end_date();
+        end.date() == slot.start_date.date()
+            && self.start <= slot.start_date.time()
+            && end.time() <= self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    fn to_slot(date: &str) -> Slot {
+        Slot::new(NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap())
+    }
+
+    #[test]
+    fn test_contains() {
+        let hours = BusinessHours::parse("09:00", "18:00").unwrap();
+        assert!(hours.contains(&to_slot("2020-01-01 09:00:00")));
+        assert!(hours.contains(&to_slot("2020-01-01 17:30:00")));
+        assert!(!hours.contains(&to_slot("2020-01-01 08:30:00")));
+        assert!(!hours.contains(&to_slot("2020-01-01 18:00:00")));
+
+        let hours = BusinessHours::parse("00:00", "23:59").unwrap();
+        assert!(!hours.contains(&to_slot("2020-01-01 23:30:00")));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(BusinessHours::parse("18:00", "09:00").is_err());
+        assert!(BusinessHours::parse("9", "18:00").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::Duration;
+use derive_new::new;
+use itertools::Itertools;
+
+use super::{
+    error::Error,
+    ics::{self, Component, DateValue},
+    slot::Slot,
+    slot_range::SlotRange,
+};
+
+/// 取り込まなかった予定
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct SkippedEvent {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkipReason {
+    /// 終日の予定
+    AllDay,
+    /// 開始・終了が枠の区切りに乗っていない
+    Unaligned,
+    /// DTENDが無い
+    NoEnd,
+    /// TZIDのタイムゾーンが分からない
+    UnknownZone,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AllDay => "allDay",
+            Self::Unaligned => "unaligned",
+            Self::NoEnd => "noEnd",
+            Self::UnknownZone => "unknownZone",
+        }
+    }
+}
+
+/// ICSから読み込んだ空き時間と予定の入っている時間
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CalendarImport {
+    /// VAVAILABILITYのAVAILABLEの枠
+    pub free: Vec<Slot>,
+    /// VEVENTの枠
+    pub busy: Vec<Slot>,
+    pub skipped: Vec<SkippedEvent>,
+}
+
+/// ICSをslot_durationの長さの枠に変換する。繰り返し(RRULE)は展開しない
+pub fn import_from_ics(text: &str, slot_duration: Duration) -> Result<CalendarImport, Error> {
+    let calendars = ics::parse(text)?;
+    let mut import = CalendarImport::default();
+    for calendar in &calendars {
+        for event in calendar.find_all("VEVENT") {
+            match to_slot_range(event, slot_duration)? {
+                Ok(range) => import.busy.extend(range.to_slots(slot_duration)),
+                Err(skipped) => import.skipped.push(skipped),
+            }
+        }
+        for available in calendar
+            .find_all("VAVAILABILITY")
+            .into_iter()
+            .flat_map(|a| a.find_all("AVAILABLE"))
+        {
+            match to_slot_range(available, slot_duration)? {
+                Ok(range) => import.free.extend(range.to_slots(slot_duration)),
+                Err(skipped) => import.skipped.push(skipped),
+            }
+        }
+    }
+    import.free = import
+        .free
+        .into_iter()
+        .sorted_by_key(|s| s.start_date)
+        .dedup()
+        .collect_vec();
+    import.busy = import
+        .busy
+        .into_iter()
+        .sorted_by_key(|s| s.start_date)
+        .dedup()
+        .collect_vec();
+    Ok(import)
+}
+
+/// 取り込めない予定の場合はErr(SkippedEvent)を返す
+fn to_slot_range(
+    component: &Component,
+    slot_duration: Duration,
+) -> Result<Result<SlotRange, SkippedEvent>, Error> {
+    let skip = |reason| {
+        Err(SkippedEvent::new(
+            component.property("UID").map(|p| p.value.clone()),
+            component
+                .property("SUMMARY")
+                .map(|p| p.text())
+                .unwrap_or_default(),
+            reason,
+        ))
+    };
+    let Some(start) = component.property("DTSTART") else {
+        return Err(Error::InvalidInput(format!(
+            "{} without DTSTART",
+            component.name
+        )));
+    };
+    let Some(end) = component.property("DTEND") else {
+        return Ok(skip(SkipReason::NoEnd));
+    };
+    if !start.is_zone_known() || !end.is_zone_known() {
+        return Ok(skip(SkipReason::UnknownZone));
+    }
+    match (start.date_value()?, end.date_value()?) {
+        (DateValue::DateTime(start), DateValue::DateTime(end)) => {
+            if !Slot::is_on_grid(&start, slot_duration) || !Slot::is_on_grid(&end, slot_duration) {
+                return Ok(skip(SkipReason::Unaligned));
+            }
+            Ok(Ok(SlotRange::new(start, end)))
+        }
+        _ => Ok(skip(SkipReason::AllDay)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_import_from_ics() {
+        let text = "BEGIN:VCALENDAR\n\
+                    BEGIN:VEVENT\n\
+                    UID:busy-1\n\
+                    DTSTART:20200101T100000\n\
+                    DTEND:20200101T110000\n\
+                    SUMMARY:Standup\n\
+                    END:VEVENT\n\
+                    BEGIN:VEVENT\n\
+                    UID:all-day\n\
+                    DTSTART;VALUE=DATE:20200102\n\
+                    DTEND;VALUE=DATE:20200103\n\
+                    SUMMARY:Offsite\n\
+                    END:VEVENT\n\
+                    BEGIN:VEVENT\n\
+                    UID:unaligned\n\
+                    DTSTART:20200101T131500\n\
+                    DTEND:20200101T134500\n\
+                    SUMMARY:Call\n\
+                    END:VEVENT\n\
+                    BEGIN:VAVAILABILITY\n\
+                    BEGIN:AVAILABLE\n\
+                    DTSTART:20200101T140000\n\
+                    DTEND:20200101T150000\n\
+                    END:AVAILABLE\n\
+                    END:VAVAILABILITY\n\
+                    END:VCALENDAR\n";
+        let import = import_from_ics(text, Slot::default_duration()).unwrap();
+        assert_eq!(
+            import.busy,
+            vec![
+                Slot::new(to_date("2020-01-01 10:00:00")),
+                Slot::new(to_date("2020-01-01 10:30:00")),
+            ]
+        );
+        assert_eq!(
+            import.free,
+            vec![
+                Slot::new(to_date("2020-01-01 14:00:00")),
+                Slot::new(to_date("2020-01-01 14:30:00")),
+            ]
+        );
+        assert_eq!(
+            import.skipped,
+            vec![
+                SkippedEvent::new(
+                    Some("all-day".to_string()),
+                    "Offsite".to_string(),
+                    SkipReason::AllDay
+                ),
+                SkippedEvent::new(
+                    Some("unaligned".to_string()),
+                    "Call".to_string(),
+                    SkipReason::Unaligned
+                ),
+            ]
+        );
+        // 同じ内容を読み込めば同じ結果になる
+        assert_eq!(
+            import_from_ics(text, Slot::default_duration()).unwrap(),
+            import
+        );
+    }
+    #[test]
+    fn test_import_from_ics_with_time_zone() {
+        use chrono::{Local, TimeZone, Utc};
+
+        let text = "BEGIN:VCALENDAR\n\
+                    BEGIN:VEVENT\n\
+                    UID:utc\n\
+                    DTSTART:20200101T010000Z\n\
+                    DTEND:20200101T013000Z\n\
+                    SUMMARY:Sync\n\
+                    END:VEVENT\n\
+                    BEGIN:VEVENT\n\
+                    UID:windows\n\
+                    DTSTART;TZID=Tokyo Standard Time:20200101T100000\n\
+                    DTEND;TZID=Tokyo Standard Time:20200101T110000\n\
+                    SUMMARY:Review\n\
+                    END:VEVENT\n\
+                    END:VCALENDAR\n";
+        let import = import_from_ics(text, Slot::default_duration()).unwrap();
+        // UTCの時刻はローカル時刻の枠になる
+        let start = Utc
+            .from_utc_datetime(&to_date("2020-01-01 01:00:00"))
+            .with_timezone(&Local)
+            .naive_local();
+        assert_eq!(import.busy, vec![Slot::new(start)]);
+        assert_eq!(
+            import.skipped,
+            vec![SkippedEvent::new(
+                Some("windows".to_string()),
+                "Review".to_string(),
+                SkipReason::UnknownZone
+            )]
+        );
+    }
+}
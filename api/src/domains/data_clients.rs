@@ -1,2 +1,13 @@
+pub mod audit_client;
+pub mod calendar_client;
+pub mod email_client;
+pub mod health_client;
+pub mod holiday_client;
+pub mod idempotency_client;
+pub mod meeting_client;
+pub mod out_of_office_client;
+pub mod outbox_client;
+pub mod resource_client;
 pub mod test_client;
 pub mod user_slot_client;
+pub mod webhook_client;
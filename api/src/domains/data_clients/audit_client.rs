@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{
+    audit::{AuditEntry, AuditFilter},
+    error::Error,
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait AuditClient: Send + Sync {
+    /// 条件に合う監査ログを新しい順に返す
+    async fn fetch_audit_entries(
+        &self,
+        tenant: &str,
+        filter: &AuditFilter,
+    ) -> Result<Vec<AuditEntry>, Error>;
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{error::Error, slot::Slot};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait CalendarClient: Send + Sync {
+    /// 前回ICSから取り込んだ空き時間・予定をfree, busyで置き換える
+    async fn replace_imported_slots(
+        &self,
+        tenant: &str,
+        account: &str,
+        free: &[Slot],
+        busy: &[Slot],
+    ) -> Result<(), Error>;
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{
+    email::{Email, PendingEmail},
+    error::Error,
+    webhook::DeliveryOutcome,
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait EmailClient: Send + Sync {
+    async fn enqueue_emails(&self, emails: &[Email]) -> Result<(), Error>;
+
+    /// 送信予定時刻を過ぎたメールを古いものから返す
+    async fn fetch_due_emails(&self, limit: u32) -> Result<Vec<PendingEmail>, Error>;
+
+    async fn record_attempt(&self, id: u32, outcome: &DeliveryOutcome) -> Result<(), Error>;
+}
+
+/// SMTPサーバにメールを渡す
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// 受け付けられた場合はSMTPの応答コードを返す
+    async fn send(&self, email: &Email) -> Result<u16, String>;
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{
+    error::Error,
+    health::{MigrationStatus, PoolStatus},
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait HealthClient: Send + Sync {
+    /// DBに接続できることを確認する
+    async fn ping(&self) -> Result<(), Error>;
+    async fn fetch_migration_status(&self) -> Result<MigrationStatus, Error>;
+    fn pool_status(&self) -> PoolStatus;
+}
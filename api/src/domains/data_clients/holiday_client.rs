@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use derive_new::new;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{error::Error, holiday::Holiday, slot::Slot};
+
+/// ユーザに適用される(全社共通とユーザの地域の)休日
+#[derive(Debug, new)]
+pub struct UserHolidays {
+    pub account: String,
+    pub dates: Vec<NaiveDate>,
+}
+
+impl UserHolidays {
+    pub fn includes(&self, slot: &Slot) -> bool {
+        self.dates.contains(&slot.start_date.date())
+    }
+}
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait HolidayClient: Send + Sync {
+    /// calendarの休日をholidaysで置き換える。regionがNoneのカレンダーは全社共通になる
+    async fn import_holidays(
+        &self,
+        tenant: &str,
+        calendar: &str,
+        region: Option<String>,
+        holidays: &[Holiday],
+    ) -> Result<(), Error>;
+}
@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{
+    error::Error,
+    idempotency::{IdempotencyRecord, StoredResponse},
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait IdempotencyClient: Send + Sync {
+    /// キーを処理中として登録する。登録済みの場合は登録済みの内容を返す。
+    /// 期限の切れたキーと、処理中のままIDEMPOTENCY_LEASE_SECONDSを過ぎたキーは登録されていないものとして扱う
+    async fn reserve_key(
+        &self,
+        tenant: &str,
+        account: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<Option<IdempotencyRecord>, Error>;
+
+    /// 処理中のキーにレスポンスを保存する
+    async fn save_response(
+        &self,
+        tenant: &str,
+        account: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), Error>;
+
+    /// 処理中のキーを消し、同じキーで再送できるようにする
+    async fn release_key(&self, tenant: &str, account: &str, key: &str) -> Result<(), Error>;
+}
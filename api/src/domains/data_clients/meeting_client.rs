@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use chrono::Duration;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{audit::Actor, error::Error, meeting::Meeting};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait MeetingClient: Send + Sync {
+    /// accountが参加している会議を、取り消されたものも含めて開始順に返す。会議の長さはslot_duration
+    async fn fetch_user_meetings(
+        &self,
+        tenant: &str,
+        account: &str,
+        slot_duration: Duration,
+    ) -> Result<Vec<Meeting>, Error>;
+
+    /// 会議の参加者を返す。会議が無い場合はNotFound
+    async fn fetch_meeting_attendees(&self, tenant: &str, id: u32) -> Result<Vec<String>, Error>;
+
+    /// 会議を取り消し、参加者と会議室の枠を空ける。監査ログとイベントの会議の長さはslot_duration
+    async fn cancel_meeting(
+        &self,
+        tenant: &str,
+        actor: &Actor,
+        id: u32,
+        slot_duration: Duration,
+    ) -> Result<(), Error>;
+}
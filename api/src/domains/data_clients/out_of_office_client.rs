@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use derive_new::new;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{error::Error, out_of_office::OutOfOffice, slot::Slot};
+
+#[derive(Debug, new)]
+pub struct UserOutOfOffices {
+    pub account: String,
+    pub periods: Vec<OutOfOffice>,
+}
+
+impl UserOutOfOffices {
+    /// slotと重なる不在期間を返す
+    pub fn find_overlap(&self, slot: &Slot) -> Option<&OutOfOffice> {
+        self.periods.iter().find(|ooo| ooo.overlaps(slot))
+    }
+}
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait OutOfOfficeClient: Send + Sync {
+    async fn register_out_of_office(
+        &self,
+        tenant: &str,
+        account: &str,
+        out_of_office: &OutOfOffice,
+    ) -> Result<(), Error>;
+
+    /// DBの現在時刻の時点でまだ終わっていない不在期間を開始順に返す
+    async fn fetch_upcoming_out_of_offices(
+        &self,
+        tenant: &str,
+        account: &str,
+    ) -> Result<Vec<OutOfOffice>, Error>;
+}
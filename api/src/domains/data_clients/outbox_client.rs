@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{
+    error::Error,
+    outbox::{OutboxCursor, OutboxEvent},
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait OutboxClient: Send + Sync {
+    /// sinkの進み具合をOUTBOX_LEASE_SECONDSの間確保する。他のプロセスが確保している場合はNone。
+    /// 初めてのsinkは、全てのsinkに配信済みのイベントの続きから配信する
+    async fn claim_cursor(&self, sink: &str) -> Result<Option<OutboxCursor>, Error>;
+
+    /// after_idより後のイベントを古いものから返す
+    async fn fetch_unpublished(&self, after_id: u64, limit: u32)
+        -> Result<Vec<OutboxEvent>, Error>;
+
+    /// 進み具合を保存して確保を解く。確保している間に期限が切れ、他のプロセスが確保した場合は何もしない
+    async fn release_cursor(&self, cursor: &OutboxCursor) -> Result<(), Error>;
+
+    /// last_idまでのイベントを全てのsinkに配信済みにする
+    async fn mark_published(&self, last_id: u64) -> Result<(), Error>;
+}
+
+/// イベントの配信先。同じイベントが2回以上届くことがあるので、受け取る側で冪等に扱う
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), Error>;
+}
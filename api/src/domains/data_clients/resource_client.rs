@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use derive_new::new;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{error::Error, resource::Resource, slot::Slot};
+
+/// リソースと、その期間に入っている予約
+#[derive(Debug, new)]
+pub struct ResourceBookings {
+    pub resource: Resource,
+    pub booked: Vec<NaiveDateTime>,
+}
+
+impl ResourceBookings {
+    /// 入っている予約もslotと同じ長さとして、重ならないかどうか
+    pub fn is_free(&self, slot: &Slot) -> bool {
+        !self
+            .booked
+            .iter()
+            .any(|b| *b < slot.end_date() && slot.start_date < *b + slot.duration)
+    }
+}
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ResourceClient: Send + Sync {
+    async fn create_resource(&self, tenant: &str, resource: &Resource) -> Result<(), Error>;
+
+    async fn fetch_resources(&self, tenant: &str) -> Result<Vec<Resource>, Error>;
+}
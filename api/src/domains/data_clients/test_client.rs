@@ -2,10 +2,12 @@ use std::collections::HashMap;
 
 use async_trait::async_trait;
 
-use crate::domains::error::Error;
+use crate::domains::{audit::Actor, data_clients::user_slot_client::UserSlots, error::Error};
 
 #[async_trait]
-pub trait TestClient {
-    async fn dump_data(&self) -> Result<HashMap<String, String>, Error>;
-    async fn clear_data(&self) -> Result<(), Error>;
+pub trait TestClient: Send + Sync {
+    async fn dump_data(&self, tenant: &str) -> Result<HashMap<String, String>, Error>;
+    /// 全ユーザの枠をアカウントごとに返す
+    async fn dump_user_slots(&self, tenant: &str) -> Result<Vec<UserSlots>, Error>;
+    async fn clear_data(&self, tenant: &str, actor: &Actor) -> Result<(), Error>;
 }
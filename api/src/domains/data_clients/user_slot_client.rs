@@ -1,30 +1,104 @@
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDateTime};
 use derive_new::new;
 #[cfg(test)]
 use mockall::automock;
 
-use crate::domains::error::Error;
+use crate::domains::{
+    audit::Actor,
+    buffer::Buffer,
+    data_clients::{
+        holiday_client::UserHolidays, out_of_office_client::UserOutOfOffices,
+        resource_client::ResourceBookings,
+    },
+    error::Error,
+    meeting_cap::MeetingCap,
+    slot::Slot,
+};
 
 #[derive(Debug, new)]
 pub struct UserSlots {
     pub account: String,
     pub slots: Vec<chrono::NaiveDateTime>,
+    /// ユーザ個別の設定が無い項目は全体の設定で補われる
+    #[new(default)]
+    pub buffer: Buffer,
+}
+
+/// 上限が設定されているユーザの上限と、上限を数える期間に入っている予定
+#[derive(Debug, new)]
+pub struct UserCaps {
+    pub account: String,
+    pub caps: Vec<MeetingCap>,
+    pub booked: Vec<NaiveDateTime>,
+}
+
+impl UserCaps {
+    /// slotを追加しても全ての上限に収まるかどうか
+    pub fn allows(&self, slot: &Slot) -> bool {
+        self.caps.iter().all(|cap| cap.allows(&self.booked, slot))
+    }
 }
 
 #[cfg_attr(test, automock)]
 #[async_trait]
-pub trait UserSlotClient {
+pub trait UserSlotClient: Send + Sync {
+    /// 取り込んだ予定とslot_durationの長さで重なる枠は除く
     async fn fetch_user_slots(
         &self,
+        tenant: &str,
         accounts: &[String],
         start_time: NaiveDateTime,
         end_time: NaiveDateTime,
+        slot_duration: Duration,
+        default_buffer: &Buffer,
     ) -> Result<Vec<UserSlots>, Error>;
 
-    async fn confirm_user_slots(
+    /// start_time..end_timeの予定を数えるのに必要な期間分の予定を含めて返す
+    async fn fetch_user_caps(
+        &self,
+        tenant: &str,
+        accounts: &[String],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Result<Vec<UserCaps>, Error>;
+
+    async fn fetch_user_holidays(
+        &self,
+        tenant: &str,
+        accounts: &[String],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Result<Vec<UserHolidays>, Error>;
+
+    async fn fetch_user_out_of_offices(
         &self,
+        tenant: &str,
         accounts: &[String],
         start_time: NaiveDateTime,
-    ) -> Result<(), Error>;
+        end_time: NaiveDateTime,
+    ) -> Result<Vec<UserOutOfOffices>, Error>;
+
+    /// 条件の絞り込みに使うため、収容人数がmin_capacity以上の会議室を予約と合わせて返す。
+    /// slot_durationの長さでstart_time..end_timeの枠と重なりうる予約を含める
+    async fn fetch_room_bookings(
+        &self,
+        tenant: &str,
+        min_capacity: u32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        slot_duration: Duration,
+    ) -> Result<Vec<ResourceBookings>, Error>;
+
+    /// slotに会議を登録してidを返す。roomを指定した場合は、参加者と同じトランザクションで会議室も予約する。
+    /// 会議室に参加者が入れない場合はUnavailable
+    async fn confirm_user_slots(
+        &self,
+        tenant: &str,
+        actor: &Actor,
+        accounts: &[String],
+        slot: &Slot,
+        default_buffer: &Buffer,
+        room: Option<String>,
+    ) -> Result<u32, Error>;
 }
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::domains::{
+    error::Error,
+    webhook::{Delivery, DeliveryOutcome, PendingDelivery, Webhook},
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait WebhookClient: Send + Sync {
+    /// eventsが空の場合は全てのイベントを受け取る
+    async fn register_webhook(
+        &self,
+        tenant: &str,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> Result<u32, Error>;
+
+    async fn fetch_webhooks(&self, tenant: &str) -> Result<Vec<Webhook>, Error>;
+
+    /// 新しいものから順に配信履歴を返す
+    async fn fetch_deliveries(&self, tenant: &str, webhook_id: u32)
+        -> Result<Vec<Delivery>, Error>;
+
+    /// 送信予定時刻を過ぎた配信を古いものから返す。返した配信はDELIVERY_LEASE_SECONDSの間、
+    /// 結果を記録するまで他のプロセスには返さない
+    async fn fetch_due_deliveries(&self, limit: u32) -> Result<Vec<PendingDelivery>, Error>;
+
+    async fn record_attempt(&self, id: u32, outcome: &DeliveryOutcome) -> Result<(), Error>;
+
+    /// イベントを購読している有効なWebhookそれぞれに配信を積む
+    async fn enqueue_event(
+        &self,
+        tenant: &str,
+        event_type: &str,
+        payload: &str,
+    ) -> Result<(), Error>;
+}
+
+/// Webhookの送信先にHTTPでPOSTする
+#[cfg_attr(test, automock)]
+#[async_trait(?Send)]
+pub trait WebhookSender {
+    /// 送信できた場合はレスポンスのステータスコードを返す
+    async fn send(
+        &self,
+        url: &str,
+        body: &str,
+        timestamp: i64,
+        signature: &str,
+    ) -> Result<u16, String>;
+}
@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use derive_new::new;
+use itertools::Itertools;
+
+use super::{
+    error::Error,
+    meeting::{meeting_to_itip, Meeting, MeetingStatus},
+    webhook::{retry_or_fail, DeliveryOutcome},
+};
+
+const DEFAULT_CONFIRMED: &str = "Subject: Invitation: meeting on {{startTime}}
+
+You have been invited to a meeting.
+
+When: {{startTime}} - {{endTime}}
+Where: {{room}}
+Attendees: {{attendees}}
+";
+
+const DEFAULT_CANCELLED: &str = "Subject: Cancelled: meeting on {{startTime}}
+
+The following meeting has been cancelled.
+
+When: {{startTime}} - {{endTime}}
+Where: {{room}}
+Attendees: {{attendees}}
+";
+
+/// メールのテンプレート。`{{startTime}}`のような変数を会議の内容で置き換える。
+/// 使える変数はmeetingId, startTime, endTime, room, attendees, recipient
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct EmailTemplate {
+    pub subject: String,
+    pub body: String,
+}
+
+impl EmailTemplate {
+    /// 1行目が`Subject: `で始まり、空行の後が本文のテキストを読む
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let text = text.replace("\r\n", "\n");
+        let (header, body) = text.split_once('\n').unwrap_or((&text, ""));
+        let subject = header
+            .strip_prefix("Subject:")
+            .ok_or_else(|| Error::InvalidInput("template must start with Subject:".to_string()))?;
+        Ok(Self::new(
+            subject.trim().to_string(),
+            body.trim_start_matches('\n').to_string(),
+        ))
+    }
+
+    pub fn render(&self, meeting: &Meeting, recipient: &str) -> (String, String) {
+        let format = "%Y/%m/%d %H:%M";
+        let vars = [
+            ("meetingId", meeting.id.to_string()),
+            (
+                "startTime",
+                meeting.slot.start_date.format(format).to_string(),
+            ),
+            (
+                "endTime",
+                meeting.slot.end_date().format(format).to_string(),
+            ),
+            ("room", meeting.room.clone().unwrap_or("-".to_string())),
+            ("attendees", meeting.attendees.iter().join(", ")),
+            ("recipient", recipient.to_string()),
+        ];
+        let render = |text: &str| {
+            vars.iter().fold(text.to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{{{}}}}}", name), value)
+            })
+        };
+        (render(&self.subject), render(&self.body))
+    }
+}
+
+/// 確定と取り消しのテンプレート
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailTemplates {
+    pub confirmed: EmailTemplate,
+    pub cancelled: EmailTemplate,
+}
+
+impl Default for EmailTemplates {
+    fn default() -> Self {
+        Self {
+            confirmed: EmailTemplate::parse(DEFAULT_CONFIRMED).unwrap(),
+            cancelled: EmailTemplate::parse(DEFAULT_CANCELLED).unwrap(),
+        }
+    }
+}
+
+impl EmailTemplates {
+    /// dirにmeeting.confirmed.txt、meeting.cancelled.txtがあればそれを使い、なければ既定のテンプレートを使う
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let load =
+            |name: &str, default: EmailTemplate| match std::fs::read_to_string(dir.join(name)) {
+                Ok(text) => EmailTemplate::parse(&text),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(default),
+                Err(e) => Err(Error::InvalidInput(format!("{}: {}", name, e))),
+            };
+        let default = Self::default();
+        Ok(Self {
+            confirmed: load("meeting.confirmed.txt", default.confirmed)?,
+            cancelled: load("meeting.cancelled.txt", default.cancelled)?,
+        })
+    }
+}
+
+/// 送信するメール。icsはiTIP形式の添付ファイル、methodはそのMETHOD
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Email {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub ics: String,
+    pub method: String,
+}
+
+/// 参加者ごとの招待メールを作る。取り消しも同じUIDのICSを添付するので、受信側の予定が更新される
+pub fn invitations(meeting: &Meeting, templates: &EmailTemplates, organizer: &str) -> Vec<Email> {
+    let (template, method) = match meeting.status {
+        MeetingStatus::Confirmed => (&templates.confirmed, "REQUEST"),
+        MeetingStatus::Cancelled => (&templates.cancelled, "CANCEL"),
+    };
+    let ics = meeting_to_itip(meeting, organizer);
+    meeting
+        .attendees
+        .iter()
+        .map(|recipient| {
+            let (subject, body) = template.render(meeting, recipient);
+            Email::new(
+                recipient.clone(),
+                subject,
+                body,
+                ics.clone(),
+                method.to_string(),
+            )
+        })
+        .collect_vec()
+}
+
+/// 送信待ちのメール
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct PendingEmail {
+    pub id: u32,
+    pub email: Email,
+    /// これまでに試行した回数
+    pub attempts: u32,
+}
+
+impl PendingEmail {
+    /// SMTPサーバに受け付けられたら成功。それ以外は試行回数が上限に達するまで間隔を空けて再送する
+    pub fn outcome(&self, result: Result<u16, String>) -> DeliveryOutcome {
+        match result {
+            Ok(code) => DeliveryOutcome::Succeeded { status_code: code },
+            Err(error) => retry_or_fail(self.attempts, None, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::domains::slot::Slot;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_parse_template() {
+        let template =
+            EmailTemplate::parse("Subject: Hello {{recipient}}\r\n\r\nbody\r\n").unwrap();
+        assert_eq!(template.subject, "Hello {{recipient}}");
+        assert_eq!(template.body, "body\n");
+        assert!(EmailTemplate::parse("Hello\n\nbody").is_err());
+    }
+
+    #[test]
+    fn test_invitations() {
+        let meeting = Meeting::new(
+            1,
+            Slot::new(to_date("2020-01-01 10:00:00")),
+            MeetingStatus::Confirmed,
+            vec![
+                "test1@example.com".to_string(),
+                "test2@example.com".to_string(),
+            ],
+            Some("room-a".to_string()),
+            to_date("2019-12-31 01:00:00"),
+        );
+        let templates = EmailTemplates {
+            confirmed: EmailTemplate::new(
+                "Meeting {{meetingId}}".to_string(),
+                "Hi {{recipient}}, {{startTime}}-{{endTime}} at {{room}} with {{attendees}}"
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        let emails = invitations(&meeting, &templates, "noreply@example.com");
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[1].recipient, "test2@example.com");
+        assert_eq!(emails[1].subject, "Meeting 1");
+        assert_eq!(
+            emails[1].body,
+            "Hi test2@example.com, 2020/01/01 10:00-2020/01/01 10:30 at room-a with test1@example.com, test2@example.com"
+        );
+        assert_eq!(emails[1].method, "REQUEST");
+        assert!(emails[1].ics.contains("UID:meeting-1@actix-web-sample"));
+
+        let cancelled = Meeting {
+            status: MeetingStatus::Cancelled,
+            ..meeting
+        };
+        let emails = invitations(&cancelled, &templates, "noreply@example.com");
+        assert_eq!(emails[0].method, "CANCEL");
+        assert!(emails[0].subject.starts_with("Cancelled: "));
+        assert!(emails[0].ics.contains("UID:meeting-1@actix-web-sample"));
+    }
+}
@@ -1,7 +1,49 @@
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("DbError: {0}")]
     DbError(String),
     #[error("already slot exist.")]
     Conflicts,
+    #[error("meeting limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("unavailable: {0}")]
+    Unavailable(String),
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("PublishError: {0}")]
+    PublishError(String),
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("idempotency key reused: {0}")]
+    KeyReused(String),
+    #[error("in progress: {0}")]
+    InProgress(String),
+    /// デッドロックでトランザクションが中断された。やり直せば成功することがある
+    #[error("deadlock: {0}")]
+    Deadlock(String),
+}
+
+impl Error {
+    /// メトリクスのラベルに使う種類
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::DbError(_) => "db_error",
+            Error::Conflicts => "conflicts",
+            Error::LimitExceeded(_) => "limit_exceeded",
+            Error::Unavailable(_) => "unavailable",
+            Error::InvalidInput(_) => "invalid_input",
+            Error::NotFound(_) => "not_found",
+            Error::PublishError(_) => "publish_error",
+            Error::Unauthorized(_) => "unauthorized",
+            Error::Forbidden(_) => "forbidden",
+            Error::KeyReused(_) => "key_reused",
+            Error::InProgress(_) => "in_progress",
+            Error::Deadlock(_) => "deadlock",
+        }
+    }
 }
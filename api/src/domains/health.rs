@@ -0,0 +1,109 @@
+use derive_new::new;
+
+/// コネクションプールの使用状況
+#[derive(Debug, Clone, Copy, new, PartialEq)]
+pub struct PoolStatus {
+    /// 開いている接続の数
+    pub size: u32,
+    /// 開いている接続のうち使われていないもの
+    pub idle: u32,
+    pub max_connections: u32,
+}
+
+impl PoolStatus {
+    pub fn in_use(&self) -> u32 {
+        self.size.saturating_sub(self.idle)
+    }
+    /// 最大接続数のうち使用中の割合。1に近いほど接続を待つリクエストが増える
+    pub fn saturation(&self) -> f64 {
+        if self.max_connections == 0 {
+            return 0.0;
+        }
+        self.in_use() as f64 / self.max_connections as f64
+    }
+}
+
+/// スキーマの変更の適用状況
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationStatus {
+    /// 適用の記録が無い。以前のdb/ddl.sqlで作ったスキーマ
+    Unmanaged,
+    /// 最後に適用したバージョン。dirtyの場合は途中で失敗している
+    Applied { version: i64, dirty: bool },
+}
+
+impl MigrationStatus {
+    /// requiredまで適用し終えていて、途中で失敗していないか
+    pub fn is_ready(&self, required: Option<i64>) -> bool {
+        match self {
+            MigrationStatus::Unmanaged => required.is_none(),
+            MigrationStatus::Applied { version, dirty } => {
+                !dirty && !matches!(required, Some(required) if *version < required)
+            }
+        }
+    }
+}
+
+/// トラフィックを受けられるかどうかの確認結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Readiness {
+    /// DBに接続できなかった場合はその理由
+    pub database: Result<(), String>,
+    /// DBに接続できなかった場合は確認できないのでNone
+    pub migrations: Option<MigrationStatus>,
+    /// このバージョンまで適用されていなければトラフィックを受けない
+    pub required_migration: Option<i64>,
+    pub pool: PoolStatus,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.database.is_ok()
+            && matches!(&self.migrations, Some(m) if m.is_ready(self.required_migration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saturation() {
+        assert_eq!(PoolStatus::new(4, 1, 10).saturation(), 0.3);
+        assert_eq!(PoolStatus::new(10, 0, 10).saturation(), 1.0);
+        assert_eq!(PoolStatus::new(0, 0, 0).saturation(), 0.0);
+    }
+
+    #[test]
+    fn test_is_ready() {
+        let pool = PoolStatus::new(1, 1, 10);
+        let readiness = |database, migrations| Readiness {
+            database,
+            migrations,
+            required_migration: None,
+            pool,
+        };
+        assert!(readiness(Ok(()), Some(MigrationStatus::Unmanaged)).is_ready());
+        let applied = |dirty| MigrationStatus::Applied { version: 1, dirty };
+        assert!(readiness(Ok(()), Some(applied(false))).is_ready());
+        assert!(!readiness(Ok(()), Some(applied(true))).is_ready());
+        assert!(!readiness(Err("refused".to_string()), None).is_ready());
+
+        // 埋め込んだバージョンまで適用されていなければ受けない
+        let required = |migrations| Readiness {
+            required_migration: Some(2),
+            ..readiness(Ok(()), Some(migrations))
+        };
+        assert!(!required(MigrationStatus::Unmanaged).is_ready());
+        assert!(!required(MigrationStatus::Applied {
+            version: 1,
+            dirty: false
+        })
+        .is_ready());
+        assert!(required(MigrationStatus::Applied {
+            version: 2,
+            dirty: false
+        })
+        .is_ready());
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{Duration, NaiveDate};
+use derive_new::new;
+use itertools::Itertools;
+
+use super::{error::Error, ics};
+
+/// 予定を入れられない祝日・休業日
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+}
+
+/// ICSのVEVENTを休日として読み込む。DTENDはその日を含まない終了日として扱う
+pub fn holidays_from_ics(text: &str) -> Result<Vec<Holiday>, Error> {
+    let calendars = ics::parse(text)?;
+    let mut holidays = vec![];
+    for event in calendars.iter().flat_map(|c| c.find_all("VEVENT")) {
+        let Some(start) = event.property("DTSTART") else {
+            return Err(Error::InvalidInput("VEVENT without DTSTART".to_string()));
+        };
+        let start = start.date_value()?.date();
+        let end = match event.property("DTEND") {
+            Some(end) => end.date_value()?.date(),
+            None => start + Duration::days(1),
+        };
+        let name = event
+            .property("SUMMARY")
+            .map(|p| p.text())
+            .unwrap_or_default();
+        let mut date = start;
+        // 1日の予定でDTENDが同じ日になっているものも1日分として扱う
+        while date < end || date == start {
+            holidays.push(Holiday::new(date, name.clone()));
+            date += Duration::days(1);
+        }
+    }
+    Ok(holidays
+        .into_iter()
+        .sorted_by_key(|h| h.date)
+        .dedup_by(|a, b| a.date == b.date)
+        .collect_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn to_date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_holidays_from_ics() {
+        let text = "BEGIN:VCALENDAR\n\
+                    BEGIN:VEVENT\n\
+                    DTSTART;VALUE=DATE:20200102\n\
+                    DTEND;VALUE=DATE:20200104\n\
+                    SUMMARY:Shutdown\n\
+                    END:VEVENT\n\
+                    BEGIN:VEVENT\n\
+                    DTSTART;VALUE=DATE:20200101\n\
+                    SUMMARY:New Year's Day\n\
+                    END:VEVENT\n\
+                    BEGIN:VEVENT\n\
+                    DTSTART;VALUE=DATE:20200103\n\
+                    SUMMARY:Duplicated\n\
+                    END:VEVENT\n\
+                    END:VCALENDAR\n";
+        assert_eq!(
+            holidays_from_ics(text).unwrap(),
+            vec![
+                Holiday::new(to_date("2020-01-01"), "New Year's Day".to_string()),
+                Holiday::new(to_date("2020-01-02"), "Shutdown".to_string()),
+                Holiday::new(to_date("2020-01-03"), "Shutdown".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt;
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use derive_new::new;
+
+use super::error::Error;
+
+/// iCalendarのプロパティ。例えば`DTSTART;VALUE=DATE:20240101`は
+/// name=DTSTART, params=[(VALUE, DATE)], value=20240101になる
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+/// BEGINからENDまでのまとまり(VCALENDAR, VEVENTなど)
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+/// DTSTARTなどの日付または日時の値
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateValue {
+    /// 終日の予定
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl DateValue {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            Self::Date(date) => *date,
+            Self::DateTime(date_time) => date_time.date(),
+        }
+    }
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    /// 日付・日時として値を読む。UTC(末尾のZ)やTZIDの付いた日時はサーバのローカル時刻にし、
+    /// どちらも無い(floating)日時はそのままの時刻として扱う
+    pub fn date_value(&self) -> Result<DateValue, Error> {
+        let (value, utc) = match self.value.strip_suffix('Z') {
+            Some(value) => (value, true),
+            None => (self.value.as_str(), false),
+        };
+        if self.param("VALUE") == Some("DATE") || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(DateValue::Date)
+                .map_err(|_| invalid_value(self));
+        }
+        let date_time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .map_err(|_| invalid_value(self))?;
+        if utc {
+            return Ok(DateValue::DateTime(to_local(&Utc, &date_time)));
+        }
+        match self.time_zone()? {
+            Some(tz) => Ok(DateValue::DateTime(to_local(&tz, &date_time))),
+            None => Ok(DateValue::DateTime(date_time)),
+        }
+    }
+    /// TZIDが指定されていて、そのタイムゾーンが分からない場合はfalse
+    pub fn is_zone_known(&self) -> bool {
+        self.time_zone().is_ok()
+    }
+    fn time_zone(&self) -> Result<Option<Tz>, Error> {
+        self.param("TZID")
+            .map(|tzid| {
+                // TZID=/Europe/Berlinのように、先頭に/が付いていることがある
+                tzid.trim_start_matches('/')
+                    .parse::<Tz>()
+                    .map_err(|_| Error::InvalidInput(format!("unknown TZID: {}", tzid)))
+            })
+            .transpose()
+    }
+    /// TEXT型の値のエスケープを戻す
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        let mut chars = self.value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') | Some('N') => text.push('\n'),
+                Some(c) => text.push(c),
+                None => {}
+            }
+        }
+        text
+    }
+}
+
+impl Property {
+    /// TEXT型の値をエスケープしてプロパティを作る
+    pub fn text_value(name: &str, text: &str) -> Self {
+        let value = text
+            .replace('\\', "\\\\")
+            .replace(';', "\\;")
+            .replace(',', "\\,")
+            .replace('\n', "\\n");
+        Self::new(name.to_string(), vec![], value)
+    }
+    /// 日時の値でプロパティを作る。タイムゾーンを付けない(floating)時刻になる
+    pub fn date_time_value(name: &str, date_time: &NaiveDateTime) -> Self {
+        Self::new(
+            name.to_string(),
+            vec![],
+            date_time.format("%Y%m%dT%H%M%S").to_string(),
+        )
+    }
+}
+
+impl fmt::Display for Property {
+    /// 1行75オクテットを超える場合は折り返す
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            if value.contains([':', ';', ',']) {
+                line.push_str(&format!(";{}=\"{}\"", key, value));
+            } else {
+                line.push_str(&format!(";{}={}", key, value));
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+
+        let mut octets = 0;
+        for c in line.chars() {
+            if octets + c.len_utf8() > 75 {
+                f.write_str("\r\n ")?;
+                octets = 1;
+            }
+            write!(f, "{}", c)?;
+            octets += c.len_utf8();
+        }
+        f.write_str("\r\n")
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BEGIN:{}\r\n", self.name)?;
+        for property in &self.properties {
+            write!(f, "{}", property)?;
+        }
+        for component in &self.components {
+            write!(f, "{}", component)?;
+        }
+        write!(f, "END:{}\r\n", self.name)
+    }
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+    /// 子孫も含めて指定した名前のコンポーネントを返す
+    pub fn find_all<'a>(&'a self, name: &str) -> Vec<&'a Component> {
+        self.components
+            .iter()
+            .flat_map(|c| {
+                let mut found = c.find_all(name);
+                if c.name.eq_ignore_ascii_case(name) {
+                    found.insert(0, c);
+                }
+                found
+            })
+            .collect()
+    }
+}
+
+/// タイムゾーンでの日時をサーバのローカル時刻にする
+fn to_local<T: TimeZone>(tz: &T, date_time: &NaiveDateTime) -> NaiveDateTime {
+    let date_time = tz
+        .from_local_datetime(date_time)
+        .earliest()
+        .unwrap_or_else(|| {
+            // 夏時間の開始で存在しない時刻は、切り替わる前のオフセットで解釈する(RFC 5545 3.3.5)
+            tz.from_local_datetime(&(*date_time - Duration::hours(1)))
+                .earliest()
+                .map(|x| x + Duration::hours(1))
+                .unwrap_or_else(|| tz.from_utc_datetime(date_time))
+        });
+    date_time.with_timezone(&Local).naive_local()
+}
+
+fn invalid_value(property: &Property) -> Error {
+    Error::InvalidInput(format!(
+        "invalid {} value: {}",
+        property.name, property.value
+    ))
+}
+
+/// iCalendar形式の文字列を読み込み、最上位のコンポーネント(通常はVCALENDAR)を返す
+pub fn parse(text: &str) -> Result<Vec<Component>, Error> {
+    let mut roots = vec![];
+    let mut stack: Vec<Component> = vec![];
+    for line in unfold(text) {
+        let property = parse_line(&line)?;
+        match property.name.to_ascii_uppercase().as_str() {
+            "BEGIN" => stack.push(Component::new(property.value, vec![], vec![])),
+            "END" => {
+                let component = stack
+                    .pop()
+                    .filter(|c| c.name.eq_ignore_ascii_case(&property.value))
+                    .ok_or_else(|| {
+                        Error::InvalidInput(format!("unexpected END:{}", property.value))
+                    })?;
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None => roots.push(component),
+                }
+            }
+            _ => {
+                let Some(current) = stack.last_mut() else {
+                    return Err(Error::InvalidInput(format!(
+                        "property outside of component: {}",
+                        property.name
+                    )));
+                };
+                current.properties.push(property);
+            }
+        }
+    }
+    if let Some(component) = stack.pop() {
+        return Err(Error::InvalidInput(format!(
+            "missing END:{}",
+            component.name
+        )));
+    }
+    Ok(roots)
+}
+
+/// 空白で始まる行を前の行に繋げる
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_line(line: &str) -> Result<Property, Error> {
+    // パラメータの値は"で囲まれていれば:や;を含められる
+    let mut in_quote = false;
+    let mut separators = vec![];
+    let mut value_start = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quote = !in_quote,
+            ';' if !in_quote => separators.push(i),
+            ':' if !in_quote => {
+                value_start = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let Some(value_start) = value_start else {
+        return Err(Error::InvalidInput(format!("invalid line: {}", line)));
+    };
+    let name = &line[..separators.first().copied().unwrap_or(value_start)];
+    let params = separators
+        .iter()
+        .enumerate()
+        .map(|(n, start)| {
+            let end = separators.get(n + 1).copied().unwrap_or(value_start);
+            let param = &line[start + 1..end];
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            (key.to_string(), value.trim_matches('"').to_string())
+        })
+        .collect();
+    Ok(Property::new(
+        name.to_string(),
+        params,
+        line[value_start + 1..].to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "BEGIN:VCALENDAR\r\n\
+                    VERSION:2.0\r\n\
+                    BEGIN:VEVENT\r\n\
+                    DTSTART;VALUE=DATE:20200101\r\n\
+                    SUMMARY:New Year\\,\r\n  Day\r\n\
+                    ATTENDEE;CN=\"Suzuki: A\":mailto:test1@example.com\r\n\
+                    END:VEVENT\r\n\
+                    END:VCALENDAR\r\n";
+        let calendars = parse(text).unwrap();
+        assert_eq!(calendars.len(), 1);
+        let events = calendars[0].find_all("VEVENT");
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].property("DTSTART").unwrap().date_value().unwrap(),
+            DateValue::Date(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())
+        );
+        assert_eq!(
+            events[0].property("SUMMARY").unwrap().text(),
+            "New Year, Day"
+        );
+        let attendee = events[0].property("ATTENDEE").unwrap();
+        assert_eq!(attendee.param("CN"), Some("Suzuki: A"));
+        assert_eq!(attendee.value, "mailto:test1@example.com");
+    }
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("BEGIN:VCALENDAR\nBEGIN:VEVENT\nEND:VCALENDAR\n").is_err());
+        assert!(parse("BEGIN:VCALENDAR\n").is_err());
+        assert!(parse("SUMMARY:test\n").is_err());
+    }
+    #[test]
+    fn test_to_string() {
+        let mut event = Component::new("VEVENT".to_string(), vec![], vec![]);
+        event
+            .properties
+            .push(Property::text_value("SUMMARY", "Meeting; with, a\\b"));
+        event.properties.push(Property::new(
+            "ATTENDEE".to_string(),
+            vec![("CN".to_string(), "Suzuki: A".to_string())],
+            "mailto:test1@example.com".to_string(),
+        ));
+        event
+            .properties
+            .push(Property::text_value("DESCRIPTION", &"a".repeat(80)));
+        let calendar = Component::new("VCALENDAR".to_string(), vec![], vec![event]);
+        let text = calendar.to_string();
+        assert!(text.contains("SUMMARY:Meeting\\; with\\, a\\\\b\r\n"));
+        assert!(text.contains("ATTENDEE;CN=\"Suzuki: A\":mailto:test1@example.com\r\n"));
+        assert!(text.lines().all(|line| line.len() <= 75));
+
+        // 書き出したものを読み込むと元に戻る
+        assert_eq!(parse(&text).unwrap(), vec![calendar]);
+    }
+    #[test]
+    fn test_date_value() {
+        let expected = NaiveDate::from_ymd_opt(2020, 1, 1)
+            .and_then(|x| x.and_hms_opt(10, 30, 0))
+            .unwrap();
+        let floating = Property::new("DTSTART".to_string(), vec![], "20200101T103000".to_string());
+        assert_eq!(
+            floating.date_value().unwrap(),
+            DateValue::DateTime(expected)
+        );
+
+        // UTCやTZIDの日時はローカル時刻にする
+        let utc = Property::new(
+            "DTSTART".to_string(),
+            vec![],
+            "20200101T103000Z".to_string(),
+        );
+        assert_eq!(
+            utc.date_value().unwrap(),
+            DateValue::DateTime(
+                Utc.from_utc_datetime(&expected)
+                    .with_timezone(&Local)
+                    .naive_local()
+            )
+        );
+        let tokyo = Property::new(
+            "DTSTART".to_string(),
+            vec![("TZID".to_string(), "Asia/Tokyo".to_string())],
+            "20200101T193000".to_string(),
+        );
+        assert!(tokyo.is_zone_known());
+        assert_eq!(tokyo.date_value().unwrap(), utc.date_value().unwrap());
+        let unknown = Property::new(
+            "DTSTART".to_string(),
+            vec![("TZID".to_string(), "Tokyo Standard Time".to_string())],
+            "20200101T193000".to_string(),
+        );
+        assert!(!unknown.is_zone_known());
+        assert!(unknown.date_value().is_err());
+
+        let invalid = Property::new("DTSTART".to_string(), vec![], "2020-01-01".to_string());
+        assert!(invalid.date_value().is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use derive_new::new;
+use sha2::{Digest, Sha256};
+
+use super::error::Error;
+
+/// 冪等キーを覚えておく時間
+pub const IDEMPOTENCY_TTL_HOURS: i64 = 24;
+/// 処理中のキーを、最初のリクエストが終わっていないとみなす時間。
+/// レスポンスを保存できなかった場合やプロセスが落ちた場合も、これを過ぎれば同じキーで処理し直せる
+pub const IDEMPOTENCY_LEASE_SECONDS: i64 = 60;
+/// 冪等キーの最大長
+const MAX_KEY_LENGTH: usize = 255;
+
+/// 冪等キーを付けたリクエストに返したレスポンス
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl StoredResponse {
+    /// サーバのエラーは保存せず、同じキーで再送できるようにする
+    pub fn is_replayable(&self) -> bool {
+        self.status < 500
+    }
+}
+
+/// 保存済みの冪等キー。処理中の場合はresponseが無い
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct IdempotencyRecord {
+    pub request_hash: String,
+    pub response: Option<StoredResponse>,
+}
+
+impl IdempotencyRecord {
+    /// 同じキーで再送されたリクエストに、最初のレスポンスを返す。
+    /// 内容の違うリクエストにキーを使い回した場合と、最初のリクエストが処理中の場合はエラー
+    pub fn replay(&self, request_hash: &str) -> Result<StoredResponse, Error> {
+        if self.request_hash != request_hash {
+            return Err(Error::KeyReused(
+                "the key was used for a different request".to_string(),
+            ));
+        }
+        self.response.clone().ok_or_else(|| {
+            Error::InProgress("a request with the same key is being processed".to_string())
+        })
+    }
+}
+
+pub fn validate_key(key: &str) -> Result<(), Error> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.chars().all(|c| c.is_ascii_graphic()) {
+        return Err(Error::InvalidInput(format!(
+            "idempotency key must be 1 to {} visible ASCII characters",
+            MAX_KEY_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// 同じキーで同じリクエストが送られたかを比べるためのハッシュ
+pub fn request_hash(method: &str, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b" ");
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let hash = request_hash("POST", "/v1/confirm", b"{}");
+        assert_ne!(hash, request_hash("POST", "/v1/confirm", b"{ }"));
+        assert_ne!(hash, request_hash("POST", "/confirm", b"{}"));
+
+        let response = StoredResponse::new(201, None, b"{}".to_vec());
+        let record = IdempotencyRecord::new(hash.clone(), Some(response.clone()));
+        assert_eq!(record.replay(&hash).unwrap(), response);
+        assert!(matches!(
+            record.replay(&request_hash("POST", "/v1/confirm", b"{ }")),
+            Err(Error::KeyReused(_))
+        ));
+
+        let record = IdempotencyRecord::new(hash.clone(), None);
+        assert!(matches!(record.replay(&hash), Err(Error::InProgress(_))));
+    }
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("7f1c2a4e-8b5d-4c8e-9f00-1a2b3c4d5e6f").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("has space").is_err());
+        assert!(validate_key(&"a".repeat(MAX_KEY_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use derive_new::new;
+
+use super::{
+    error::Error,
+    ics::{Component, Property},
+    slot::Slot,
+    webhook::{EventType, MeetingEvent},
+};
+
+/// ICSのUIDなどに使うドメイン
+const UID_DOMAIN: &str = "actix-web-sample";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeetingStatus {
+    Confirmed,
+    Cancelled,
+}
+
+impl FromStr for MeetingStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "confirmed" => Ok(Self::Confirmed),
+            "cancelled" => Ok(Self::Cancelled),
+            _ => Err(Error::DbError(format!("unknown meeting status: {}", s))),
+        }
+    }
+}
+
+impl MeetingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Confirmed => "confirmed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// /confirmで確定した会議
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Meeting {
+    pub id: u32,
+    pub slot: Slot,
+    pub status: MeetingStatus,
+    pub attendees: Vec<String>,
+    pub room: Option<String>,
+    /// 最後に更新された日時(UTC)
+    pub updated_at: NaiveDateTime,
+}
+
+impl Meeting {
+    /// イベントが発生した時点の会議。updated_atにはイベントの発生日時を入れる
+    pub fn from_event(event: &MeetingEvent, updated_at: NaiveDateTime) -> Self {
+        let status = match event.event_type {
+            EventType::MeetingConfirmed => MeetingStatus::Confirmed,
+            EventType::MeetingCancelled => MeetingStatus::Cancelled,
+        };
+        Self::new(
+            event.meeting_id,
+            event.slot.clone(),
+            status,
+            event.attendees.clone(),
+            event.room.clone(),
+            updated_at,
+        )
+    }
+
+    /// 変わらないidから作るので、何度書き出しても同じUIDになる
+    pub fn uid(&self) -> String {
+        format!("meeting-{}@{}", self.id, UID_DOMAIN)
+    }
+
+    pub fn to_vevent(&self) -> Component {
+        let (status, sequence) = match self.status {
+            MeetingStatus::Confirmed => ("CONFIRMED", "0"),
+            // 取り消しは更新として扱われるようにSEQUENCEを上げる
+            MeetingStatus::Cancelled => ("CANCELLED", "1"),
+        };
+        let mut properties = vec![
+            Property::new("UID".to_string(), vec![], self.uid()),
+            Property::new(
+                "DTSTAMP".to_string(),
+                vec![],
+                self.updated_at.format("%Y%m%dT%H%M%SZ").to_string(),
+            ),
+            Property::date_time_value("DTSTART", &self.slot.start_date),
+            Property::date_time_value("DTEND", &self.slot.end_date()),
+            Property::text_value("SUMMARY", "Meeting"),
+            Property::new("STATUS".to_string(), vec![], status.to_string()),
+            Property::new("SEQUENCE".to_string(), vec![], sequence.to_string()),
+        ];
+        if let Some(room) = &self.room {
+            properties.push(Property::text_value("LOCATION", room));
+        }
+        properties.extend(self.attendees.iter().map(|attendee| {
+            Property::new(
+                "ATTENDEE".to_string(),
+                vec![("CN".to_string(), attendee.clone())],
+                format!("mailto:{}", attendee),
+            )
+        }));
+        Component::new("VEVENT".to_string(), properties, vec![])
+    }
+}
+
+/// 会議の一覧をVCALENDARとして書き出す
+pub fn meetings_to_ics(meetings: &[Meeting]) -> String {
+    let events = meetings.iter().map(Meeting::to_vevent).collect();
+    Component::new("VCALENDAR".to_string(), calendar_properties(), events).to_string()
+}
+
+/// 招待メールに添付するiTIP(RFC 5546)形式のVCALENDAR。
+/// 確定はREQUEST、取り消しはCANCELとして、同じUIDで送ることで受信側の予定が更新される
+pub fn meeting_to_itip(meeting: &Meeting, organizer: &str) -> String {
+    let method = match meeting.status {
+        MeetingStatus::Confirmed => "REQUEST",
+        MeetingStatus::Cancelled => "CANCEL",
+    };
+    let mut properties = calendar_properties();
+    properties.push(Property::new(
+        "METHOD".to_string(),
+        vec![],
+        method.to_string(),
+    ));
+    let mut event = meeting.to_vevent();
+    event.properties.push(Property::new(
+        "ORGANIZER".to_string(),
+        vec![],
+        format!("mailto:{}", organizer),
+    ));
+    Component::new("VCALENDAR".to_string(), properties, vec![event]).to_string()
+}
+
+fn calendar_properties() -> Vec<Property> {
+    vec![
+        Property::new("VERSION".to_string(), vec![], "2.0".to_string()),
+        Property::new(
+            "PRODID".to_string(),
+            vec![],
+            format!("-//{}//meetings//EN", UID_DOMAIN),
+        ),
+        Property::new("CALSCALE".to_string(), vec![], "GREGORIAN".to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::domains::ics;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_meetings_to_ics() {
+        let confirmed = Meeting::new(
+            1,
+            Slot::new(to_date("2020-01-01 10:00:00")),
+            MeetingStatus::Confirmed,
+            vec![
+                "test1@example.com".to_string(),
+                "test2@example.com".to_string(),
+            ],
+            Some("room-a".to_string()),
+            to_date("2019-12-31 01:00:00"),
+        );
+        let cancelled = Meeting::new(
+            2,
+            Slot::new(to_date("2020-01-02 10:00:00")),
+            MeetingStatus::Cancelled,
+            vec!["test1@example.com".to_string()],
+            None,
+            to_date("2019-12-31 02:00:00"),
+        );
+        let text = meetings_to_ics(&[confirmed, cancelled]);
+        let calendars = ics::parse(&text).unwrap();
+        let events = calendars[0].find_all("VEVENT");
+        assert_eq!(events.len(), 2);
+
+        let value = |i: usize, name: &str| events[i].property(name).unwrap().value.clone();
+        assert_eq!(value(0, "UID"), "meeting-1@actix-web-sample");
+        assert_eq!(value(0, "DTSTART"), "20200101T100000");
+        assert_eq!(value(0, "DTEND"), "20200101T103000");
+        assert_eq!(value(0, "STATUS"), "CONFIRMED");
+        assert_eq!(value(0, "LOCATION"), "room-a");
+        assert_eq!(
+            events[0]
+                .properties
+                .iter()
+                .filter(|p| p.name == "ATTENDEE")
+                .map(|p| p.value.as_str())
+                .collect::<Vec<_>>(),
+            vec!["mailto:test1@example.com", "mailto:test2@example.com"]
+        );
+        assert_eq!(value(1, "UID"), "meeting-2@actix-web-sample");
+        assert_eq!(value(1, "STATUS"), "CANCELLED");
+    }
+
+    #[test]
+    fn test_meeting_to_itip() {
+        let event = MeetingEvent::new(
+            "default".to_string(),
+            EventType::MeetingCancelled,
+            1,
+            Slot::new(to_date("2020-01-01 10:00:00")),
+            vec!["test1@example.com".to_string()],
+            None,
+        );
+        let meeting = Meeting::from_event(&event, to_date("2019-12-31 01:00:00"));
+        let text = meeting_to_itip(&meeting, "noreply@example.com");
+        let calendars = ics::parse(&text).unwrap();
+        assert_eq!(calendars[0].property("METHOD").unwrap().value, "CANCEL");
+
+        let events = calendars[0].find_all("VEVENT");
+        let value = |name: &str| events[0].property(name).unwrap().value.clone();
+        assert_eq!(value("UID"), "meeting-1@actix-web-sample");
+        assert_eq!(value("STATUS"), "CANCELLED");
+        assert_eq!(value("ORGANIZER"), "mailto:noreply@example.com");
+    }
+}
@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use derive_new::new;
+
+use super::{error::Error, slot::Slot, slot_range::SlotRange};
+
+/// 上限を数える期間
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapPeriod {
+    Day,
+    /// 月曜始まりの1週間
+    Week,
+}
+
+impl FromStr for CapPeriod {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "week" => Ok(Self::Week),
+            _ => Err(Error::DbError(format!("unknown cap period: {}", s))),
+        }
+    }
+}
+
+impl CapPeriod {
+    /// dateを含む期間を返す
+    pub fn range_of(&self, date: NaiveDate) -> SlotRange {
+        let start = match self {
+            Self::Day => date,
+            Self::Week => date - Duration::days(date.weekday().num_days_from_monday().into()),
+        };
+        let days = match self {
+            Self::Day => 1,
+            Self::Week => 7,
+        };
+        let start = start.and_hms_opt(0, 0, 0).unwrap();
+        SlotRange::new(start, start + Duration::days(days))
+    }
+}
+
+/// 期間あたりの会議数・会議時間(分)の上限
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct MeetingCap {
+    pub period: CapPeriod,
+    pub max_meetings: Option<u32>,
+    pub max_minutes: Option<u32>,
+}
+
+impl MeetingCap {
+    /// 既に入っている予定にslotを追加しても上限に収まるかどうか
+    pub fn allows(&self, booked: &[NaiveDateTime], slot: &Slot) -> bool {
+        let range = self.period.range_of(slot.start_date.date());
+        let count = booked
+            .iter()
+            .filter(|b| range.start <= **b && **b < range.end)
+            .count() as i64
+            + 1;
+        // 入っている予定もslotと同じ長さになる
+        let minutes = count * slot.duration.num_minutes();
+        self.max_meetings
+            .map(|max| count <= max.into())
+            .unwrap_or(true)
+            && self
+                .max_minutes
+                .map(|max| minutes <= max.into())
+                .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_range_of() {
+        // 2020-01-01は水曜日
+        let date = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        assert_eq!(
+            CapPeriod::Day.range_of(date),
+            SlotRange::new(
+                to_date("2020-01-01 00:00:00"),
+                to_date("2020-01-02 00:00:00")
+            )
+        );
+        assert_eq!(
+            CapPeriod::Week.range_of(date),
+            SlotRange::new(
+                to_date("2019-12-30 00:00:00"),
+                to_date("2020-01-06 00:00:00")
+            )
+        );
+    }
+    #[test]
+    fn test_allows() {
+        let booked = vec![
+            to_date("2020-01-01 10:00:00"),
+            to_date("2020-01-01 11:00:00"),
+            to_date("2020-01-02 10:00:00"),
+        ];
+        let daily = MeetingCap::new(CapPeriod::Day, Some(2), None);
+        assert!(!daily.allows(&booked, &Slot::new(to_date("2020-01-01 15:00:00"))));
+        assert!(daily.allows(&booked, &Slot::new(to_date("2020-01-02 15:00:00"))));
+
+        let weekly = MeetingCap::new(CapPeriod::Week, None, Some(120));
+        assert!(weekly.allows(&booked, &Slot::new(to_date("2020-01-03 15:00:00"))));
+        assert!(!weekly.allows(
+            &[booked.clone(), vec![to_date("2020-01-03 10:00:00")]].concat(),
+            &Slot::new(to_date("2020-01-03 15:00:00"))
+        ));
+        // 翌週は別に数える
+        assert!(weekly.allows(
+            &[booked, vec![to_date("2020-01-03 10:00:00")]].concat(),
+            &Slot::new(to_date("2020-01-06 15:00:00"))
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::NaiveDateTime;
+use derive_new::new;
+
+use super::{error::Error, slot::Slot};
+
+/// 休暇などで予定を入れられない期間。endは含まない
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct OutOfOffice {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub reason: String,
+}
+
+impl OutOfOffice {
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.start >= self.end {
+            return Err(Error::InvalidInput(
+                "out of office must end after it starts".to_string(),
+            ));
+        }
+        Ok(())
+    }
+    /// slotと少しでも重なるかどうか。境界が接しているだけなら重ならない
+    pub fn overlaps(&self, slot: &Slot) -> bool {
+        self.start < slot.end_date() && slot.start_date < self.end
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let ooo = OutOfOffice::new(
+            to_date("2020-01-01 12:00:00"),
+            to_date("2020-01-02 12:00:00"),
+            "vacation".to_string(),
+        );
+        assert!(!ooo.overlaps(&Slot::new(to_date("2020-01-01 11:30:00"))));
+        assert!(ooo.overlaps(&Slot::new(to_date("2020-01-01 11:45:00"))));
+        assert!(ooo.overlaps(&Slot::new(to_date("2020-01-02 11:30:00"))));
+        assert!(!ooo.overlaps(&Slot::new(to_date("2020-01-02 12:00:00"))));
+    }
+    #[test]
+    fn test_validate() {
+        let ooo = OutOfOffice::new(
+            to_date("2020-01-01 12:00:00"),
+            to_date("2020-01-01 12:00:00"),
+            "vacation".to_string(),
+        );
+        assert!(ooo.validate().is_err());
+    }
+}
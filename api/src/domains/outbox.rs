@@ -0,0 +1,63 @@
+use chrono::NaiveDateTime;
+use derive_new::new;
+
+/// sinkを確保してから、他のプロセスが配信し始めてよくなるまでの時間。
+/// 配信中にプロセスが落ちても、これを過ぎれば他のプロセスが続きから配信する
+pub const OUTBOX_LEASE_SECONDS: i64 = 60;
+
+/// 予約の変更と同じトランザクションでt_outboxに書かれたイベント。idの順に配信する
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct OutboxEvent {
+    pub id: u64,
+    pub event_type: String,
+    /// イベントの対象(会議のid)
+    pub aggregate_id: u32,
+    /// JSON文字列
+    pub payload: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// sinkごとの配信の進み具合。確保したプロセスだけがtokenを知っている
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct OutboxCursor {
+    pub sink: String,
+    pub token: String,
+    /// このsinkに配信した最後のイベントのid
+    pub last_id: u64,
+}
+
+impl OutboxEvent {
+    /// 1行1イベントのJSONにする。payloadはそのままJSONとして埋め込む
+    pub fn to_json_line(&self) -> String {
+        let payload = serde_json::from_str::<serde_json::Value>(&self.payload)
+            .unwrap_or_else(|_| serde_json::Value::String(self.payload.clone()));
+        serde_json::json!({
+            "id": self.id,
+            "type": self.event_type,
+            "aggregateId": self.aggregate_id,
+            "payload": payload,
+            "createdAt": self.created_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_json_line() {
+        let event = OutboxEvent::new(
+            3,
+            "meeting.confirmed".to_string(),
+            1,
+            r#"{"meetingId":1}"#.to_string(),
+            NaiveDateTime::parse_from_str("2020-01-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+        );
+        assert_eq!(
+            event.to_json_line(),
+            r#"{"aggregateId":1,"createdAt":"2020-01-01T10:00:00","id":3,"payload":{"meetingId":1},"type":"meeting.confirmed"}"#
+        );
+    }
+}
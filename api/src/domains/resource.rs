@@ -0,0 +1,97 @@
+use std::str::FromStr;
+
+use derive_new::new;
+
+use super::{error::Error, slot::Slot};
+
+/// 人以外に予約できるもの
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResourceKind {
+    Room,
+    Equipment,
+}
+
+impl FromStr for ResourceKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "room" => Ok(Self::Room),
+            "equipment" => Ok(Self::Equipment),
+            _ => Err(Error::InvalidInput(format!("unknown resource kind: {}", s))),
+        }
+    }
+}
+
+impl ResourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Room => "room",
+            Self::Equipment => "equipment",
+        }
+    }
+}
+
+/// 会議室や機材。nameで識別する
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Resource {
+    pub name: String,
+    pub kind: ResourceKind,
+    /// 収容人数。機材の場合は同時に使える人数
+    pub capacity: u32,
+    /// "projector"などの設備
+    pub attributes: Vec<String>,
+}
+
+/// /slotsで指定する会議室の条件
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct RoomRequirement {
+    pub min_capacity: u32,
+    pub attributes: Vec<String>,
+}
+
+impl RoomRequirement {
+    pub fn matches(&self, resource: &Resource) -> bool {
+        resource.kind == ResourceKind::Room
+            && resource.capacity >= self.min_capacity
+            && self
+                .attributes
+                .iter()
+                .all(|a| resource.attributes.contains(a))
+    }
+}
+
+/// 会議室を割り当てた枠
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct RoomSlot {
+    pub slot: Slot,
+    pub room: Resource,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let requirement = RoomRequirement::new(4, vec!["projector".to_string()]);
+        let room = Resource::new(
+            "room-a".to_string(),
+            ResourceKind::Room,
+            6,
+            vec!["projector".to_string(), "whiteboard".to_string()],
+        );
+        assert!(requirement.matches(&room));
+
+        let small = Resource::new("room-b".to_string(), ResourceKind::Room, 2, vec![]);
+        assert!(!RoomRequirement::new(4, vec![]).matches(&small));
+
+        let equipment = Resource::new(
+            "projector-1".to_string(),
+            ResourceKind::Equipment,
+            10,
+            vec!["projector".to_string()],
+        );
+        assert!(!requirement.matches(&equipment));
+    }
+}
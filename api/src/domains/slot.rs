@@ -1,24 +1,53 @@
 use std::collections::HashSet;
 
-use chrono::{Duration, NaiveDateTime};
-use derive_new::new;
+use chrono::{Duration, NaiveDateTime, Timelike};
 use itertools::Itertools;
 
-use super::slot_range::SlotRange;
+use super::{error::Error, slot_range::SlotRange};
 
-/// 30分の時間枠
-#[derive(Debug, Clone, new, PartialEq)]
+/// 枠の長さ(分)の既定値
+pub const DEFAULT_DURATION_MINUTES: i64 = 30;
+
+/// 時間枠。長さは設定で変えられ、既定は30分
+#[derive(Debug, Clone, PartialEq)]
 pub struct Slot {
     pub start_date: NaiveDateTime,
+    pub duration: Duration,
 }
 
 impl Slot {
-    // 1枠は30分
-    pub fn duration() -> chrono::Duration {
-        chrono::Duration::minutes(30)
+    /// 既定の長さの枠
+    pub fn new(start_date: NaiveDateTime) -> Self {
+        Self::with_duration(start_date, Self::default_duration())
+    }
+    pub fn with_duration(start_date: NaiveDateTime, duration: Duration) -> Self {
+        Self {
+            start_date,
+            duration,
+        }
+    }
+    pub fn default_duration() -> Duration {
+        Duration::minutes(DEFAULT_DURATION_MINUTES)
+    }
+    /// 枠の区切りが毎時0分に揃うように、60分を割り切れる長さだけを使える
+    pub fn validate_duration(minutes: i64) -> Result<(), Error> {
+        if minutes <= 0 || 60 % minutes != 0 {
+            return Err(Error::InvalidInput(format!(
+                "slot duration must divide 60 minutes: {}",
+                minutes
+            )));
+        }
+        Ok(())
     }
     pub fn end_date(&self) -> chrono::NaiveDateTime {
-        self.start_date + Self::duration()
+        self.start_date + self.duration
+    }
+
+    /// durationの長さの枠の区切り(30分なら毎時0分・30分)に乗っているかどうか
+    pub fn is_on_grid(date: &NaiveDateTime, duration: Duration) -> bool {
+        date.second() == 0
+            && date.nanosecond() == 0
+            && i64::from(date.minute()) % duration.num_minutes() == 0
     }
 
     // 時間が連続しているかどうか
@@ -89,6 +118,20 @@ mod test {
         assert!(!slot2.is_continuous(&slot3));
     }
     #[test]
+    fn test_is_on_grid() {
+        let to_date =
+            |date: &str| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap();
+        let duration = Slot::default_duration();
+        assert!(Slot::is_on_grid(&to_date("2020-01-01 10:00:00"), duration));
+        assert!(Slot::is_on_grid(&to_date("2020-01-01 10:30:00"), duration));
+        assert!(!Slot::is_on_grid(&to_date("2020-01-01 10:15:00"), duration));
+        assert!(!Slot::is_on_grid(&to_date("2020-01-01 10:30:30"), duration));
+        assert!(Slot::is_on_grid(
+            &to_date("2020-01-01 10:15:00"),
+            Duration::minutes(15)
+        ));
+    }
+    #[test]
     fn test_collect_slot_ranges1() {
         // test collect_slot_ranges method
         let slot1 = Slot::new(
@@ -0,0 +1,93 @@
+use chrono::Duration;
+use derive_new::new;
+use tokio::sync::broadcast;
+
+use super::{slot_range::SlotRange, webhook::MeetingEvent};
+
+/// 確定・取り消しのイベントを、このプロセスで購読しているクライアントに配る
+#[derive(Debug, Clone)]
+pub struct SlotEvents {
+    sender: broadcast::Sender<MeetingEvent>,
+}
+
+impl SlotEvents {
+    /// capacityを超えて溜まったイベントは古いものから捨てられ、受信側にはLaggedが届く
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: MeetingEvent) {
+        // 購読者がいない場合はエラーになるが、捨てて構わない
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MeetingEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// クライアントが購読しているアカウントと期間
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct SlotWatch {
+    pub tenant: String,
+    pub accounts: Vec<String>,
+    pub range: SlotRange,
+}
+
+impl SlotWatch {
+    /// 確定可能な枠が変わりうるイベントかどうか。
+    /// 週単位の上限やバッファがあるので、期間の前後1週間の会議も対象にする
+    pub fn is_affected_by(&self, event: &MeetingEvent) -> bool {
+        let margin = Duration::weeks(1);
+        let range = SlotRange::new(self.range.start - margin, self.range.end + margin);
+        event.tenant == self.tenant
+            && event.attendees.iter().any(|a| self.accounts.contains(a))
+            && range.contains(&SlotRange::from(event.slot.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::domains::{slot::Slot, webhook::EventType};
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_is_affected_by() {
+        let watch = SlotWatch::new(
+            "default".to_string(),
+            vec!["test1@example.com".to_string()],
+            SlotRange::new(
+                to_date("2020-01-10 00:00:00"),
+                to_date("2020-01-11 00:00:00"),
+            ),
+        );
+        let event = |account: &str, start: &str| {
+            MeetingEvent::new(
+                "default".to_string(),
+                EventType::MeetingConfirmed,
+                1,
+                Slot::new(to_date(start)),
+                vec![account.to_string()],
+                None,
+            )
+        };
+        assert!(watch.is_affected_by(&event("test1@example.com", "2020-01-10 10:00:00")));
+        // 同じ週の上限に関わる
+        assert!(watch.is_affected_by(&event("test1@example.com", "2020-01-06 10:00:00")));
+        assert!(!watch.is_affected_by(&event("test2@example.com", "2020-01-10 10:00:00")));
+        assert!(!watch.is_affected_by(&event("test1@example.com", "2020-02-10 10:00:00")));
+        // 同じアカウントでも別のテナントの会議は関係ない
+        let other = MeetingEvent {
+            tenant: "acme".to_string(),
+            ..event("test1@example.com", "2020-01-10 10:00:00")
+        };
+        assert!(!watch.is_affected_by(&other));
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::Duration;
 use derive_new::new;
 
 use super::slot::Slot;
@@ -19,10 +20,15 @@ impl From<Slot> for SlotRange {
 }
 impl SlotRange {
     /// 重なる時間があるかどうか
+    #[allow(clippy::nonminimal_bool, clippy::eq_op)]
     fn intersects(&self, other: &Self) -> bool {
         self.start <= other.end && other.start <= self.end
             || other.start <= self.end && self.start <= other.end
     }
+    /// otherの時間帯を全て含むかどうか
+    pub fn contains(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
     /// 重なる時間がある場合、重なる時間の範囲を返す
     pub fn intersect_slot_range(&self, other: &Self) -> Option<SlotRange> {
         if !self.intersects(other) {
@@ -41,14 +47,15 @@ impl SlotRange {
 
         Some(SlotRange::new(start, end))
     }
-    pub fn to_slots(&self) -> Vec<Slot> {
+    /// durationの長さの枠に分ける
+    pub fn to_slots(&self, duration: Duration) -> Vec<Slot> {
         let start = self.start;
         let end = self.end;
         let mut slots = vec![];
         let mut current = start;
-        while current + Slot::duration() <= end {
-            slots.push(Slot::new(current));
-            current += Slot::duration();
+        while current + duration <= end {
+            slots.push(Slot::with_duration(current, duration));
+            current += duration;
         }
         slots
     }
@@ -95,7 +102,7 @@ mod test {
             to_date("2020-01-01 10:00:00"),
             to_date("2020-01-01 11:30:00"),
         );
-        let slots = slot_range.to_slots();
+        let slots = slot_range.to_slots(Slot::default_duration());
         assert_eq!(
             slots,
             vec![
@@ -207,6 +214,25 @@ mod test {
         );
     }
     #[test]
+    fn test_contains() {
+        let range = SlotRange::new(
+            to_date("2020-01-01 10:00:00"),
+            to_date("2020-01-01 11:30:00"),
+        );
+        assert!(range.contains(&SlotRange::new(
+            to_date("2020-01-01 10:00:00"),
+            to_date("2020-01-01 11:30:00")
+        )));
+        assert!(range.contains(&SlotRange::new(
+            to_date("2020-01-01 10:30:00"),
+            to_date("2020-01-01 11:00:00")
+        )));
+        assert!(!range.contains(&SlotRange::new(
+            to_date("2020-01-01 09:50:00"),
+            to_date("2020-01-01 10:30:00")
+        )));
+    }
+    #[test]
     fn test_intersect() {
         assert!(SlotRange::new(
             to_date("2020-01-01 10:00:00"),
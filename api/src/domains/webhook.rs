@@ -0,0 +1,311 @@
+use std::str::FromStr;
+
+use chrono::Duration;
+use derive_new::new;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{auth::DEFAULT_TENANT, error::Error, slot::Slot};
+
+/// 配信を諦めるまでの試行回数
+pub const MAX_ATTEMPTS: u32 = 8;
+/// 送信するために確保した配信を、他のプロセスが送信しないでおく時間。
+/// 1回に確保する配信を全て送信し終えるより長くする。送信中にプロセスが落ちた場合は、これを過ぎてから送り直す
+pub const DELIVERY_LEASE_SECONDS: i64 = 15 * 60;
+
+/// 予約のライフサイクルで発生するイベント
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventType {
+    MeetingConfirmed,
+    MeetingCancelled,
+}
+
+impl FromStr for EventType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "meeting.confirmed" => Ok(Self::MeetingConfirmed),
+            "meeting.cancelled" => Ok(Self::MeetingCancelled),
+            _ => Err(Error::InvalidInput(format!("unknown event type: {}", s))),
+        }
+    }
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MeetingConfirmed => "meeting.confirmed",
+            Self::MeetingCancelled => "meeting.cancelled",
+        }
+    }
+}
+
+/// 会議に関するイベント。payloadがWebhookで送るJSONになる
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct MeetingEvent {
+    /// 会議が属するテナント
+    pub tenant: String,
+    pub event_type: EventType,
+    pub meeting_id: u32,
+    pub slot: Slot,
+    pub attendees: Vec<String>,
+    pub room: Option<String>,
+}
+
+impl MeetingEvent {
+    /// payloadから読み戻す
+    pub fn from_payload(payload: &str) -> Result<Self, Error> {
+        #[derive(serde::Deserialize)]
+        struct Payload {
+            /// テナントを導入する前のイベントには無い
+            #[serde(default = "default_tenant")]
+            tenant: String,
+            #[serde(rename = "type")]
+            event_type: String,
+            #[serde(rename = "meetingId")]
+            meeting_id: u32,
+            #[serde(rename = "startTime")]
+            start_time: String,
+            /// 無いものは既定の長さの枠として読む
+            #[serde(rename = "endTime")]
+            end_time: Option<String>,
+            attendees: Vec<String>,
+            room: Option<String>,
+        }
+        let payload: Payload = serde_json::from_str(payload)
+            .map_err(|e| Error::InvalidInput(format!("invalid event payload: {}", e)))?;
+        let parse = |date: &str| {
+            chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S")
+                .map_err(|e| Error::InvalidInput(format!("invalid event payload: {}", e)))
+        };
+        let start_time = parse(&payload.start_time)?;
+        let slot = match &payload.end_time {
+            Some(end_time) => Slot::with_duration(start_time, parse(end_time)? - start_time),
+            None => Slot::new(start_time),
+        };
+        Ok(Self::new(
+            payload.tenant,
+            payload.event_type.parse()?,
+            payload.meeting_id,
+            slot,
+            payload.attendees,
+            payload.room,
+        ))
+    }
+
+    pub fn payload(&self) -> String {
+        serde_json::json!({
+            "tenant": self.tenant,
+            "type": self.event_type.as_str(),
+            "meetingId": self.meeting_id,
+            "startTime": self.slot.start_date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "endTime": self.slot.end_date().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "attendees": self.attendees,
+            "room": self.room,
+        })
+        .to_string()
+    }
+}
+
+fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+/// 登録されたWebhookの送信先。eventsが空の場合は全てのイベントを受け取る
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Webhook {
+    pub id: u32,
+    pub url: String,
+    pub events: Vec<String>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// 配信履歴
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct Delivery {
+    pub id: u32,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: u32,
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// 送信待ちの配信
+#[derive(Debug, Clone, new, PartialEq)]
+pub struct PendingDelivery {
+    pub id: u32,
+    pub url: String,
+    pub secret: String,
+    pub payload: String,
+    /// これまでに試行した回数
+    pub attempts: u32,
+}
+
+/// 1回の送信の結果を受けて、配信をどうするか
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryOutcome {
+    Succeeded {
+        status_code: u16,
+    },
+    Retry {
+        status_code: Option<u16>,
+        error: String,
+        after: Duration,
+    },
+    Failed {
+        status_code: Option<u16>,
+        error: String,
+    },
+}
+
+impl PendingDelivery {
+    /// 2xxなら成功。それ以外は試行回数が上限に達するまで間隔を空けて再送する
+    pub fn outcome(&self, result: Result<u16, String>) -> DeliveryOutcome {
+        let (status_code, error) = match result {
+            Ok(code) if (200..300).contains(&code) => {
+                return DeliveryOutcome::Succeeded { status_code: code }
+            }
+            Ok(code) => (Some(code), format!("unexpected status: {}", code)),
+            Err(error) => (None, error),
+        };
+        retry_or_fail(self.attempts, status_code, error)
+    }
+}
+
+/// 送信に失敗したとき、これまでの試行回数から再送するか諦めるかを決める
+pub fn retry_or_fail(attempts: u32, status_code: Option<u16>, error: String) -> DeliveryOutcome {
+    let attempts = attempts + 1;
+    if attempts >= MAX_ATTEMPTS {
+        return DeliveryOutcome::Failed { status_code, error };
+    }
+    DeliveryOutcome::Retry {
+        status_code,
+        error,
+        after: backoff(attempts),
+    }
+}
+
+/// 30秒から倍々に伸ばし、1時間で打ち止めにする
+pub fn backoff(attempts: u32) -> Duration {
+    let seconds = 30_i64.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    Duration::seconds(seconds.min(60 * 60))
+}
+
+/// `{timestamp}.{body}`のHMAC-SHA256を16進数で返す。受信側はX-Webhook-Signatureと比較する
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn test_sign() {
+        // echo -n '1700000000.{}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", 1700000000, "{}"),
+            "b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163"
+        );
+        assert_ne!(
+            sign("secret", 1700000000, "{}"),
+            sign("other", 1700000000, "{}")
+        );
+    }
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::seconds(30));
+        assert_eq!(backoff(2), Duration::seconds(60));
+        assert_eq!(backoff(4), Duration::seconds(240));
+        assert_eq!(backoff(20), Duration::seconds(3600));
+    }
+    #[test]
+    fn test_outcome() {
+        let delivery = PendingDelivery::new(
+            1,
+            "http://localhost/hook".to_string(),
+            "secret".to_string(),
+            "{}".to_string(),
+            0,
+        );
+        assert_eq!(
+            delivery.outcome(Ok(204)),
+            DeliveryOutcome::Succeeded { status_code: 204 }
+        );
+        assert_eq!(
+            delivery.outcome(Ok(500)),
+            DeliveryOutcome::Retry {
+                status_code: Some(500),
+                error: "unexpected status: 500".to_string(),
+                after: Duration::seconds(30),
+            }
+        );
+        let last = PendingDelivery {
+            attempts: MAX_ATTEMPTS - 1,
+            ..delivery
+        };
+        assert_eq!(
+            last.outcome(Err("connection refused".to_string())),
+            DeliveryOutcome::Failed {
+                status_code: None,
+                error: "connection refused".to_string(),
+            }
+        );
+    }
+    #[test]
+    fn test_payload() {
+        let event = MeetingEvent::new(
+            "acme".to_string(),
+            EventType::MeetingConfirmed,
+            1,
+            Slot::new(
+                NaiveDateTime::parse_from_str("2020-01-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+            ),
+            vec!["test1@example.com".to_string()],
+            None,
+        );
+        let payload: serde_json::Value = serde_json::from_str(&event.payload()).unwrap();
+        assert_eq!(payload["tenant"], "acme");
+        assert_eq!(payload["type"], "meeting.confirmed");
+        assert_eq!(payload["meetingId"], 1);
+        assert_eq!(payload["startTime"], "2020-01-01T10:00:00");
+        assert_eq!(payload["endTime"], "2020-01-01T10:30:00");
+        assert_eq!(payload["attendees"][0], "test1@example.com");
+        assert!(payload["room"].is_null());
+        assert_eq!(MeetingEvent::from_payload(&event.payload()).unwrap(), event);
+        let mut long = event.clone();
+        long.slot = Slot::with_duration(event.slot.start_date, Duration::minutes(60));
+        assert_eq!(MeetingEvent::from_payload(&long.payload()).unwrap(), long);
+
+        // テナントが無いものは既定のテナントのイベントとして読む
+        let legacy = r#"{"type":"meeting.cancelled","meetingId":1,"startTime":"2020-01-01T10:00:00","attendees":[],"room":null}"#;
+        assert_eq!(
+            MeetingEvent::from_payload(legacy).unwrap().tenant,
+            DEFAULT_TENANT
+        );
+    }
+}
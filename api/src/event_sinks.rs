@@ -0,0 +1,5 @@
+pub mod email_sink;
+pub mod file_sink;
+pub mod log_sink;
+pub mod slot_event_sink;
+pub mod webhook_sink;
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::domains::{
+    data_clients::{email_client::EmailClient, outbox_client::EventSink},
+    email::{invitations, EmailTemplates},
+    error::Error,
+    meeting::Meeting,
+    outbox::OutboxEvent,
+    webhook::MeetingEvent,
+};
+
+/// 参加者それぞれへの招待メールを積む。送信はEmailUsecaseが行う
+pub struct EmailSink {
+    pool: Arc<dyn EmailClient>,
+    templates: EmailTemplates,
+    /// 差出人。ICSのORGANIZERにもなる
+    organizer: String,
+}
+
+impl EmailSink {
+    pub fn new(pool: Arc<dyn EmailClient>, templates: EmailTemplates, organizer: String) -> Self {
+        Self {
+            pool,
+            templates,
+            organizer,
+        }
+    }
+}
+
+#[async_trait]
+impl EventSink for EmailSink {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), Error> {
+        let meeting_event = MeetingEvent::from_payload(&event.payload)?;
+        let meeting = Meeting::from_event(&meeting_event, event.created_at);
+        let emails = invitations(&meeting, &self.templates, &self.organizer);
+        self.pool.enqueue_emails(&emails).await
+    }
+}
@@ -0,0 +1,69 @@
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use async_trait::async_trait;
+
+use crate::domains::{data_clients::outbox_client::EventSink, error::Error, outbox::OutboxEvent};
+
+/// イベントを1行1件のJSONでファイルに追記する
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl EventSink for FileSink {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), Error> {
+        let path = self.path.clone();
+        let line = event.to_json_line();
+        actix_web::rt::task::spawn_blocking(move || {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", line)
+        })
+        .await
+        .map_err(|e| Error::PublishError(e.to_string()))?
+        .map_err(|e| Error::PublishError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_publish() {
+        let path = std::env::temp_dir().join(format!("outbox-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let sink = FileSink::new(&path);
+        let created_at =
+            NaiveDateTime::parse_from_str("2020-01-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        for id in 1..=2 {
+            let event = OutboxEvent::new(
+                id,
+                "meeting.confirmed".to_string(),
+                1,
+                "{}".to_string(),
+                created_at,
+            );
+            sink.publish(&event).await.unwrap();
+        }
+
+        let lines = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let ids = lines
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2]);
+    }
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use crate::domains::{data_clients::outbox_client::EventSink, error::Error, outbox::OutboxEvent};
+
+/// イベントをログに出力する
+pub struct LogSink;
+
+#[async_trait]
+impl EventSink for LogSink {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), Error> {
+        log::info!("event: {}", event.to_json_line());
+        Ok(())
+    }
+}
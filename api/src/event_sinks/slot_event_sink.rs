@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+
+use crate::domains::{
+    data_clients::outbox_client::EventSink, error::Error, outbox::OutboxEvent,
+    slot_events::SlotEvents, webhook::MeetingEvent,
+};
+
+/// 空き枠のストリームを購読しているクライアントにイベントを配る
+pub struct SlotEventSink {
+    events: SlotEvents,
+}
+
+impl SlotEventSink {
+    pub fn new(events: SlotEvents) -> Self {
+        Self { events }
+    }
+}
+
+#[async_trait]
+impl EventSink for SlotEventSink {
+    fn name(&self) -> &str {
+        "slot_events"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), Error> {
+        self.events
+            .publish(MeetingEvent::from_payload(&event.payload)?);
+        Ok(())
+    }
+}
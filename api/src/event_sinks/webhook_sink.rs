@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::domains::{
+    data_clients::{outbox_client::EventSink, webhook_client::WebhookClient},
+    error::Error,
+    outbox::OutboxEvent,
+    webhook::MeetingEvent,
+};
+
+/// イベントを購読しているWebhookへの配信を積む。送信はWebhookUsecaseが行う
+pub struct WebhookSink {
+    pool: Arc<dyn WebhookClient>,
+}
+
+impl WebhookSink {
+    pub fn new(pool: Arc<dyn WebhookClient>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> Result<(), Error> {
+        let tenant = MeetingEvent::from_payload(&event.payload)?.tenant;
+        self.pool
+            .enqueue_event(&tenant, &event.event_type, &event.payload)
+            .await
+    }
+}
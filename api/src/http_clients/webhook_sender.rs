@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::domains::data_clients::webhook_client::WebhookSender;
+
+/// awcでWebhookを送信する
+pub struct HttpWebhookSender {
+    timeout: Duration,
+}
+
+impl HttpWebhookSender {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+#[async_trait(?Send)]
+impl WebhookSender for HttpWebhookSender {
+    async fn send(
+        &self,
+        url: &str,
+        body: &str,
+        timestamp: i64,
+        signature: &str,
+    ) -> Result<u16, String> {
+        let client = awc::Client::builder().timeout(self.timeout).finish();
+        let response = client
+            .post(url)
+            .insert_header(("Content-Type", "application/json"))
+            .insert_header(("X-Webhook-Timestamp", timestamp.to_string()))
+            .insert_header(("X-Webhook-Signature", format!("sha256={}", signature)))
+            .send_body(body.to_string())
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(response.status().as_u16())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{post, App, HttpRequest, HttpResponse, HttpServer};
+
+    use super::*;
+
+    #[post("/hook")]
+    async fn hook(req: HttpRequest, body: String) -> HttpResponse {
+        let header = |name| req.headers().get(name).unwrap().to_str().unwrap();
+        if header("X-Webhook-Timestamp") == "1700000000"
+            && header("X-Webhook-Signature") == "sha256=abc"
+            && body == "{}"
+        {
+            HttpResponse::NoContent().finish()
+        } else {
+            HttpResponse::BadRequest().finish()
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_send() {
+        let server = HttpServer::new(|| App::new().service(hook))
+            .workers(1)
+            .bind("127.0.0.1:0")
+            .unwrap();
+        let addr = server.addrs()[0];
+        let handle = server.run();
+        actix_web::rt::spawn(handle);
+
+        let sender = HttpWebhookSender::new(Duration::from_secs(5));
+        let url = format!("http://{}/hook", addr);
+        assert_eq!(sender.send(&url, "{}", 1700000000, "abc").await, Ok(204));
+        assert_eq!(
+            sender.send(&url, "{\"a\":1}", 1700000000, "abc").await,
+            Ok(400)
+        );
+
+        // 接続できない場合はエラー
+        let url = format!("http://{}/hook", "127.0.0.1:1");
+        assert!(sender.send(&url, "{}", 1700000000, "abc").await.is_err());
+    }
+}
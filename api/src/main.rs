@@ -1,39 +1,302 @@
-use std::{env, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
-use actix_web::{middleware::Logger, web, App, HttpServer};
-use controllers::{data, user_slots};
-use sqlx::mysql::MySqlPoolOptions;
-use usecases::{data::DataUsecase, user_slots::UserSlotUsecase};
+use actix_web::{web, App, HttpServer};
+use chrono::Utc;
+use config::{AuthConfig, Config, ConfigError, OutboxConfig};
+use domains::{
+    auth::Authenticator, data_clients::outbox_client::EventSink, slot_events::SlotEvents,
+};
+use event_sinks::{
+    email_sink::EmailSink, file_sink::FileSink, log_sink::LogSink, slot_event_sink::SlotEventSink,
+    webhook_sink::WebhookSink,
+};
+use http_clients::webhook_sender::HttpWebhookSender;
+use migrations::Command;
+use shutdown::{Coordinator, Signals};
+use sqlx::MySqlPool;
+use usecases::{
+    audit::AuditUsecase, calendars::CalendarUsecase, data::DataUsecase, emails::EmailUsecase,
+    health::HealthUsecase, holidays::HolidayUsecase, idempotency::IdempotencyUsecase,
+    meetings::MeetingUsecase, out_of_offices::OutOfOfficeUsecase, outbox::OutboxUsecase,
+    resources::ResourceUsecase, user_slots::UserSlotUsecase, webhooks::WebhookUsecase,
+};
+mod config;
 mod controllers;
 mod domains;
+mod event_sinks;
+mod http_clients;
+mod metrics;
+mod migrations;
+mod shutdown;
+mod smtp_clients;
 mod sql_clients;
+mod telemetry;
 mod usecases;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // FIXME: 今回は無条件でdev.envの内容を読み込む
-    dotenvy::from_filename_override("dev.env").ok();
-    let db_url = env::var("DATABASE_URL").unwrap();
-    let pool = Arc::new(
-        MySqlPoolOptions::default()
-            .connect(db_url.as_str())
-            .await
-            .unwrap(),
-    );
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    HttpServer::new(move || {
+    let (command, args) = match Command::from_args(std::env::args().skip(1).collect()) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    // 設定に誤りがあれば、全ての誤りを表示して起動しない
+    let config = match Config::load(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let tracer_provider = match telemetry::init(&config.log) {
+        Ok(provider) => provider,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let test_data = config.features.test_data_routes;
+    let slot_duration = config.slots.slot_duration();
+    let buffer = config.slots.buffer();
+    let business_hours = or_exit(config.slots.business_hours());
+    // DBに接続できるまではreadinessで503を返すので、接続を待たずに起動する
+    let pool = match config
+        .database
+        .pool_options()
+        .connect_lazy(config.database.url.as_deref().unwrap_or_default())
+    {
+        Ok(pool) => Arc::new(pool),
+        Err(e) => {
+            eprintln!("invalid DATABASE_URL: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(command) = command {
+        if let Err(e) = migrate(command, &pool).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // バックグラウンドのタスクは停止のシグナルを受け取ったら、処理中のものを終えてから止まる
+    let shutdown_timeout = Duration::from_secs(config.server.shutdown_timeout_seconds);
+    let mut coordinator = Coordinator::new(shutdown_timeout);
+    let health_uc = HealthUsecase::new(pool.clone());
+    let migrate_on_startup = config.database.migrate_on_startup;
+    let migrate_pool = pool.clone();
+    coordinator.spawn(|mut shutdown| async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(5));
+        while shutdown.tick(&mut interval).await {
+            match health_uc.readiness().await.database {
+                Ok(()) => {
+                    log::info!("connected to the database");
+                    // 適用し終えるまでreadinessは503を返す
+                    if migrate_on_startup {
+                        match migrations::run(&migrate_pool).await {
+                            Ok(applied) => {
+                                for version in applied {
+                                    log::info!("applied migration {}", version);
+                                }
+                            }
+                            Err(e) => log::error!("failed to apply migrations: {}", e),
+                        }
+                    }
+                    break;
+                }
+                Err(e) => log::warn!("waiting for the database: {}", e),
+            }
+        }
+    });
+
+    // SMTP_HOSTが設定されている場合だけ招待メールを送る
+    let email_sender = or_exit(config.smtp.sender());
+
+    // 予約と同じトランザクションでt_outboxに書かれたイベントを、別タスクで順番にsinkへ配信する
+    let slot_events = SlotEvents::new(1024);
+    let mut sinks = or_exit(event_sinks(&pool, &config, email_sender.is_some()));
+    sinks.push(Arc::new(SlotEventSink::new(slot_events.clone())));
+    let outbox_uc = OutboxUsecase::new(pool.clone(), sinks);
+    coordinator.spawn(|mut shutdown| async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(1));
+        while shutdown.tick(&mut interval).await {
+            if let Err(e) = outbox_uc.dispatch_pending().await {
+                log::error!("failed to dispatch events: {}", e);
+            }
+        }
+    });
+
+    // Webhookの配信はoutboxから積まれるので、別タスクで定期的に送信する
+    if config.features.webhook_delivery {
+        let webhook_uc = WebhookUsecase::new(pool.clone());
+        coordinator.spawn(|mut shutdown| async move {
+            let sender = HttpWebhookSender::new(Duration::from_secs(10));
+            let mut interval = actix_web::rt::time::interval(Duration::from_secs(5));
+            while shutdown.tick(&mut interval).await {
+                if let Err(e) = webhook_uc
+                    .deliver_pending(&sender, &|| Utc::now().timestamp())
+                    .await
+                {
+                    log::error!("failed to deliver webhooks: {}", e);
+                }
+            }
+        });
+    }
+
+    // 招待メールもoutboxから積まれるので、別タスクで定期的に送信する
+    if let Some(sender) = email_sender {
+        let email_uc = EmailUsecase::new(pool.clone());
+        coordinator.spawn(|mut shutdown| async move {
+            let mut interval = actix_web::rt::time::interval(Duration::from_secs(5));
+            while shutdown.tick(&mut interval).await {
+                if let Err(e) = email_uc.deliver_pending(&sender).await {
+                    log::error!("failed to send emails: {}", e);
+                }
+            }
+        });
+    }
+    let authenticator = web::Data::new(or_exit(authenticator(&config.auth)));
+    let db = pool.clone();
+    let requests = coordinator.requests();
+    let streams = web::Data::new(coordinator.streams());
+    let drain_timeout = coordinator.drain_timeout();
+    let server = HttpServer::new(move || {
+        let requests = requests.clone();
         App::new()
-            .wrap(Logger::default())
-            .app_data(web::Data::from(Arc::new(DataUsecase::new(pool.clone()))))
-            .app_data(web::Data::from(Arc::new(UserSlotUsecase::new(
-                pool.clone(),
-            ))))
-            .service(data::index)
-            .service(data::clear)
-            .service(user_slots::index)
-            .service(user_slots::post)
+            .wrap_fn(move |req, srv| requests.track(req, srv))
+            .wrap_fn(controllers::metrics::record)
+            .wrap_fn(controllers::trace::trace)
+            .app_data(web::Data::new(DataUsecase::new(pool.clone())))
+            .app_data(web::Data::new(
+                UserSlotUsecase::new(pool.clone(), buffer)
+                    .with_business_hours(business_hours)
+                    .with_slot_duration(slot_duration),
+            ))
+            .app_data(web::Data::new(HolidayUsecase::new(pool.clone())))
+            .app_data(web::Data::new(OutOfOfficeUsecase::new(pool.clone())))
+            .app_data(web::Data::new(ResourceUsecase::new(pool.clone())))
+            .app_data(web::Data::new(
+                MeetingUsecase::new(pool.clone()).with_slot_duration(slot_duration),
+            ))
+            .app_data(web::Data::new(
+                CalendarUsecase::new(pool.clone()).with_slot_duration(slot_duration),
+            ))
+            .app_data(web::Data::new(WebhookUsecase::new(pool.clone())))
+            .app_data(web::Data::new(AuditUsecase::new(pool.clone())))
+            .app_data(web::Data::new(IdempotencyUsecase::new(pool.clone())))
+            .app_data(web::Data::new(
+                HealthUsecase::new(pool.clone())
+                    .with_required_migration(migrations::latest_version()),
+            ))
+            .app_data(web::Data::new(slot_events.clone()))
+            .app_data(streams.clone())
+            .app_data(authenticator.clone())
+            .configure(controllers::routes(test_data))
+    })
+    // シグナルはCoordinatorで扱い、サーバ、バックグラウンドのタスク、DBの順に止める。
+    // 処理中のリクエストを待つのは全体の時間の一部だけにし、残りでタスクとDBを止める
+    .shutdown_timeout(drain_timeout.as_secs().max(1))
+    .disable_signals();
+    let server = match config.server.workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+    let mut signals = Signals::new()?;
+    let server = server.bind(&config.server.listen)?.run();
+    let handle = server.handle();
+    let running = actix_web::rt::spawn(server);
+
+    let signal = signals.recv().await;
+    log::info!("received {}, shutting down", signal);
+    if !coordinator.shutdown(handle, db.close()).await {
+        log::warn!(
+            "shutdown did not finish within {}s",
+            shutdown_timeout.as_secs()
+        );
+    }
+    let ret = running
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+    // 送り残したspanを送る
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("failed to flush spans: {}", e);
+        }
+    }
+    ret
+}
+
+/// `api migrate [run|revert|status]`。サーバは起動せずに終了する
+async fn migrate(command: Command, pool: &MySqlPool) -> Result<(), String> {
+    match command {
+        Command::Run => {
+            let applied = migrations::run(pool).await?;
+            if applied.is_empty() {
+                println!("no pending migrations");
+            }
+            for version in applied {
+                println!("applied {}", version);
+            }
+        }
+        Command::Revert => {
+            let version = migrations::revert(pool).await?;
+            println!("reverted {}", version);
+        }
+        Command::Status => {
+            let states = migrations::status(pool).await.map_err(|e| e.to_string())?;
+            for state in states {
+                println!("{}", state);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// OUTBOX_SINKSに指定したsink("log", "file")とWebhook、メールにイベントを配信する
+fn event_sinks(
+    pool: &Arc<MySqlPool>,
+    config: &Config,
+    email: bool,
+) -> Result<Vec<Arc<dyn EventSink>>, String> {
+    let OutboxConfig { sinks: names, file } = &config.outbox;
+    let mut sinks: Vec<Arc<dyn EventSink>> = vec![];
+    for name in names {
+        match name.as_str() {
+            "log" => sinks.push(Arc::new(LogSink)),
+            "file" => sinks.push(Arc::new(FileSink::new(file.clone()))),
+            // Config::validateで確認済み
+            other => unreachable!("unknown outbox sink: {}", other),
+        }
+    }
+    sinks.push(Arc::new(WebhookSink::new(pool.clone())));
+    if email {
+        // EMAIL_TEMPLATE_DIRにテンプレートがあれば既定のものを置き換える
+        sinks.push(Arc::new(EmailSink::new(
+            pool.clone(),
+            config.email.templates()?,
+            config.smtp.from.clone(),
+        )));
+    }
+    Ok(sinks)
+}
+
+/// API_KEYSのAPIキーと、JWT_PUBLIC_KEY_FILE(RS256)またはJWT_SECRET(HS256)で検証するJWTで認証する
+fn authenticator(config: &AuthConfig) -> Result<Authenticator, String> {
+    let api_keys =
+        Authenticator::parse_api_keys(&config.api_keys).map_err(|e| format!("API_KEYS: {}", e))?;
+    let jwt = config.jwt_verifier()?;
+    if api_keys.is_empty() && jwt.is_none() {
+        log::warn!("neither API_KEYS nor JWT_SECRET is set, all requests will be rejected");
+    }
+    Ok(Authenticator::new(api_keys, jwt))
+}
+
+/// Config::validateで確かめた設定から作る。その後でファイルが読めなくなった場合なども、
+/// 設定の誤りと同じく表示して終了する
+fn or_exit<T>(result: Result<T, String>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", ConfigError(vec![e]));
+        std::process::exit(2);
     })
-    .bind("0.0.0.0:8080")?
-    .run()
-    .await
 }
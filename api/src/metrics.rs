@@ -0,0 +1,150 @@
+use std::sync::OnceLock;
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::domains::health::PoolStatus;
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// /metricsで返すPrometheusのメトリクス。プロセスで一つだけ持つ
+pub struct Metrics {
+    registry: Registry,
+    /// メソッド、ルート、ステータスごとのリクエスト数
+    pub http_requests: IntCounterVec,
+    /// メソッド、ルートごとの処理時間(秒)
+    pub http_request_duration: HistogramVec,
+    /// 呼び出し元に返したErrorの種類ごとの数
+    pub errors: IntCounterVec,
+    /// 確定可能な枠の計算にかかった時間(秒)
+    pub slots_fetch_duration: Histogram,
+    /// 確定可能な枠を計算した参加者の人数
+    pub slots_attendees: Histogram,
+    /// デッドロックでやり直したトランザクションの数
+    pub transaction_retries: IntCounterVec,
+    /// 状態(idle, in_use)ごとのコネクションプールの接続数
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_max_connections: IntGauge,
+}
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "HTTP request latency by route",
+                ),
+                &["method", "route"],
+            )
+            .unwrap(),
+            errors: IntCounterVec::new(
+                Opts::new("errors_total", "errors returned to callers by kind"),
+                &["kind"],
+            )
+            .unwrap(),
+            slots_fetch_duration: Histogram::with_opts(HistogramOpts::new(
+                "slots_fetch_duration_seconds",
+                "time to compute confirmable slots",
+            ))
+            .unwrap(),
+            slots_attendees: Histogram::with_opts(
+                HistogramOpts::new(
+                    "slots_attendees",
+                    "number of attendees slots were computed for",
+                )
+                .buckets(vec![1.0, 2.0, 3.0, 5.0, 8.0, 13.0, 21.0, 34.0, 55.0]),
+            )
+            .unwrap(),
+            transaction_retries: IntCounterVec::new(
+                Opts::new(
+                    "transaction_retries_total",
+                    "transactions retried after a deadlock",
+                ),
+                &["operation"],
+            )
+            .unwrap(),
+            db_pool_connections: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "database connections by state"),
+                &["state"],
+            )
+            .unwrap(),
+            db_pool_max_connections: IntGauge::new(
+                "db_pool_max_connections",
+                "maximum database connections",
+            )
+            .unwrap(),
+            registry,
+        };
+        for collector in [
+            Box::new(metrics.http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.errors.clone()),
+            Box::new(metrics.slots_fetch_duration.clone()),
+            Box::new(metrics.slots_attendees.clone()),
+            Box::new(metrics.transaction_retries.clone()),
+            Box::new(metrics.db_pool_connections.clone()),
+            Box::new(metrics.db_pool_max_connections.clone()),
+        ] {
+            metrics.registry.register(collector).unwrap();
+        }
+        metrics
+    }
+
+    /// スクレイプの時点のコネクションプールの状態を反映する
+    pub fn set_pool_status(&self, pool: &PoolStatus) {
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(pool.idle.into());
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set(pool.in_use().into());
+        self.db_pool_max_connections
+            .set(pool.max_connections.into());
+    }
+
+    /// Prometheusのテキスト形式で書き出す
+    pub fn encode(&self) -> String {
+        let mut buf = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let metrics = Metrics::new();
+        metrics.set_pool_status(&PoolStatus::new(4, 1, 10));
+        metrics.errors.with_label_values(&["conflicts"]).inc_by(2);
+        let text = metrics.encode();
+        assert!(
+            text.contains("db_pool_connections{state=\"in_use\"} 3"),
+            "{}",
+            text
+        );
+        assert!(text.contains("db_pool_max_connections 10"), "{}", text);
+        assert!(
+            text.contains("errors_total{kind=\"conflicts\"} 2"),
+            "{}",
+            text
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, HashMap};
+
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migration, Migrator},
+    MySqlPool,
+};
+
+/// migrations/以下のスキーマの変更。バイナリに埋め込み、適用したもののチェックサムを_sqlx_migrationsに記録する。
+/// 変更を加える場合は、次の番号で<version>_<説明>.up.sqlと戻すための.down.sqlを追加する。
+/// 適用済みのファイルを書き換えるとチェックサムが合わなくなり、適用できなくなる
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// 戻さない最初のバージョン。既存のテーブルとデータを全て消すことになる
+const BASELINE_VERSION: i64 = 1;
+/// 最初のバージョンのテーブルと列。以前のdb/ddl.sqlで作ったDBと同じもので、
+/// 適用の記録が無いDBはこれと同じか、空でなければ適用しない
+const BASELINE_COLUMNS: &[(&str, &[&str])] = &[
+    ("t_user", &["id", "email", "created_at", "updated_at"]),
+    (
+        "t_user_slot",
+        &["id", "user_id", "start", "created_at", "updated_at"],
+    ),
+];
+
+/// migrateサブコマンド
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// 適用していないものを全て適用する
+    Run,
+    /// 最後に適用したものを一つ戻す
+    Revert,
+    /// それぞれの適用状況を表示する
+    Status,
+}
+
+impl Command {
+    /// 先頭が"migrate"ならサブコマンドとして取り出し、残りの引数と一緒に返す
+    pub fn from_args(args: Vec<String>) -> Result<(Option<Self>, Vec<String>), String> {
+        if args.first().map(String::as_str) != Some("migrate") {
+            return Ok((None, args));
+        }
+        let mut args = args.into_iter().skip(1).peekable();
+        let command = match args.peek().map(String::as_str) {
+            Some(x) if x.starts_with("--") => Self::Run,
+            None => Self::Run,
+            Some("run") => Self::Run,
+            Some("revert") => Self::Revert,
+            Some("status") => Self::Status,
+            Some(other) => return Err(format!("unknown migrate command: {}", other)),
+        };
+        if matches!(args.peek(), Some(x) if !x.starts_with("--")) {
+            args.next();
+        }
+        Ok((Some(command), args.collect()))
+    }
+}
+
+/// 埋め込んだものの中で最新のバージョン。readinessはこれが適用されるまで503を返す
+pub fn latest_version() -> Option<i64> {
+    up_migrations().map(|m| m.version).max()
+}
+
+fn up_migrations() -> impl Iterator<Item = &'static Migration> {
+    MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+}
+
+/// 適用していないものを順に適用し、適用したバージョンを返す。
+/// 複数のプロセスが同時に起動しても、ロックを取ったものだけが適用する
+pub async fn run(pool: &MySqlPool) -> Result<Vec<i64>, String> {
+    let applied = applied_checksums(pool).await.map_err(|e| e.to_string())?;
+    if applied.is_empty() {
+        check_unmanaged(&fetch_columns(pool).await.map_err(|e| e.to_string())?)?;
+    }
+    let pending = up_migrations()
+        .filter(|m| !applied.contains_key(&m.version))
+        .map(|m| m.version)
+        .collect();
+    MIGRATOR.run(pool).await.map_err(|e| e.to_string())?;
+    Ok(pending)
+}
+
+/// 適用の記録が無いDBにあるテーブルが、最初のバージョンと違う場合はエラーにする。
+/// IF NOT EXISTSで作らずに済ませたテーブルに、後のバージョンの変更を適用できないため
+fn check_unmanaged(columns: &[(String, String)]) -> Result<(), String> {
+    let mut tables: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (table, column) in columns {
+        tables.entry(table).or_default().push(column);
+    }
+    let baseline: BTreeMap<&str, Vec<&str>> = BASELINE_COLUMNS
+        .iter()
+        .map(|(table, columns)| (*table, columns.to_vec()))
+        .collect();
+    if tables.is_empty() || tables == baseline {
+        return Ok(());
+    }
+    let differs: Vec<&str> = tables
+        .iter()
+        .filter(|(table, columns)| baseline.get(*table) != Some(*columns))
+        .map(|(table, _)| *table)
+        .collect();
+    Err(format!(
+        "the database was not created by migrations and does not match the initial migration \
+         (differs in: {}); bring it to a migrated version and record it in _sqlx_migrations",
+        differs.join(", ")
+    ))
+}
+
+/// _sqlx_migrationsを除く、全てのテーブルの列
+async fn fetch_columns(pool: &MySqlPool) -> Result<Vec<(String, String)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT
+            table_name,
+            column_name
+        FROM
+            information_schema.columns
+        WHERE
+            table_schema = DATABASE()
+            and table_name != '_sqlx_migrations'
+        ORDER BY
+            table_name,
+            ordinal_position
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// 最後に適用したものをdown.sqlで戻し、戻したバージョンを返す。最初のバージョンは戻さない
+pub async fn revert(pool: &MySqlPool) -> Result<i64, String> {
+    let applied = applied_checksums(pool).await.map_err(|e| e.to_string())?;
+    let mut versions: Vec<i64> = applied.into_keys().collect();
+    versions.sort();
+    let latest = match versions.pop() {
+        Some(version) if version > BASELINE_VERSION => version,
+        Some(_) => return Err("the initial migration cannot be reverted".to_string()),
+        None => return Err("no migrations have been applied".to_string()),
+    };
+    let has_down = MIGRATOR
+        .iter()
+        .any(|m| m.version == latest && m.migration_type.is_down_migration());
+    if !has_down {
+        return Err(format!("migration {} has no down migration", latest));
+    }
+    let target = versions.pop().unwrap_or(0);
+    MIGRATOR
+        .undo(pool, target)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(latest)
+}
+
+/// バージョンごとの適用状況
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationState {
+    pub version: i64,
+    pub description: String,
+    pub state: State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Applied,
+    Pending,
+    /// 適用した後でファイルが書き換えられている
+    Modified,
+}
+
+impl std::fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self.state {
+            State::Applied => "applied",
+            State::Pending => "pending",
+            State::Modified => "modified",
+        };
+        write!(f, "{:04} {} {}", self.version, self.description, state)
+    }
+}
+
+pub async fn status(pool: &MySqlPool) -> Result<Vec<MigrationState>, MigrateError> {
+    let applied = applied_checksums(pool).await?;
+    Ok(states(&applied))
+}
+
+fn states(applied: &HashMap<i64, Vec<u8>>) -> Vec<MigrationState> {
+    up_migrations()
+        .map(|m| MigrationState {
+            version: m.version,
+            description: m.description.to_string(),
+            state: match applied.get(&m.version) {
+                None => State::Pending,
+                Some(checksum) if checksum[..] == m.checksum[..] => State::Applied,
+                Some(_) => State::Modified,
+            },
+        })
+        .collect()
+}
+
+async fn applied_checksums(pool: &MySqlPool) -> Result<HashMap<i64, Vec<u8>>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+    Ok(applied
+        .into_iter()
+        .map(|m| (m.version, m.checksum.into_owned()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sql_clients::test_database::{self, empty_database};
+
+    use super::*;
+
+    #[test]
+    fn test_from_args() {
+        let args = |x: &[&str]| x.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            Command::from_args(args(&["--config", "api.toml"])),
+            Ok((None, args(&["--config", "api.toml"])))
+        );
+        assert_eq!(
+            Command::from_args(args(&["migrate", "--config", "api.toml"])),
+            Ok((Some(Command::Run), args(&["--config", "api.toml"])))
+        );
+        assert_eq!(
+            Command::from_args(args(&["migrate", "revert"])),
+            Ok((Some(Command::Revert), vec![]))
+        );
+        assert!(Command::from_args(args(&["migrate", "down"])).is_err());
+    }
+
+    #[test]
+    fn test_migrations() {
+        // 全てのバージョンに戻すためのdown.sqlがある
+        for m in up_migrations() {
+            assert!(
+                MIGRATOR
+                    .iter()
+                    .any(|x| x.version == m.version && x.migration_type.is_down_migration()),
+                "{} has no down migration",
+                m.version
+            );
+        }
+        assert_eq!(up_migrations().next().unwrap().version, BASELINE_VERSION);
+
+        let applied = up_migrations()
+            .take(1)
+            .map(|m| (m.version, m.checksum.to_vec()))
+            .collect();
+        let applied = states(&applied);
+        assert_eq!(applied[0].state, State::Applied);
+        assert_eq!(applied[0].to_string(), "0001 initial applied");
+        let modified = HashMap::from([(BASELINE_VERSION, vec![0u8])]);
+        assert_eq!(states(&modified)[0].state, State::Modified);
+    }
+
+    #[test]
+    fn test_check_unmanaged() {
+        let columns = |x: &[(&str, &[&str])]| {
+            x.iter()
+                .flat_map(|(table, columns)| {
+                    columns
+                        .iter()
+                        .map(|column| (table.to_string(), column.to_string()))
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(check_unmanaged(&[]).is_ok());
+        // 以前のdb/ddl.sqlで作ったDBには、全てのバージョンを適用できる
+        assert!(check_unmanaged(&columns(BASELINE_COLUMNS)).is_ok());
+        // 後から列を加えたものは、後のバージョンを適用できないので起動しない
+        let e = check_unmanaged(&columns(&[
+            (
+                "t_user",
+                &["id", "tenant_id", "email", "created_at", "updated_at"],
+            ),
+            (
+                "t_user_slot",
+                &["id", "user_id", "start", "created_at", "updated_at"],
+            ),
+            ("t_tenant", &["id", "code"]),
+        ]))
+        .unwrap_err();
+        assert!(e.contains("differs in: t_tenant, t_user)"), "{}", e);
+    }
+
+    /// 以前のdb/ddl.sqlで作ったDB
+    const BASELINE_DUMP: &str = r#"
+        CREATE TABLE `t_user` (
+          `id` int UNSIGNED NOT NULL,
+          `email` varchar(256) CHARACTER SET utf8mb4 COLLATE utf8mb4_general_ci NOT NULL,
+          `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+          `updated_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+        ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+        INSERT INTO `t_user` (`id`, `email`) VALUES (1, 'test1@example.com'), (2, 'test2@example.com');
+        CREATE TABLE `t_user_slot` (
+          `id` int UNSIGNED NOT NULL,
+          `user_id` int UNSIGNED NOT NULL,
+          `start` datetime NOT NULL,
+          `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP,
+          `updated_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+        ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_general_ci;
+        ALTER TABLE `t_user` ADD PRIMARY KEY (`id`), ADD UNIQUE KEY `email` (`email`);
+        ALTER TABLE `t_user_slot` ADD PRIMARY KEY (`id`), ADD UNIQUE KEY `user_id` (`user_id`,`start`);
+        ALTER TABLE `t_user` MODIFY `id` int UNSIGNED NOT NULL AUTO_INCREMENT, AUTO_INCREMENT=3;
+        ALTER TABLE `t_user_slot` MODIFY `id` int UNSIGNED NOT NULL AUTO_INCREMENT;
+        ALTER TABLE `t_user_slot` ADD CONSTRAINT `t_user_slot_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `t_user` (`id`);
+        INSERT INTO `t_user_slot` (`user_id`, `start`) VALUES (1, '2023-07-20 10:00:00');
+    "#;
+
+    /// 実際のMySQLで適用と戻しを確かめる
+    #[actix_web::test]
+    #[ignore]
+    async fn test_against_mysql() {
+        use sqlx::Executor;
+        let _lock = test_database::lock().await;
+        let url = test_database::url();
+        let latest = latest_version().unwrap();
+        let all: Vec<i64> = up_migrations().map(|m| m.version).collect();
+
+        // 空のDBには全て適用でき、最初のもの以外は戻して適用し直せる
+        let pool = empty_database(&url).await;
+        assert_eq!(run(&pool).await.unwrap(), all);
+        for version in all.iter().skip(1).rev() {
+            assert_eq!(revert(&pool).await.unwrap(), *version);
+        }
+        assert!(revert(&pool).await.is_err());
+        assert_eq!(run(&pool).await.unwrap(), all[1..].to_vec());
+        assert!(run(&pool).await.unwrap().is_empty());
+
+        // 以前のdb/ddl.sqlで作ったDBは、データを残したまま最新にできる
+        let pool = empty_database(&url).await;
+        pool.execute(BASELINE_DUMP).await.unwrap();
+        assert_eq!(run(&pool).await.unwrap(), all);
+        let (users,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM t_user WHERE tenant_id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(users, 2);
+        let (slots,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM t_user_slot WHERE meeting_id IS NULL")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(slots, 1);
+        assert!(status(&pool)
+            .await
+            .unwrap()
+            .iter()
+            .all(|x| x.state == State::Applied));
+        assert_eq!(latest, *all.last().unwrap());
+
+        // 記録の無い、最初のバージョンと違うスキーマには適用しない
+        let pool = empty_database(&url).await;
+        pool.execute(BASELINE_DUMP).await.unwrap();
+        pool.execute("ALTER TABLE t_user ADD COLUMN tenant_id int UNSIGNED NOT NULL DEFAULT 1")
+            .await
+            .unwrap();
+        assert!(run(&pool).await.is_err());
+    }
+}
@@ -0,0 +1,341 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use actix_web::{
+    dev::{ServerHandle, Service, ServiceRequest, ServiceResponse},
+    rt::task::JoinHandle,
+};
+use futures::FutureExt;
+use tokio::{
+    signal::unix::{signal, Signal, SignalKind},
+    sync::watch,
+    time::Interval,
+};
+
+/// 停止の合図として受け取るシグナル。docker-composeはSIGHUPを送る
+pub struct Signals {
+    term: Signal,
+    int: Signal,
+    hup: Signal,
+}
+
+impl Signals {
+    /// 作った時点からシグナルを受け取り、既定の動作(即時終了)をしなくなる
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            term: signal(SignalKind::terminate())?,
+            int: signal(SignalKind::interrupt())?,
+            hup: signal(SignalKind::hangup())?,
+        })
+    }
+    /// いずれかのシグナルを受け取るまで待ち、その名前を返す
+    pub async fn recv(&mut self) -> &'static str {
+        tokio::select! {
+            _ = self.term.recv() => "SIGTERM",
+            _ = self.int.recv() => "SIGINT",
+            _ = self.hup.recv() => "SIGHUP",
+        }
+    }
+}
+
+/// バックグラウンドのタスクやストリームのレスポンスが停止を知るためのもの
+#[derive(Clone)]
+pub struct Shutdown {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn is_stopping(&self) -> bool {
+        *self.receiver.borrow()
+    }
+    /// 停止を伝えられるまで待つ。Coordinatorが無くなった場合は停止を伝えられることはない
+    pub async fn stopped(&mut self) {
+        if self.receiver.wait_for(|stopping| *stopping).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+    /// 次の周期まで待つ。停止を伝えられた場合はfalseを返すので、ループを抜ける。
+    /// 周期ごとの処理の途中では止めないので、処理中の配信は最後まで行う
+    pub async fn tick(&mut self, interval: &mut Interval) -> bool {
+        if self.is_stopping() {
+            return false;
+        }
+        tokio::select! {
+            _ = interval.tick() => !self.is_stopping(),
+            _ = self.receiver.changed() => false,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Shutdown {
+    /// Coordinatorを使わずに停止を伝える
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self { receiver })
+    }
+}
+
+/// 処理中のリクエストの数。`wrap_fn`でtrackを使って数える
+#[derive(Clone)]
+pub struct Requests {
+    count: Arc<watch::Sender<usize>>,
+}
+
+impl Requests {
+    fn new() -> Self {
+        Self {
+            count: Arc::new(watch::channel(0).0),
+        }
+    }
+    /// レスポンスを返すまでの間、処理中として数える
+    pub fn track<S, B>(
+        &self,
+        req: ServiceRequest,
+        srv: &S,
+    ) -> impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    {
+        self.count.send_modify(|count| *count += 1);
+        let count = self.count.clone();
+        srv.call(req).map(move |res| {
+            count.send_modify(|count| *count -= 1);
+            res
+        })
+    }
+    /// 処理中のリクエストが無くなるまで待つ
+    async fn idle(&self) {
+        let _ = self.count.subscribe().wait_for(|count| *count == 0).await;
+    }
+}
+
+/// サーバとバックグラウンドのタスクを、処理中のものを終えてから順番に止める
+pub struct Coordinator {
+    sender: watch::Sender<bool>,
+    streams: watch::Sender<bool>,
+    requests: Requests,
+    tasks: Vec<JoinHandle<()>>,
+    timeout: Duration,
+}
+
+impl Coordinator {
+    /// timeoutを過ぎても止まらない場合は待つのをやめる
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            sender: watch::channel(false).0,
+            streams: watch::channel(false).0,
+            requests: Requests::new(),
+            tasks: vec![],
+            timeout,
+        }
+    }
+    /// 処理中のリクエストを待つのに使う時間。残りはバックグラウンドのタスクとDBを閉じるのに使う
+    pub fn drain_timeout(&self) -> Duration {
+        self.timeout / 2
+    }
+    /// サーバを止める前に処理中のリクエストを待つため、リクエストを数える
+    pub fn requests(&self) -> Requests {
+        self.requests.clone()
+    }
+    /// SSEのように終わらないレスポンスは、これで停止を知って終える
+    pub fn streams(&self) -> Shutdown {
+        Shutdown {
+            receiver: self.streams.subscribe(),
+        }
+    }
+    /// 停止を伝えられるまで動くタスクを起動する
+    pub fn spawn<F, Fut>(&mut self, task: F)
+    where
+        F: FnOnce(Shutdown) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let shutdown = Shutdown {
+            receiver: self.sender.subscribe(),
+        };
+        self.tasks.push(actix_web::rt::spawn(task(shutdown)));
+    }
+    /// ストリームを終え、新しい接続を受けるのをやめて処理中のリクエストを終えてから、
+    /// バックグラウンドのタスクを止め、最後にcloseでDBの接続を閉じる。
+    /// timeoutまでに終わった場合はtrueを返す
+    pub async fn shutdown<F>(self, server: ServerHandle, close: F) -> bool
+    where
+        F: Future<Output = ()>,
+    {
+        let drain_timeout = self.drain_timeout();
+        let Self {
+            sender,
+            streams,
+            requests,
+            tasks,
+            timeout,
+        } = self;
+        let steps = async move {
+            // 終わらないストリームを待つと、タスクを止めるための時間が無くなる
+            streams.send_replace(true);
+            // 確定の途中のリクエストはトランザクションを終えるまで待つ。
+            // actix-serverはワーカーより先に接続を受けるスレッドを止め、そのときに処理中の接続を
+            // 落とすことがあるので、処理中のリクエストが無くなってからサーバを止める
+            let drain = async {
+                server.pause().await;
+                requests.idle().await;
+                server.stop(true).await;
+            };
+            if actix_web::rt::time::timeout(drain_timeout, drain)
+                .await
+                .is_err()
+            {
+                log::warn!(
+                    "requests did not finish within {}s, closing connections",
+                    drain_timeout.as_secs()
+                );
+                // 残っている接続はサーバのshutdown_timeoutを過ぎると閉じられる
+                drop(server.stop(false));
+            }
+            // リクエストが積んだイベントを配信し終えてから止める
+            sender.send_replace(true);
+            for task in tasks {
+                if let Err(e) = task.await {
+                    log::error!("background task failed: {}", e);
+                }
+            }
+            close.await;
+        };
+        actix_web::rt::time::timeout(timeout, steps).await.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpStream,
+        process::Command,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    use actix_web::{get, post, web, web::Bytes, App, HttpResponse, HttpServer};
+    use futures::StreamExt;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    #[post("/confirm")]
+    async fn confirm(started: web::Data<mpsc::UnboundedSender<()>>) -> HttpResponse {
+        started.send(()).unwrap();
+        // トランザクションの途中でシグナルを受け取る
+        actix_web::rt::time::sleep(Duration::from_millis(500)).await;
+        HttpResponse::Created().finish()
+    }
+
+    #[get("/stream")]
+    async fn slots_stream(shutdown: web::Data<Shutdown>) -> HttpResponse {
+        let mut shutdown = Shutdown::clone(&shutdown);
+        let opened =
+            futures::stream::once(async { Ok::<_, actix_web::Error>(Bytes::from("open")) });
+        // 停止を伝えられるまで何も送らない
+        let idle = futures::stream::once(async move { shutdown.stopped().await })
+            .filter_map(|_| async { None });
+        HttpResponse::Ok().streaming(opened.chain(idle))
+    }
+
+    fn send(addr: std::net::SocketAddr, request: &str) -> BufReader<TcpStream> {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        BufReader::new(stream)
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_on_signal() {
+        let mut signals = Signals::new().unwrap();
+        let mut coordinator = Coordinator::new(Duration::from_secs(5));
+        let worker_stopped = Arc::new(AtomicBool::new(false));
+        let stopped = worker_stopped.clone();
+        coordinator.spawn(|mut shutdown| async move {
+            let mut interval = actix_web::rt::time::interval(Duration::from_millis(10));
+            while shutdown.tick(&mut interval).await {}
+            stopped.store(true, Ordering::SeqCst);
+        });
+
+        let (sender, mut started) = mpsc::unbounded_channel::<()>();
+        let sender = web::Data::new(sender);
+        let streams = web::Data::new(coordinator.streams());
+        let requests = coordinator.requests();
+        let server = HttpServer::new(move || {
+            let requests = requests.clone();
+            App::new()
+                .wrap_fn(move |req, srv| requests.track(req, srv))
+                .app_data(sender.clone())
+                .app_data(streams.clone())
+                .service(confirm)
+                .service(slots_stream)
+        })
+        .workers(1)
+        .shutdown_timeout(5)
+        .disable_signals()
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        let running = actix_web::rt::spawn(server);
+
+        // 処理中のリクエストと開いたままのストリームは、サーバと別のスレッドから送る
+        let (opened, mut stream_opened) = mpsc::unbounded_channel::<()>();
+        let streaming = std::thread::spawn(move || {
+            let mut reader = send(
+                addr,
+                "GET /stream HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            );
+            let mut status = String::new();
+            reader.read_line(&mut status).unwrap();
+            opened.send(()).unwrap();
+            let mut rest = String::new();
+            reader.read_to_string(&mut rest).unwrap();
+            rest
+        });
+        stream_opened.recv().await.unwrap();
+        let request = std::thread::spawn(move || {
+            let mut status = String::new();
+            send(
+                addr,
+                "POST /confirm HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n",
+            )
+            .read_line(&mut status)
+            .unwrap();
+            status
+        });
+        started.recv().await.unwrap();
+        let status = Command::new("kill")
+            .args(["-HUP", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(signals.recv().await, "SIGHUP");
+
+        let closed = Arc::new(AtomicBool::new(false));
+        let close = {
+            let closed = closed.clone();
+            async move { closed.store(true, Ordering::SeqCst) }
+        };
+        // ストリームが開いたままでも、サーバのshutdown_timeoutまで待たずに止まる
+        let stopping = std::time::Instant::now();
+        assert!(coordinator.shutdown(handle, close).await);
+        assert!(stopping.elapsed() < Duration::from_secs(5));
+
+        // 処理中だったリクエストは最後まで処理してから止まる
+        assert!(request.join().unwrap().starts_with("HTTP/1.1 201"));
+        // ストリームは最後のチャンクを送って閉じる
+        let rest = streaming.join().unwrap();
+        assert!(rest.contains("open"));
+        assert!(rest.ends_with("0\r\n\r\n"));
+        assert!(worker_stopped.load(Ordering::SeqCst));
+        assert!(closed.load(Ordering::SeqCst));
+        running.await.unwrap().unwrap();
+
+        // 止まった後は接続を受けない
+        assert!(TcpStream::connect(addr).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::domains::{data_clients::email_client::EmailSender, email::Email, error::Error};
+
+/// SMTPでメールを送信する
+pub struct SmtpEmailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpEmailSender {
+    /// starttlsがfalseの場合は平文で接続する。ローカルのSMTPサーバ向け
+    pub fn new(
+        host: &str,
+        port: u16,
+        credentials: Option<(String, String)>,
+        starttls: bool,
+        from: &str,
+    ) -> Result<Self, Error> {
+        let builder = if starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(|e| Error::InvalidInput(format!("smtp host: {}", e)))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+        let builder = match credentials {
+            Some((user, password)) => builder.credentials(Credentials::new(user, password)),
+            None => builder,
+        };
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| Error::InvalidInput(format!("smtp from: {}", e)))?;
+        Ok(Self {
+            transport: builder.port(port).build(),
+            from,
+        })
+    }
+
+    fn to_message(&self, email: &Email) -> Result<Message, String> {
+        let to = email
+            .recipient
+            .parse::<Mailbox>()
+            .map_err(|e| e.to_string())?;
+        let content_type = ContentType::parse(&format!(
+            "text/calendar; charset=utf-8; method={}",
+            email.method
+        ))
+        .map_err(|e| e.to_string())?;
+        Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&email.subject)
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(email.body.clone()))
+                    .singlepart(
+                        Attachment::new("invite.ics".to_string())
+                            .body(email.ics.clone(), content_type),
+                    ),
+            )
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpEmailSender {
+    async fn send(&self, email: &Email) -> Result<u16, String> {
+        let message = self.to_message(email)?;
+        let response = self
+            .transport
+            .send(message)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(response.code().to_string().parse().unwrap_or(250))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    /// 1通だけ受け取って内容を返すSMTPサーバ
+    fn smtp_sink() -> (u16, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut data = String::new();
+            let mut in_data = false;
+            writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                if in_data {
+                    if line == ".\r\n" {
+                        in_data = false;
+                        writer.write_all(b"250 OK\r\n").unwrap();
+                    } else {
+                        data.push_str(&line);
+                    }
+                    continue;
+                }
+                let command = line.to_ascii_uppercase();
+                if command.starts_with("EHLO") {
+                    writer.write_all(b"250 localhost\r\n").unwrap();
+                } else if command.starts_with("DATA") {
+                    in_data = true;
+                    writer.write_all(b"354 go ahead\r\n").unwrap();
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 OK\r\n").unwrap();
+                }
+            }
+            data
+        });
+        (port, handle)
+    }
+
+    #[actix_web::test]
+    async fn test_send() {
+        let (port, handle) = smtp_sink();
+        let sender =
+            SmtpEmailSender::new("127.0.0.1", port, None, false, "noreply@example.com").unwrap();
+        let email = Email::new(
+            "test1@example.com".to_string(),
+            "Invitation".to_string(),
+            "You have been invited.".to_string(),
+            "BEGIN:VCALENDAR\r\nMETHOD:REQUEST\r\nEND:VCALENDAR\r\n".to_string(),
+            "REQUEST".to_string(),
+        );
+        assert_eq!(sender.send(&email).await, Ok(250));
+        drop(sender);
+
+        let data = handle.join().unwrap();
+        assert!(data.contains("To: test1@example.com"));
+        assert!(data.contains("Subject: Invitation"));
+        assert!(data.contains("text/calendar; charset=utf-8; method=REQUEST"));
+        assert!(data.contains("invite.ics"));
+    }
+}
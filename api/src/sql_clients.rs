@@ -1,4 +1,17 @@
+pub mod audit;
+pub mod calendars;
 pub mod data;
+pub mod emails;
 pub mod error;
+pub mod health;
+pub mod holidays;
+pub mod idempotency;
+pub mod meetings;
+pub mod out_of_offices;
+pub mod outbox;
+pub mod resources;
 pub mod sql_helper;
+#[cfg(test)]
+pub mod test_database;
 pub mod user_slots;
+pub mod webhooks;
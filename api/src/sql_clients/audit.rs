@@ -0,0 +1,142 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde_json::Value;
+use sqlx::{types::Json, FromRow, MySqlConnection, MySqlPool};
+
+use crate::domains::{
+    audit::{Actor, AuditAction, AuditEntry, AuditFilter},
+    data_clients::audit_client::AuditClient,
+    error::Error,
+};
+
+#[async_trait]
+impl AuditClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_audit_entries(
+        &self,
+        tenant: &str,
+        filter: &AuditFilter,
+    ) -> Result<Vec<AuditEntry>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u64,
+            pub actor: String,
+            pub action: String,
+            pub request_id: Option<String>,
+            pub accounts: Json<Vec<String>>,
+            pub before_state: Option<Json<Value>>,
+            pub after_state: Option<Json<Value>>,
+            pub created_at: NaiveDateTime,
+        }
+        // 指定された条件だけをWHEREに足す。bindは条件と同じ順番で行う
+        let mut conditions = vec!["tenant_id = (SELECT id FROM t_tenant WHERE code = ?)"];
+        if filter.actor.is_some() {
+            conditions.push("actor = ?");
+        }
+        if filter.account.is_some() {
+            conditions.push("JSON_CONTAINS(accounts, JSON_QUOTE(?))");
+        }
+        if filter.action.is_some() {
+            conditions.push("action = ?");
+        }
+        if filter.from.is_some() {
+            conditions.push("created_at >= ?");
+        }
+        if filter.to.is_some() {
+            conditions.push("created_at < ?");
+        }
+        let query = format!(
+            r#"
+            SELECT
+                id,
+                actor,
+                action,
+                request_id,
+                accounts,
+                before_state,
+                after_state,
+                created_at
+            FROM
+                t_audit_log
+            WHERE
+                {}
+            ORDER BY
+                id DESC
+            LIMIT ?
+            "#,
+            conditions.join("\n                and ")
+        );
+        let mut q = sqlx::query_as::<_, Row>(&query).bind(tenant);
+        if let Some(actor) = &filter.actor {
+            q = q.bind(actor);
+        }
+        if let Some(account) = &filter.account {
+            q = q.bind(account);
+        }
+        if let Some(action) = filter.action {
+            q = q.bind(action.as_str());
+        }
+        if let Some(from) = filter.from {
+            q = q.bind(from);
+        }
+        if let Some(to) = filter.to {
+            q = q.bind(to);
+        }
+        let rows = q.bind(filter.limit).fetch_all(self).await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(AuditEntry {
+                    id: row.id,
+                    actor: row.actor,
+                    action: row.action.parse::<AuditAction>().map_err(|_| {
+                        Error::DbError(format!("unknown audit action: {}", row.action))
+                    })?,
+                    request_id: row.request_id,
+                    accounts: row.accounts.0,
+                    before: row.before_state.map(|x| x.0),
+                    after: row.after_state.map(|x| x.0),
+                    created_at: row.created_at,
+                })
+            })
+            .collect()
+    }
+}
+
+/// 予約の変更と同じトランザクションで監査ログを追記する
+pub async fn insert_audit_entry(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    actor: &Actor,
+    action: AuditAction,
+    accounts: &[String],
+    before: Option<Value>,
+    after: Option<Value>,
+) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO t_audit_log (tenant_id, actor, action, request_id, accounts, before_state, after_state)
+        SELECT
+            id,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?
+        FROM t_tenant
+        WHERE
+            code = ?
+        "#,
+    )
+    .bind(&actor.account)
+    .bind(action.as_str())
+    .bind(&actor.request_id)
+    .bind(Json(accounts))
+    .bind(before.map(Json))
+    .bind(after.map(Json))
+    .bind(tenant)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use itertools::Itertools;
+use sqlx::MySqlPool;
+
+use crate::{
+    domains::{data_clients::calendar_client::CalendarClient, error::Error, slot::Slot},
+    sql_clients::sql_helper::create_place_holder,
+};
+
+/// ICSから取り込んだt_user_slotの行のsource
+const ICS_SOURCE: &str = "ics";
+
+#[async_trait]
+impl CalendarClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn replace_imported_slots(
+        &self,
+        tenant: &str,
+        account: &str,
+        free: &[Slot],
+        busy: &[Slot],
+    ) -> Result<(), Error> {
+        let mut tx = self.begin().await?;
+        let user: Option<(u32,)> = sqlx::query_as(
+            "SELECT id FROM t_user WHERE tenant_id = (SELECT id FROM t_tenant WHERE code = ?) and email = ? FOR UPDATE",
+        )
+        .bind(tenant)
+        .bind(account)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((user_id,)) = user else {
+            return Err(Error::NotFound(account.to_string()));
+        };
+
+        // 会議で確定した枠や、ICS以外で登録された枠は残す
+        sqlx::query(
+            "DELETE FROM t_user_slot WHERE user_id = ? and source = ? and meeting_id IS NULL",
+        )
+        .bind(user_id)
+        .bind(ICS_SOURCE)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM t_user_busy WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if !free.is_empty() {
+            // 既に登録されている枠とは重複しうるので無視する
+            let ins_query = format!(
+                "INSERT IGNORE INTO t_user_slot (user_id, start, source) VALUES {}",
+                free.iter()
+                    .map(|_| format!("({})", create_place_holder(3)))
+                    .join(",")
+            );
+            free.iter()
+                .fold(sqlx::query(&ins_query), |q, slot| {
+                    q.bind(user_id).bind(slot.start_date).bind(ICS_SOURCE)
+                })
+                .execute(&mut *tx)
+                .await?;
+        }
+        if !busy.is_empty() {
+            let ins_query = format!(
+                "INSERT INTO t_user_busy (user_id, start) VALUES {}",
+                busy.iter()
+                    .map(|_| format!("({})", create_place_holder(2)))
+                    .join(",")
+            );
+            busy.iter()
+                .fold(sqlx::query(&ins_query), |q, slot| {
+                    q.bind(user_id).bind(slot.start_date)
+                })
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
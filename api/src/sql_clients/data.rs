@@ -1,33 +1,45 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use itertools::Itertools;
 use serde::Deserialize;
 use sqlx::{FromRow, MySqlPool};
 
-use crate::domains::{data_clients::test_client::TestClient, error::Error};
+use crate::{
+    domains::{
+        audit::{Actor, AuditAction},
+        data_clients::{test_client::TestClient, user_slot_client::UserSlots},
+        error::Error,
+    },
+    sql_clients::audit::insert_audit_entry,
+};
 
 #[async_trait]
 impl TestClient for MySqlPool {
-    async fn dump_data(&self) -> Result<HashMap<String, String>, Error> {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn dump_data(&self, tenant: &str) -> Result<HashMap<String, String>, Error> {
         #[derive(Debug, FromRow, Deserialize)]
         struct Row {
             email: String,
             starts: Option<String>,
         }
-        let rows: Vec<Row> = sqlx::query_as!(
-            Row,
+        let rows: Vec<Row> = sqlx::query_as(
             r#"
             SELECT
                 u.email,
                 CONCAT('[', GROUP_CONCAT(DATE_FORMAT(us.start, '%Y/%m/%d %H:%i') order by us.start), ']') as starts
             FROM
                 t_user u INNER JOIN t_user_slot us ON u.id = us.user_id
+            WHERE
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
             GROUP BY
                 u.id
             ORDER BY
                 u.id
-            "#
+            "#,
         )
+        .bind(tenant)
         .fetch_all(self)
         .await?;
 
@@ -38,11 +50,111 @@ impl TestClient for MySqlPool {
         Ok(map)
     }
 
-    async fn clear_data(&self) -> Result<(), Error> {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn dump_user_slots(&self, tenant: &str) -> Result<Vec<UserSlots>, Error> {
+        #[derive(Debug, FromRow)]
+        struct Row {
+            email: String,
+            start: NaiveDateTime,
+        }
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                u.email,
+                us.start
+            FROM
+                t_user u INNER JOIN t_user_slot us ON u.id = us.user_id
+            WHERE
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            ORDER BY
+                u.id, us.start
+            "#,
+        )
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .group_by(|r| r.email.clone())
+            .into_iter()
+            .map(|(email, rows)| UserSlots::new(email, rows.map(|r| r.start).collect_vec()))
+            .collect_vec())
+    }
+
+    /// テナントの予約だけを消す。消した件数は監査ログに残す
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn clear_data(&self, tenant: &str, actor: &Actor) -> Result<(), Error> {
         let mut tx = self.begin().await?;
-        sqlx::query!("DELETE FROM t_user_slot")
-            .execute(&mut *tx)
-            .await?;
+        let accounts: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT
+                u.email
+            FROM
+                t_user u INNER JOIN t_user_slot us ON u.id = us.user_id
+            WHERE
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            ORDER BY
+                u.email
+            "#,
+        )
+        .bind(tenant)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let queries = [
+            (
+                "userSlots",
+                r#"
+                DELETE us FROM t_user_slot us INNER JOIN t_user u ON u.id = us.user_id
+                WHERE u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                "#,
+            ),
+            (
+                "busySlots",
+                r#"
+                DELETE b FROM t_user_busy b INNER JOIN t_user u ON u.id = b.user_id
+                WHERE u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                "#,
+            ),
+            (
+                "resourceSlots",
+                r#"
+                DELETE rs FROM t_resource_slot rs INNER JOIN t_resource r ON r.id = rs.resource_id
+                WHERE r.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                "#,
+            ),
+            (
+                "attendees",
+                r#"
+                DELETE a FROM t_meeting_attendee a INNER JOIN t_meeting m ON m.id = a.meeting_id
+                WHERE m.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                "#,
+            ),
+            (
+                "meetings",
+                "DELETE FROM t_meeting WHERE tenant_id = (SELECT id FROM t_tenant WHERE code = ?)",
+            ),
+        ];
+        let mut before = serde_json::Map::new();
+        for (name, query) in queries {
+            let deleted = sqlx::query(query)
+                .bind(tenant)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            before.insert(name.to_string(), deleted.into());
+        }
+        insert_audit_entry(
+            &mut tx,
+            tenant,
+            actor,
+            AuditAction::Clear,
+            &accounts.into_iter().map(|(email,)| email).collect_vec(),
+            Some(before.into()),
+            None,
+        )
+        .await?;
         tx.commit().await?;
         Ok(())
     }
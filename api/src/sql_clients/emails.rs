@@ -0,0 +1,138 @@
+use async_trait::async_trait;
+use itertools::Itertools;
+use sqlx::{FromRow, MySqlPool};
+
+use crate::{
+    domains::{
+        data_clients::email_client::EmailClient,
+        email::{Email, PendingEmail},
+        error::Error,
+        webhook::{DeliveryOutcome, DeliveryStatus},
+    },
+    sql_clients::sql_helper::create_place_holder,
+};
+
+#[async_trait]
+impl EmailClient for MySqlPool {
+    #[tracing::instrument(skip_all, err)]
+    async fn enqueue_emails(&self, emails: &[Email]) -> Result<(), Error> {
+        if emails.is_empty() {
+            return Ok(());
+        }
+        let values = vec![format!("({})", create_place_holder(6)); emails.len()].join(", ");
+        let query = format!(
+            r#"
+            INSERT INTO t_email (recipient, subject, body, ics, ics_method, status)
+            VALUES {}
+            "#,
+            values
+        );
+        emails
+            .iter()
+            .fold(sqlx::query(&query), |q, email| {
+                q.bind(&email.recipient)
+                    .bind(&email.subject)
+                    .bind(&email.body)
+                    .bind(&email.ics)
+                    .bind(&email.method)
+                    .bind(DeliveryStatus::Pending.as_str())
+            })
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, err)]
+    async fn fetch_due_emails(&self, limit: u32) -> Result<Vec<PendingEmail>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u32,
+            pub recipient: String,
+            pub subject: String,
+            pub body: String,
+            pub ics: String,
+            pub ics_method: String,
+            pub attempts: u32,
+        }
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                id,
+                recipient,
+                subject,
+                body,
+                ics,
+                ics_method,
+                attempts
+            FROM
+                t_email
+            WHERE
+                status = ?
+                and next_attempt_at <= NOW()
+            ORDER BY
+                id
+            LIMIT ?
+            "#,
+        )
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(limit)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                PendingEmail::new(
+                    r.id,
+                    Email::new(r.recipient, r.subject, r.body, r.ics, r.ics_method),
+                    r.attempts,
+                )
+            })
+            .collect_vec())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    async fn record_attempt(&self, id: u32, outcome: &DeliveryOutcome) -> Result<(), Error> {
+        let (status, status_code, error, after) = match outcome {
+            DeliveryOutcome::Succeeded { status_code } => {
+                (DeliveryStatus::Succeeded, Some(*status_code), None, 0)
+            }
+            DeliveryOutcome::Retry {
+                status_code,
+                error,
+                after,
+            } => (
+                DeliveryStatus::Pending,
+                *status_code,
+                Some(error.as_str()),
+                after.num_seconds(),
+            ),
+            DeliveryOutcome::Failed { status_code, error } => (
+                DeliveryStatus::Failed,
+                *status_code,
+                Some(error.as_str()),
+                0,
+            ),
+        };
+        sqlx::query(
+            r#"
+            UPDATE t_email
+            SET
+                status = ?,
+                attempts = attempts + 1,
+                last_status_code = ?,
+                last_error = ?,
+                next_attempt_at = DATE_ADD(NOW(), INTERVAL ? SECOND)
+            WHERE
+                id = ?
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(status_code)
+        .bind(error)
+        .bind(after)
+        .bind(id)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
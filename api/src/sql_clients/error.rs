@@ -1,7 +1,18 @@
 use crate::domains::error::Error;
 
+/// MySQLがデッドロックで中断したトランザクションのSQLSTATE
+const DEADLOCK_SQLSTATE: &str = "40001";
+
 impl From<sqlx::Error> for Error {
     fn from(err: sqlx::Error) -> Error {
+        let code = err.as_database_error().and_then(|e| e.code());
+        if code.as_deref() == Some(DEADLOCK_SQLSTATE) {
+            return Error::Deadlock(err.to_string());
+        }
+        // 一意制約に反する(MySQLの1062)。同じ名前のリソースを登録した場合など
+        if matches!(err.as_database_error(), Some(e) if e.is_unique_violation()) {
+            return Error::Conflicts;
+        }
         Error::DbError(err.to_string())
     }
 }
@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use sqlx::{Connection, FromRow, MySqlPool};
+
+use crate::domains::{
+    data_clients::health_client::HealthClient,
+    error::Error,
+    health::{MigrationStatus, PoolStatus},
+};
+
+#[async_trait]
+impl HealthClient for MySqlPool {
+    #[tracing::instrument(skip_all, err)]
+    async fn ping(&self) -> Result<(), Error> {
+        self.acquire().await?.ping().await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    async fn fetch_migration_status(&self) -> Result<MigrationStatus, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub version: i64,
+            pub success: bool,
+        }
+        let (tables,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*)
+            FROM
+                information_schema.tables
+            WHERE
+                table_schema = DATABASE()
+                and table_name = '_sqlx_migrations'
+            "#,
+        )
+        .fetch_one(self)
+        .await?;
+        if tables == 0 {
+            return Ok(MigrationStatus::Unmanaged);
+        }
+        // 失敗したものより後は適用されないので、最後のものだけを見ればよい
+        let row: Option<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                version,
+                success
+            FROM
+                _sqlx_migrations
+            ORDER BY
+                version DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(self)
+        .await?;
+        Ok(match row {
+            None => MigrationStatus::Unmanaged,
+            Some(row) => MigrationStatus::Applied {
+                version: row.version,
+                dirty: !row.success,
+            },
+        })
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        PoolStatus::new(
+            self.size(),
+            self.num_idle() as u32,
+            self.options().get_max_connections(),
+        )
+    }
+}
@@ -0,0 +1,133 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use itertools::Itertools;
+use sqlx::{FromRow, MySqlConnection, MySqlPool};
+
+use crate::{
+    domains::{
+        data_clients::holiday_client::{HolidayClient, UserHolidays},
+        error::Error,
+        holiday::Holiday,
+    },
+    sql_clients::sql_helper::create_place_holder,
+};
+
+#[async_trait]
+impl HolidayClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn import_holidays(
+        &self,
+        tenant: &str,
+        calendar: &str,
+        region: Option<String>,
+        holidays: &[Holiday],
+    ) -> Result<(), Error> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO t_holiday_calendar (tenant_id, name, region)
+            SELECT
+                id,
+                ?,
+                ?
+            FROM t_tenant
+            WHERE
+                code = ?
+            ON DUPLICATE KEY UPDATE region = VALUES(region)
+            "#,
+        )
+        .bind(calendar)
+        .bind(region)
+        .bind(tenant)
+        .execute(&mut *tx)
+        .await?;
+        let calendar_id: Option<(u32,)> = sqlx::query_as(
+            "SELECT id FROM t_holiday_calendar WHERE tenant_id = (SELECT id FROM t_tenant WHERE code = ?) and name = ? FOR UPDATE",
+        )
+        .bind(tenant)
+        .bind(calendar)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((calendar_id,)) = calendar_id else {
+            return Err(Error::NotFound(format!("tenant {}", tenant)));
+        };
+
+        // 取り込み直しても同じ結果になるように、カレンダーの休日は全て置き換える
+        sqlx::query("DELETE FROM t_holiday WHERE calendar_id = ?")
+            .bind(calendar_id)
+            .execute(&mut *tx)
+            .await?;
+        if !holidays.is_empty() {
+            let ins_query = format!(
+                "INSERT INTO t_holiday (calendar_id, date, name) VALUES {}",
+                holidays
+                    .iter()
+                    .map(|_| format!("({})", create_place_holder(3)))
+                    .join(",")
+            );
+            holidays
+                .iter()
+                .fold(sqlx::query(&ins_query), |q, holiday| {
+                    q.bind(calendar_id).bind(holiday.date).bind(&holiday.name)
+                })
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// テナント共通のカレンダーと、ユーザの地域のカレンダーの休日を取得する
+pub async fn select_user_holidays(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    accounts: &[String],
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+) -> Result<Vec<UserHolidays>, Error> {
+    #[derive(Debug, FromRow)]
+    pub struct Row {
+        pub email: String,
+        pub date: NaiveDate,
+    }
+    let query = format!(
+        r#"
+        SELECT
+            u.email,
+            h.date
+        FROM
+            t_user u
+            INNER JOIN t_holiday_calendar c
+                ON c.tenant_id = u.tenant_id and (c.region IS NULL OR c.region = u.region)
+            INNER JOIN t_holiday h ON c.id = h.calendar_id
+        WHERE
+            u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and u.email IN ({})
+            and h.date between DATE(?) and DATE(?)
+        "#,
+        create_place_holder(accounts.len())
+    );
+    let rows: Vec<Row> = accounts
+        .iter()
+        .fold(sqlx::query_as(&query).bind(tenant), |q, email| {
+            q.bind(email)
+        })
+        .bind(start_time)
+        .bind(end_time)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(accounts
+        .iter()
+        .map(|account| {
+            let dates = rows
+                .iter()
+                .filter(|r| r.email == *account)
+                .map(|r| r.date)
+                .unique()
+                .collect_vec();
+            UserHolidays::new(account.clone(), dates)
+        })
+        .collect_vec())
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use sqlx::{FromRow, MySqlPool};
+
+use crate::domains::{
+    data_clients::idempotency_client::IdempotencyClient,
+    error::Error,
+    idempotency::{
+        IdempotencyRecord, StoredResponse, IDEMPOTENCY_LEASE_SECONDS, IDEMPOTENCY_TTL_HOURS,
+    },
+};
+
+#[async_trait]
+impl IdempotencyClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn reserve_key(
+        &self,
+        tenant: &str,
+        account: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<Option<IdempotencyRecord>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub request_hash: String,
+            pub status_code: Option<u16>,
+            pub content_type: Option<String>,
+            pub response_body: Option<Vec<u8>>,
+        }
+        let mut tx = self.begin().await?;
+        // 期限の切れたキーと、レスポンスを保存できないまま残った処理中のキーは登録し直す
+        sqlx::query(
+            r#"
+            DELETE FROM t_idempotency_key
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and account = ?
+                and idempotency_key = ?
+                and (
+                    created_at < NOW() - INTERVAL ? HOUR
+                    or (status_code IS NULL and updated_at < NOW() - INTERVAL ? SECOND)
+                )
+            "#,
+        )
+        .bind(tenant)
+        .bind(account)
+        .bind(key)
+        .bind(IDEMPOTENCY_TTL_HOURS)
+        .bind(IDEMPOTENCY_LEASE_SECONDS)
+        .execute(&mut *tx)
+        .await?;
+
+        // 同じキーが同時に送られた場合は、一意制約で片方だけが登録できる
+        let inserted = sqlx::query(
+            r#"
+            INSERT IGNORE INTO t_idempotency_key (tenant_id, account, idempotency_key, request_hash)
+            SELECT
+                id,
+                ?,
+                ?,
+                ?
+            FROM t_tenant
+            WHERE
+                code = ?
+            "#,
+        )
+        .bind(account)
+        .bind(key)
+        .bind(request_hash)
+        .bind(tenant)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if inserted > 0 {
+            tx.commit().await?;
+            return Ok(None);
+        }
+
+        let row: Option<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                request_hash,
+                status_code,
+                content_type,
+                response_body
+            FROM
+                t_idempotency_key
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and account = ?
+                and idempotency_key = ?
+            "#,
+        )
+        .bind(tenant)
+        .bind(account)
+        .bind(key)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        let Some(row) = row else {
+            return Err(Error::NotFound(format!("tenant {}", tenant)));
+        };
+        let response = row.status_code.map(|status| {
+            StoredResponse::new(
+                status,
+                row.content_type,
+                row.response_body.unwrap_or_default(),
+            )
+        });
+        Ok(Some(IdempotencyRecord::new(row.request_hash, response)))
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn save_response(
+        &self,
+        tenant: &str,
+        account: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE t_idempotency_key
+            SET
+                status_code = ?,
+                content_type = ?,
+                response_body = ?
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and account = ?
+                and idempotency_key = ?
+            "#,
+        )
+        .bind(response.status)
+        .bind(&response.content_type)
+        .bind(&response.body)
+        .bind(tenant)
+        .bind(account)
+        .bind(key)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn release_key(&self, tenant: &str, account: &str, key: &str) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM t_idempotency_key
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and account = ?
+                and idempotency_key = ?
+                and status_code IS NULL
+            "#,
+        )
+        .bind(tenant)
+        .bind(account)
+        .bind(key)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
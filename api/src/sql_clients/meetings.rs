@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime};
+use itertools::Itertools;
+use sqlx::{FromRow, MySqlConnection, MySqlPool};
+
+use crate::{
+    domains::{
+        audit::{meeting_state, Actor, AuditAction},
+        data_clients::meeting_client::MeetingClient,
+        error::Error,
+        meeting::{Meeting, MeetingStatus},
+        slot::Slot,
+        webhook::{EventType, MeetingEvent},
+    },
+    sql_clients::{
+        audit::insert_audit_entry, outbox::insert_outbox_event, sql_helper::create_place_holder,
+    },
+};
+
+#[async_trait]
+impl MeetingClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_user_meetings(
+        &self,
+        tenant: &str,
+        account: &str,
+        slot_duration: Duration,
+    ) -> Result<Vec<Meeting>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u32,
+            pub start: NaiveDateTime,
+            pub status: String,
+            pub room: Option<String>,
+            pub updated_at: NaiveDateTime,
+        }
+        #[derive(Debug, FromRow)]
+        pub struct AttendeeRow {
+            pub meeting_id: u32,
+            pub email: String,
+        }
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                m.id,
+                m.start,
+                m.status,
+                r.name AS room,
+                m.updated_at
+            FROM
+                t_meeting m
+                INNER JOIN t_meeting_attendee a ON m.id = a.meeting_id
+                INNER JOIN t_user u ON u.id = a.user_id
+                LEFT JOIN t_resource r ON r.id = m.resource_id
+            WHERE
+                m.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and u.email = ?
+            ORDER BY
+                m.start, m.id
+            "#,
+        )
+        .bind(tenant)
+        .bind(account)
+        .fetch_all(self)
+        .await?;
+        if rows.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let attendees_query = format!(
+            r#"
+            SELECT
+                a.meeting_id,
+                u.email
+            FROM
+                t_meeting_attendee a INNER JOIN t_user u ON u.id = a.user_id
+            WHERE
+                a.meeting_id IN ({})
+            ORDER BY
+                u.id
+            "#,
+            create_place_holder(rows.len())
+        );
+        let attendee_rows: Vec<AttendeeRow> = rows
+            .iter()
+            .fold(sqlx::query_as(&attendees_query), |q, row| q.bind(row.id))
+            .fetch_all(self)
+            .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let attendees = attendee_rows
+                    .iter()
+                    .filter(|a| a.meeting_id == row.id)
+                    .map(|a| a.email.clone())
+                    .collect_vec();
+                Ok(Meeting::new(
+                    row.id,
+                    Slot::with_duration(row.start, slot_duration),
+                    row.status.parse::<MeetingStatus>()?,
+                    attendees,
+                    row.room,
+                    row.updated_at,
+                ))
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_meeting_attendees(&self, tenant: &str, id: u32) -> Result<Vec<String>, Error> {
+        let mut conn = self.acquire().await?;
+        let attendees = fetch_attendees(&mut conn, tenant, id).await?;
+        if attendees.is_empty() {
+            return Err(Error::NotFound(format!("meeting {}", id)));
+        }
+        Ok(attendees)
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn cancel_meeting(
+        &self,
+        tenant: &str,
+        actor: &Actor,
+        id: u32,
+        slot_duration: Duration,
+    ) -> Result<(), Error> {
+        let mut tx = self.begin().await?;
+        let meeting: Option<(NaiveDateTime, String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT
+                m.start,
+                m.status,
+                r.name
+            FROM
+                t_meeting m LEFT JOIN t_resource r ON r.id = m.resource_id
+            WHERE
+                m.id = ?
+                and m.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .bind(tenant)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((start, status, room)) = meeting else {
+            return Err(Error::NotFound(format!("meeting {}", id)));
+        };
+        if status.parse::<MeetingStatus>()? == MeetingStatus::Cancelled {
+            // 既に取り消し済み
+            return Ok(());
+        }
+
+        sqlx::query("UPDATE t_meeting SET status = ? WHERE id = ?")
+            .bind(MeetingStatus::Cancelled.as_str())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        // ICSから取り込んだ枠は空き時間に戻し、それ以外の枠は消す
+        sqlx::query(
+            "UPDATE t_user_slot SET meeting_id = NULL WHERE meeting_id = ? and source IS NOT NULL",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM t_user_slot WHERE meeting_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM t_resource_slot WHERE meeting_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        // 監査ログとイベントを同じトランザクションで書き込む
+        let attendees = fetch_attendees(&mut tx, tenant, id).await?;
+        let slot = Slot::with_duration(start, slot_duration);
+        insert_audit_entry(
+            &mut tx,
+            tenant,
+            actor,
+            AuditAction::Cancel,
+            &attendees,
+            Some(meeting_state(
+                id,
+                &slot,
+                MeetingStatus::Confirmed,
+                room.as_deref(),
+            )),
+            Some(meeting_state(
+                id,
+                &slot,
+                MeetingStatus::Cancelled,
+                room.as_deref(),
+            )),
+        )
+        .await?;
+        let event = MeetingEvent::new(
+            tenant.to_string(),
+            EventType::MeetingCancelled,
+            id,
+            slot,
+            attendees,
+            room,
+        );
+        insert_outbox_event(&mut tx, &event).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+async fn fetch_attendees(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    id: u32,
+) -> Result<Vec<String>, Error> {
+    let attendees: Vec<(String,)> = sqlx::query_as(
+        r#"
+        SELECT
+            u.email
+        FROM
+            t_meeting m
+            INNER JOIN t_meeting_attendee a ON m.id = a.meeting_id
+            INNER JOIN t_user u ON u.id = a.user_id
+        WHERE
+            m.id = ?
+            and m.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+        ORDER BY
+            u.id
+        "#,
+    )
+    .bind(id)
+    .bind(tenant)
+    .fetch_all(conn)
+    .await?;
+    Ok(attendees.into_iter().map(|(email,)| email).collect_vec())
+}
@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use itertools::Itertools;
+use sqlx::{FromRow, MySqlConnection, MySqlPool};
+
+use crate::{
+    domains::{
+        data_clients::out_of_office_client::{OutOfOfficeClient, UserOutOfOffices},
+        error::Error,
+        out_of_office::OutOfOffice,
+    },
+    sql_clients::sql_helper::create_place_holder,
+};
+
+#[derive(Debug, FromRow)]
+struct Row {
+    email: String,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    reason: String,
+}
+
+impl From<&Row> for OutOfOffice {
+    fn from(row: &Row) -> Self {
+        OutOfOffice::new(row.start, row.end, row.reason.clone())
+    }
+}
+
+#[async_trait]
+impl OutOfOfficeClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn register_out_of_office(
+        &self,
+        tenant: &str,
+        account: &str,
+        out_of_office: &OutOfOffice,
+    ) -> Result<(), Error> {
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO t_user_ooo (user_id, start, end, reason)
+            SELECT
+                id,
+                ?,
+                ?,
+                ?
+            FROM t_user u
+            WHERE
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and u.email = ?
+            "#,
+        )
+        .bind(out_of_office.start)
+        .bind(out_of_office.end)
+        .bind(&out_of_office.reason)
+        .bind(tenant)
+        .bind(account)
+        .execute(self)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            return Err(Error::NotFound(account.to_string()));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_upcoming_out_of_offices(
+        &self,
+        tenant: &str,
+        account: &str,
+    ) -> Result<Vec<OutOfOffice>, Error> {
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                u.email,
+                o.start,
+                o.end,
+                o.reason
+            FROM
+                t_user u INNER JOIN t_user_ooo o ON u.id = o.user_id
+            WHERE
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and u.email = ?
+                and o.end > NOW()
+            ORDER BY
+                o.start
+            "#,
+        )
+        .bind(tenant)
+        .bind(account)
+        .fetch_all(self)
+        .await?;
+        Ok(rows.iter().map(OutOfOffice::from).collect_vec())
+    }
+}
+
+/// start_time..end_timeと重なる不在期間を取得する
+pub async fn select_user_out_of_offices(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    accounts: &[String],
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+) -> Result<Vec<UserOutOfOffices>, Error> {
+    let query = format!(
+        r#"
+        SELECT
+            u.email,
+            o.start,
+            o.end,
+            o.reason
+        FROM
+            t_user u INNER JOIN t_user_ooo o ON u.id = o.user_id
+        WHERE
+            u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and u.email IN ({})
+            and o.start < ? and o.end > ?
+        ORDER BY
+            o.start
+        "#,
+        create_place_holder(accounts.len())
+    );
+    let rows: Vec<Row> = accounts
+        .iter()
+        .fold(sqlx::query_as(&query).bind(tenant), |q, email| {
+            q.bind(email)
+        })
+        .bind(end_time)
+        .bind(start_time)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(accounts
+        .iter()
+        .map(|account| {
+            let periods = rows
+                .iter()
+                .filter(|r| r.email == *account)
+                .map(OutOfOffice::from)
+                .collect_vec();
+            UserOutOfOffices::new(account.clone(), periods)
+        })
+        .collect_vec())
+}
@@ -0,0 +1,165 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use itertools::Itertools;
+use sqlx::{FromRow, MySqlConnection, MySqlPool};
+
+use crate::domains::{
+    data_clients::outbox_client::OutboxClient,
+    error::Error,
+    outbox::{OutboxCursor, OutboxEvent, OUTBOX_LEASE_SECONDS},
+    webhook::MeetingEvent,
+};
+
+#[async_trait]
+impl OutboxClient for MySqlPool {
+    #[tracing::instrument(level = "debug", skip_all, err, fields(sink = %sink))]
+    async fn claim_cursor(&self, sink: &str) -> Result<Option<OutboxCursor>, Error> {
+        sqlx::query(
+            r#"
+            INSERT IGNORE INTO t_outbox_cursor (sink, last_id)
+            SELECT
+                ?,
+                COALESCE(MAX(id), 0)
+            FROM
+                t_outbox
+            WHERE
+                published_at IS NOT NULL
+            "#,
+        )
+        .bind(sink)
+        .execute(self)
+        .await?;
+
+        // 期限内に確保しているプロセスがいなければ、一つのプロセスだけが更新できる
+        let token = uuid::Uuid::new_v4().to_string();
+        let claimed = sqlx::query(
+            r#"
+            UPDATE t_outbox_cursor
+            SET
+                token = ?,
+                locked_until = NOW() + INTERVAL ? SECOND
+            WHERE
+                sink = ?
+                and (locked_until IS NULL or locked_until < NOW())
+            "#,
+        )
+        .bind(&token)
+        .bind(OUTBOX_LEASE_SECONDS)
+        .bind(sink)
+        .execute(self)
+        .await?
+        .rows_affected()
+            > 0;
+        if !claimed {
+            return Ok(None);
+        }
+        let (last_id,): (u64,) =
+            sqlx::query_as("SELECT last_id FROM t_outbox_cursor WHERE sink = ? and token = ?")
+                .bind(sink)
+                .bind(&token)
+                .fetch_one(self)
+                .await?;
+        Ok(Some(OutboxCursor::new(sink.to_string(), token, last_id)))
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, err)]
+    async fn fetch_unpublished(
+        &self,
+        after_id: u64,
+        limit: u32,
+    ) -> Result<Vec<OutboxEvent>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u64,
+            pub event_type: String,
+            pub aggregate_id: u32,
+            pub payload: String,
+            pub created_at: NaiveDateTime,
+        }
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                id,
+                event_type,
+                aggregate_id,
+                payload,
+                created_at
+            FROM
+                t_outbox
+            WHERE
+                id > ?
+            ORDER BY
+                id
+            LIMIT ?
+            "#,
+        )
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| OutboxEvent::new(r.id, r.event_type, r.aggregate_id, r.payload, r.created_at))
+            .collect_vec())
+    }
+
+    #[tracing::instrument(skip_all, err, fields(sink = %cursor.sink))]
+    async fn release_cursor(&self, cursor: &OutboxCursor) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE t_outbox_cursor
+            SET
+                last_id = ?,
+                token = NULL,
+                locked_until = NULL
+            WHERE
+                sink = ?
+                and token = ?
+            "#,
+        )
+        .bind(cursor.last_id)
+        .bind(&cursor.sink)
+        .bind(&cursor.token)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    async fn mark_published(&self, last_id: u64) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE t_outbox
+            SET
+                published_at = NOW()
+            WHERE
+                id <= ?
+                and published_at IS NULL
+            "#,
+        )
+        .bind(last_id)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
+
+/// 予約の変更と同じトランザクションでイベントを書き込む。
+/// コミットされた変更には必ずイベントが残るので、配信は後から何度でもやり直せる
+pub async fn insert_outbox_event(
+    conn: &mut MySqlConnection,
+    event: &MeetingEvent,
+) -> Result<(), Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO t_outbox (event_type, aggregate_id, payload)
+        VALUES (?, ?, ?)
+        "#,
+    )
+    .bind(event.event_type.as_str())
+    .bind(event.meeting_id)
+    .bind(event.payload())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
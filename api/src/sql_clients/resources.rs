@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime};
+use itertools::Itertools;
+use sqlx::{types::Json, FromRow, MySqlConnection, MySqlPool};
+
+use crate::domains::{
+    data_clients::resource_client::{ResourceBookings, ResourceClient},
+    error::Error,
+    resource::{Resource, ResourceKind},
+    slot::Slot,
+};
+
+#[derive(Debug, FromRow)]
+struct Row {
+    id: u32,
+    name: String,
+    kind: String,
+    capacity: u32,
+    attributes: Json<Vec<String>>,
+}
+
+impl TryFrom<&Row> for Resource {
+    type Error = Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        Ok(Resource::new(
+            row.name.clone(),
+            row.kind.parse::<ResourceKind>()?,
+            row.capacity,
+            row.attributes.0.clone(),
+        ))
+    }
+}
+
+#[async_trait]
+impl ResourceClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn create_resource(&self, tenant: &str, resource: &Resource) -> Result<(), Error> {
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO t_resource (tenant_id, name, kind, capacity, attributes)
+            SELECT
+                id,
+                ?,
+                ?,
+                ?,
+                ?
+            FROM t_tenant
+            WHERE
+                code = ?
+            "#,
+        )
+        .bind(&resource.name)
+        .bind(resource.kind.as_str())
+        .bind(resource.capacity)
+        .bind(Json(&resource.attributes))
+        .bind(tenant)
+        .execute(self)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            return Err(Error::NotFound(format!("tenant {}", tenant)));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_resources(&self, tenant: &str) -> Result<Vec<Resource>, Error> {
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                id,
+                name,
+                kind,
+                capacity,
+                attributes
+            FROM
+                t_resource
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            ORDER BY
+                id
+            "#,
+        )
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        rows.iter().map(Resource::try_from).collect()
+    }
+}
+
+/// 収容人数がmin_capacity以上の会議室と、start_time..end_timeのslot_durationの枠に重なる予約を取得する
+pub async fn select_room_bookings(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    min_capacity: u32,
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+    slot_duration: Duration,
+) -> Result<Vec<ResourceBookings>, Error> {
+    #[derive(Debug, FromRow)]
+    pub struct BookedRow {
+        pub resource_id: u32,
+        pub start: NaiveDateTime,
+    }
+    let rooms: Vec<Row> = sqlx::query_as(
+        r#"
+        SELECT
+            id,
+            name,
+            kind,
+            capacity,
+            attributes
+        FROM
+            t_resource
+        WHERE
+            tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and kind = ?
+            and capacity >= ?
+        ORDER BY
+            capacity, id
+        "#,
+    )
+    .bind(tenant)
+    .bind(ResourceKind::Room.as_str())
+    .bind(min_capacity)
+    .fetch_all(&mut *conn)
+    .await?;
+    if rooms.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let booked_rows: Vec<BookedRow> = sqlx::query_as(
+        r#"
+        SELECT
+            rs.resource_id,
+            rs.start
+        FROM
+            t_resource r INNER JOIN t_resource_slot rs ON r.id = rs.resource_id
+        WHERE
+            r.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and r.kind = ?
+            and r.capacity >= ?
+            and rs.start > ? and rs.start < ?
+        "#,
+    )
+    .bind(tenant)
+    .bind(ResourceKind::Room.as_str())
+    .bind(min_capacity)
+    .bind(start_time - slot_duration)
+    .bind(end_time + slot_duration)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rooms
+        .iter()
+        .map(|room| {
+            let booked = booked_rows
+                .iter()
+                .filter(|r| r.resource_id == room.id)
+                .map(|r| r.start)
+                .collect_vec();
+            Ok(ResourceBookings::new(Resource::try_from(room)?, booked))
+        })
+        .collect()
+}
+
+/// 会議室などのリソースをロックし、参加者が入れることとslotの時間に空いていることを確認してidを返す
+pub async fn lock_free_resource(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    name: &str,
+    attendees: usize,
+    slot: &Slot,
+) -> Result<u32, Error> {
+    let locked: Option<(u32, u32)> = sqlx::query_as(
+        "SELECT id, capacity FROM t_resource WHERE tenant_id = (SELECT id FROM t_tenant WHERE code = ?) and name = ? FOR UPDATE",
+    )
+    .bind(tenant)
+    .bind(name)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((resource_id, capacity)) = locked else {
+        return Err(Error::NotFound(name.to_string()));
+    };
+    // 提案していない会議室を指定されることもあるので、確定する際にも確認する
+    if (capacity as usize) < attendees {
+        return Err(Error::Unavailable(format!(
+            "{} holds {} but {} are attending",
+            name, capacity, attendees
+        )));
+    }
+
+    let conflicts = sqlx::query(
+        r#"
+        SELECT
+            1
+        FROM
+            t_resource_slot
+        WHERE
+            resource_id = ?
+            and ABS(TIMESTAMPDIFF(MINUTE, ?, start)) < ?
+        "#,
+    )
+    .bind(resource_id)
+    .bind(slot.start_date)
+    .bind(slot.duration.num_minutes())
+    .fetch_optional(&mut *conn)
+    .await?
+    .is_some();
+    if conflicts {
+        return Err(Error::Conflicts);
+    }
+    Ok(resource_id)
+}
@@ -0,0 +1,43 @@
+use sqlx::{Executor, MySqlPool};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::migrations;
+
+/// テストは並行に実行されるので、同じDBを使うテストを一つずつにする
+static LOCK: Mutex<()> = Mutex::const_new(());
+
+/// 実際のMySQLを使うテストの間持っておくロック。中身を消してよいDBを
+/// TEST_DATABASE_URLに指定して`cargo test -- --ignored`で実行する
+pub async fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().await
+}
+
+pub fn url() -> String {
+    std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is required")
+}
+
+/// 空のDBを消して作り直す。中のテーブルは全て消える
+pub async fn empty_database(url: &str) -> MySqlPool {
+    let pool = MySqlPool::connect(url).await.unwrap();
+    let tables: Vec<(String,)> = sqlx::query_as(
+        "SELECT table_name FROM information_schema.tables WHERE table_schema = DATABASE()",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    pool.execute("SET FOREIGN_KEY_CHECKS = 0").await.unwrap();
+    for (table,) in tables {
+        pool.execute(&*format!("DROP TABLE `{}`", table))
+            .await
+            .unwrap();
+    }
+    pool.close().await;
+    MySqlPool::connect(url).await.unwrap()
+}
+
+/// 空にしたDBに全てのマイグレーションを適用する。defaultテナントにtest1〜test10@example.comがいる
+pub async fn migrated_database() -> MySqlPool {
+    let pool = empty_database(&url()).await;
+    migrations::run(&pool).await.unwrap();
+    pool
+}
@@ -1,39 +1,76 @@
 use async_trait::async_trait;
+use chrono::Duration;
 use itertools::Itertools;
-use sqlx::{types::chrono::NaiveDateTime, FromRow, MySqlPool};
+use sqlx::{types::chrono::NaiveDateTime, FromRow, MySqlConnection, MySqlPool};
 
 use crate::{
     domains::{
-        data_clients::user_slot_client::{UserSlotClient, UserSlots},
+        audit::{meeting_state, Actor, AuditAction},
+        buffer::Buffer,
+        data_clients::{
+            holiday_client::UserHolidays,
+            out_of_office_client::UserOutOfOffices,
+            resource_client::ResourceBookings,
+            user_slot_client::{UserCaps, UserSlotClient, UserSlots},
+        },
         error::Error,
+        meeting::MeetingStatus,
+        meeting_cap::{CapPeriod, MeetingCap},
+        slot::Slot,
+        webhook::{EventType, MeetingEvent},
+    },
+    sql_clients::{
+        audit::insert_audit_entry,
+        holidays::select_user_holidays,
+        out_of_offices::select_user_out_of_offices,
+        outbox::insert_outbox_event,
+        resources::{lock_free_resource, select_room_bookings},
+        sql_helper::create_place_holder,
     },
-    sql_clients::sql_helper::create_place_holder,
 };
 
 #[async_trait]
 impl UserSlotClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
     async fn fetch_user_slots(
         &self,
+        tenant: &str,
         accounts: &[String],
         start_time: NaiveDateTime,
         end_time: NaiveDateTime,
+        slot_duration: Duration,
+        default_buffer: &Buffer,
     ) -> Result<Vec<UserSlots>, Error> {
         #[derive(Debug, FromRow)]
         pub struct Row {
             pub email: String,
             pub start: Option<NaiveDateTime>,
+            pub buffer_before: Option<u32>,
+            pub buffer_after: Option<u32>,
         }
         let query = format!(
             r#"
             SELECT
                 u.email,
-                us.start
+                us.start,
+                u.buffer_before,
+                u.buffer_after
             FROM
                 t_user u LEFT JOIN t_user_slot us ON u.id = us.user_id
             WHERE
-                u.email IN ({})
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and u.email IN ({})
                 and us.start between ? and ?
-                and TIME(us.start) between '10:00:00' and '19:30:00'
+                and NOT EXISTS (
+                    SELECT
+                        1
+                    FROM
+                        t_user_busy b
+                    WHERE
+                        b.user_id = u.id
+                        and b.start < us.start + INTERVAL ? MINUTE
+                        and us.start < b.start + INTERVAL ? MINUTE
+                )
             ORDER BY
                 u.id
             "#,
@@ -41,56 +78,174 @@ impl UserSlotClient for MySqlPool {
         );
         let rows: Vec<Row> = accounts
             .iter()
-            .fold(sqlx::query_as(&query), |q, email| q.bind(email))
+            .fold(sqlx::query_as(&query).bind(tenant), |q, email| {
+                q.bind(email)
+            })
             .bind(start_time)
             .bind(end_time)
+            .bind(slot_duration.num_minutes())
+            .bind(slot_duration.num_minutes())
             .fetch_all(self)
             .await?;
 
         let slots = accounts
             .iter()
             .map(|account| {
-                let account_slots = rows
-                    .iter()
-                    .filter(|r| r.email == *account)
-                    .flat_map(|r| r.start)
-                    .collect_vec();
+                let account_rows = rows.iter().filter(|r| r.email == *account).collect_vec();
+                let account_slots = account_rows.iter().flat_map(|r| r.start).collect_vec();
+                let buffer = account_rows
+                    .first()
+                    .map(|r| {
+                        Buffer::new(
+                            r.buffer_before
+                                .map(|m| Duration::minutes(m.into()))
+                                .unwrap_or(default_buffer.before),
+                            r.buffer_after
+                                .map(|m| Duration::minutes(m.into()))
+                                .unwrap_or(default_buffer.after),
+                        )
+                    })
+                    .unwrap_or(*default_buffer);
                 UserSlots {
                     account: account.clone(),
                     slots: account_slots,
+                    buffer,
                 }
             })
             .collect_vec();
         Ok(slots)
     }
 
-    async fn confirm_user_slots(
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_user_caps(
+        &self,
+        tenant: &str,
+        accounts: &[String],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Result<Vec<UserCaps>, Error> {
+        let mut conn = self.acquire().await?;
+        select_user_caps(&mut conn, tenant, accounts, start_time, end_time).await
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_user_holidays(
         &self,
+        tenant: &str,
         accounts: &[String],
         start_time: NaiveDateTime,
-    ) -> Result<(), Error> {
+        end_time: NaiveDateTime,
+    ) -> Result<Vec<UserHolidays>, Error> {
+        let mut conn = self.acquire().await?;
+        select_user_holidays(&mut conn, tenant, accounts, start_time, end_time).await
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_user_out_of_offices(
+        &self,
+        tenant: &str,
+        accounts: &[String],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+    ) -> Result<Vec<UserOutOfOffices>, Error> {
+        let mut conn = self.acquire().await?;
+        select_user_out_of_offices(&mut conn, tenant, accounts, start_time, end_time).await
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_room_bookings(
+        &self,
+        tenant: &str,
+        min_capacity: u32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        slot_duration: Duration,
+    ) -> Result<Vec<ResourceBookings>, Error> {
+        let mut conn = self.acquire().await?;
+        select_room_bookings(
+            &mut conn,
+            tenant,
+            min_capacity,
+            start_time,
+            end_time,
+            slot_duration,
+        )
+        .await
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn confirm_user_slots(
+        &self,
+        tenant: &str,
+        actor: &Actor,
+        accounts: &[String],
+        slot: &Slot,
+        default_buffer: &Buffer,
+        room: Option<String>,
+    ) -> Result<u32, Error> {
+        let start_time = slot.start_date;
         let mut tx = self.begin().await?;
 
-        // slotを追加する対象のユーザをロック
+        // slotを追加する対象のユーザをロック。他のテナントのユーザとは会議を組めない
         let lock_query = format!(
             r#"
             SELECT
-                *
+                email
             FROM
                 t_user
             WHERE
-                email IN ({})
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and email IN ({})
             FOR UPDATE
             "#,
             create_place_holder(accounts.len())
         );
-        accounts
+        let members: Vec<(String,)> = accounts
             .iter()
-            .fold(sqlx::query(&lock_query), |q, account| q.bind(account))
-            .execute(&mut *tx)
+            .fold(sqlx::query_as(&lock_query).bind(tenant), |q, account| {
+                q.bind(account)
+            })
+            .fetch_all(&mut *tx)
             .await?;
+        if let Some(account) = accounts
+            .iter()
+            .find(|account| !members.iter().any(|(email,)| email == *account))
+        {
+            return Err(Error::Forbidden(format!(
+                "{} is not a member of {}",
+                account, tenant
+            )));
+        }
 
-        // コンフリクト確認
+        // 休日確認
+        let holiday = select_user_holidays(&mut tx, tenant, accounts, start_time, start_time)
+            .await?
+            .into_iter()
+            .find(|holidays| holidays.includes(slot));
+        if let Some(holidays) = holiday {
+            return Err(Error::Unavailable(format!(
+                "{} is a holiday for {}",
+                start_time.date(),
+                holidays.account
+            )));
+        }
+
+        // 不在期間の確認
+        let out_of_office =
+            select_user_out_of_offices(&mut tx, tenant, accounts, start_time, slot.end_date())
+                .await?
+                .into_iter()
+                .find_map(|ooo| {
+                    ooo.find_overlap(slot).map(|period| {
+                        format!("{} is out of office: {}", ooo.account, period.reason)
+                    })
+                });
+        if let Some(reason) = out_of_office {
+            return Err(Error::Unavailable(reason));
+        }
+
+        // コンフリクト確認。ユーザごとのバッファ(未設定なら全体の設定)分だけ前後に広げて確認する。
+        // 会議の無い枠(ICSから取り込んだ空き時間など)は予定ではないので見ない
         let check_conflicts_query = format!(
             r#"
         SELECT
@@ -98,18 +253,27 @@ impl UserSlotClient for MySqlPool {
         FROM
             t_user u INNER JOIN t_user_slot us ON u.id = us.user_id
         WHERE
-            u.email IN ({})
-            and ABS(TIMESTAMPDIFF(MINUTE, ?, us.start)) < 30
+            u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and u.email IN ({})
+            and us.meeting_id IS NOT NULL
+            and TIMESTAMPDIFF(MINUTE, ?, us.start) > -(? + COALESCE(u.buffer_before, ?))
+            and TIMESTAMPDIFF(MINUTE, ?, us.start) < ? + COALESCE(u.buffer_after, ?)
             "#,
             create_place_holder(accounts.len())
         );
 
         let conflicts: bool = accounts
             .iter()
-            .fold(sqlx::query(&check_conflicts_query), |q, email| {
-                q.bind(email)
-            })
+            .fold(
+                sqlx::query(&check_conflicts_query).bind(tenant),
+                |q, email| q.bind(email),
+            )
+            .bind(start_time)
+            .bind(slot.duration.num_minutes())
+            .bind(default_buffer.before.num_minutes())
             .bind(start_time)
+            .bind(slot.duration.num_minutes())
+            .bind(default_buffer.after.num_minutes())
             .fetch_optional(&mut *tx)
             .await?
             .is_some();
@@ -118,27 +282,263 @@ impl UserSlotClient for MySqlPool {
             return Err(Error::Conflicts);
         }
 
-        // slotの更新
+        // ICSから取り込んだ予定とのコンフリクト確認
+        let check_busy_query = format!(
+            r#"
+        SELECT
+            1
+        FROM
+            t_user u INNER JOIN t_user_busy b ON u.id = b.user_id
+        WHERE
+            u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and u.email IN ({})
+            and TIMESTAMPDIFF(MINUTE, ?, b.start) > -(? + COALESCE(u.buffer_before, ?))
+            and TIMESTAMPDIFF(MINUTE, ?, b.start) < ? + COALESCE(u.buffer_after, ?)
+            "#,
+            create_place_holder(accounts.len())
+        );
+        let busy: bool = accounts
+            .iter()
+            .fold(sqlx::query(&check_busy_query).bind(tenant), |q, email| {
+                q.bind(email)
+            })
+            .bind(start_time)
+            .bind(slot.duration.num_minutes())
+            .bind(default_buffer.before.num_minutes())
+            .bind(start_time)
+            .bind(slot.duration.num_minutes())
+            .bind(default_buffer.after.num_minutes())
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if busy {
+            return Err(Error::Conflicts);
+        }
+
+        // 会議数・会議時間の上限確認
+        let exceeded = select_user_caps(&mut tx, tenant, accounts, start_time, start_time)
+            .await?
+            .into_iter()
+            .find(|caps| !caps.allows(slot));
+        if let Some(caps) = exceeded {
+            return Err(Error::LimitExceeded(caps.account));
+        }
+
+        // 会議室の確認。参加者をロックした後にロックするので、ロックの順番は常に参加者→会議室になる
+        let resource_id = match &room {
+            Some(room) => {
+                Some(lock_free_resource(&mut tx, tenant, room, accounts.len(), slot).await?)
+            }
+            None => None,
+        };
+
+        // 会議の登録
+        let meeting_id = sqlx::query(
+            r#"
+            INSERT INTO t_meeting (tenant_id, start, resource_id, status)
+            SELECT
+                id,
+                ?,
+                ?,
+                ?
+            FROM t_tenant
+            WHERE
+                code = ?
+            "#,
+        )
+        .bind(start_time)
+        .bind(resource_id)
+        .bind(MeetingStatus::Confirmed.as_str())
+        .bind(tenant)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_id() as u32;
+        let attendee_query = format!(
+            r#"
+            INSERT INTO t_meeting_attendee (meeting_id, user_id)
+            SELECT
+                ?,
+                id
+            FROM t_user u
+            WHERE
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and u.email IN ({})
+            "#,
+            create_place_holder(accounts.len())
+        );
+        accounts
+            .iter()
+            .fold(
+                sqlx::query(&attendee_query).bind(meeting_id).bind(tenant),
+                |q, email| q.bind(email),
+            )
+            .execute(&mut *tx)
+            .await?;
+        if let Some(resource_id) = resource_id {
+            sqlx::query(
+                "INSERT INTO t_resource_slot (resource_id, meeting_id, start) VALUES (?, ?, ?)",
+            )
+            .bind(resource_id)
+            .bind(meeting_id)
+            .bind(start_time)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // slotの更新。同じ時刻に会議の無い枠があれば、その枠に会議を入れる
         let ins_query = format!(
             r#"
-            INSERT INTO t_user_slot (user_id, start)
+            INSERT INTO t_user_slot (user_id, start, meeting_id)
             SELECT 
                 id,
+                ?,
                 ?
             FROM t_user u
             WHERE
-                u.email IN ({})
+                u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and u.email IN ({})
+            ON DUPLICATE KEY UPDATE meeting_id = VALUES(meeting_id)
         "#,
             create_place_holder(accounts.len())
         );
         accounts
             .iter()
-            .fold(sqlx::query(&ins_query).bind(start_time), |q, email| {
-                q.bind(email)
-            })
+            .fold(
+                sqlx::query(&ins_query)
+                    .bind(start_time)
+                    .bind(meeting_id)
+                    .bind(tenant),
+                |q, email| q.bind(email),
+            )
             .execute(&mut *tx)
             .await?;
+
+        // 監査ログとイベントを同じトランザクションで書き込む
+        let after = meeting_state(meeting_id, slot, MeetingStatus::Confirmed, room.as_deref());
+        insert_audit_entry(
+            &mut tx,
+            tenant,
+            actor,
+            AuditAction::Confirm,
+            accounts,
+            None,
+            Some(after),
+        )
+        .await?;
+        let event = MeetingEvent::new(
+            tenant.to_string(),
+            EventType::MeetingConfirmed,
+            meeting_id,
+            slot.clone(),
+            accounts.to_vec(),
+            room,
+        );
+        insert_outbox_event(&mut tx, &event).await?;
         tx.commit().await?;
-        Ok(())
+        Ok(meeting_id)
+    }
+}
+
+/// 上限が設定されているユーザについて、上限と週単位で数えるのに必要な予定を取得する。
+/// 会議の無い枠(ICSから取り込んだ空き時間など)は数えない
+async fn select_user_caps(
+    conn: &mut MySqlConnection,
+    tenant: &str,
+    accounts: &[String],
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+) -> Result<Vec<UserCaps>, Error> {
+    #[derive(Debug, FromRow)]
+    pub struct CapRow {
+        pub email: String,
+        pub period: String,
+        pub max_meetings: Option<u32>,
+        pub max_minutes: Option<u32>,
     }
+    #[derive(Debug, FromRow)]
+    pub struct BookedRow {
+        pub email: String,
+        pub start: NaiveDateTime,
+    }
+    let caps_query = format!(
+        r#"
+        SELECT
+            u.email,
+            c.period,
+            c.max_meetings,
+            c.max_minutes
+        FROM
+            t_user u INNER JOIN t_user_cap c ON u.id = c.user_id
+        WHERE
+            u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and u.email IN ({})
+        "#,
+        create_place_holder(accounts.len())
+    );
+    let cap_rows: Vec<CapRow> = accounts
+        .iter()
+        .fold(sqlx::query_as(&caps_query).bind(tenant), |q, email| {
+            q.bind(email)
+        })
+        .fetch_all(&mut *conn)
+        .await?;
+    if cap_rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // 日単位の期間は週単位の期間に含まれるので、週単位で取得すれば足りる
+    let from = CapPeriod::Week.range_of(start_time.date()).start;
+    let to = CapPeriod::Week.range_of(end_time.date()).end;
+    let capped_accounts = cap_rows
+        .iter()
+        .map(|r| r.email.clone())
+        .unique()
+        .collect_vec();
+    let booked_query = format!(
+        r#"
+        SELECT
+            u.email,
+            us.start
+        FROM
+            t_user u INNER JOIN t_user_slot us ON u.id = us.user_id
+        WHERE
+            u.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            and u.email IN ({})
+            and us.meeting_id IS NOT NULL
+            and us.start >= ? and us.start < ?
+        "#,
+        create_place_holder(capped_accounts.len())
+    );
+    let booked_rows: Vec<BookedRow> = capped_accounts
+        .iter()
+        .fold(sqlx::query_as(&booked_query).bind(tenant), |q, email| {
+            q.bind(email)
+        })
+        .bind(from)
+        .bind(to)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    capped_accounts
+        .into_iter()
+        .map(|account| {
+            let caps = cap_rows
+                .iter()
+                .filter(|r| r.email == account)
+                .map(|r| {
+                    Ok(MeetingCap::new(
+                        r.period.parse()?,
+                        r.max_meetings,
+                        r.max_minutes,
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let booked = booked_rows
+                .iter()
+                .filter(|r| r.email == account)
+                .map(|r| r.start)
+                .collect_vec();
+            Ok(UserCaps::new(account, caps, booked))
+        })
+        .collect()
 }
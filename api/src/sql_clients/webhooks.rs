@@ -0,0 +1,278 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use itertools::Itertools;
+use sqlx::{types::Json, FromRow, MySqlPool};
+
+use crate::{
+    domains::{
+        data_clients::webhook_client::WebhookClient,
+        error::Error,
+        webhook::{
+            Delivery, DeliveryOutcome, DeliveryStatus, PendingDelivery, Webhook,
+            DELIVERY_LEASE_SECONDS,
+        },
+    },
+    sql_clients::sql_helper::create_place_holder,
+};
+
+#[async_trait]
+impl WebhookClient for MySqlPool {
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn register_webhook(
+        &self,
+        tenant: &str,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> Result<u32, Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO t_webhook (tenant_id, url, secret, events, active)
+            SELECT
+                id,
+                ?,
+                ?,
+                ?,
+                1
+            FROM t_tenant
+            WHERE
+                code = ?
+            "#,
+        )
+        .bind(url)
+        .bind(secret)
+        .bind(Json(events))
+        .bind(tenant)
+        .execute(self)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("tenant {}", tenant)));
+        }
+        Ok(result.last_insert_id() as u32)
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_webhooks(&self, tenant: &str) -> Result<Vec<Webhook>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u32,
+            pub url: String,
+            pub events: Json<Vec<String>>,
+            pub active: bool,
+        }
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                id,
+                url,
+                events,
+                active
+            FROM
+                t_webhook
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            ORDER BY
+                id
+            "#,
+        )
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| Webhook::new(r.id, r.url, r.events.0, r.active))
+            .collect_vec())
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn fetch_deliveries(
+        &self,
+        tenant: &str,
+        webhook_id: u32,
+    ) -> Result<Vec<Delivery>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u32,
+            pub event_type: String,
+            pub status: String,
+            pub attempts: u32,
+            pub last_status_code: Option<u16>,
+            pub last_error: Option<String>,
+            pub created_at: NaiveDateTime,
+        }
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                d.id,
+                d.event_type,
+                d.status,
+                d.attempts,
+                d.last_status_code,
+                d.last_error,
+                d.created_at
+            FROM
+                t_webhook_delivery d INNER JOIN t_webhook w ON w.id = d.webhook_id
+            WHERE
+                d.webhook_id = ?
+                and w.tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+            ORDER BY
+                d.id DESC
+            LIMIT 100
+            "#,
+        )
+        .bind(webhook_id)
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                Delivery::new(
+                    r.id,
+                    r.event_type,
+                    r.status,
+                    r.attempts,
+                    r.last_status_code,
+                    r.last_error,
+                    r.created_at,
+                )
+            })
+            .collect_vec())
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, err)]
+    async fn fetch_due_deliveries(&self, limit: u32) -> Result<Vec<PendingDelivery>, Error> {
+        #[derive(Debug, FromRow)]
+        pub struct Row {
+            pub id: u32,
+            pub url: String,
+            pub secret: String,
+            pub payload: String,
+            pub attempts: u32,
+        }
+        let mut tx = self.begin().await?;
+        // 他のプロセスが確保中の配信は飛ばす
+        let rows: Vec<Row> = sqlx::query_as(
+            r#"
+            SELECT
+                d.id,
+                w.url,
+                w.secret,
+                d.payload,
+                d.attempts
+            FROM
+                t_webhook_delivery d INNER JOIN t_webhook w ON w.id = d.webhook_id
+            WHERE
+                d.status = ?
+                and d.next_attempt_at <= NOW()
+            ORDER BY
+                d.id
+            LIMIT ?
+            FOR UPDATE OF d SKIP LOCKED
+            "#,
+        )
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+        if !rows.is_empty() {
+            // 送信予定時刻を先に延ばして確保する。結果を記録すると次の予定時刻で上書きされる
+            let lease_query = format!(
+                "UPDATE t_webhook_delivery SET next_attempt_at = NOW() + INTERVAL ? SECOND WHERE id IN ({})",
+                create_place_holder(rows.len())
+            );
+            rows.iter()
+                .fold(
+                    sqlx::query(&lease_query).bind(DELIVERY_LEASE_SECONDS),
+                    |q, r| q.bind(r.id),
+                )
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| PendingDelivery::new(r.id, r.url, r.secret, r.payload, r.attempts))
+            .collect_vec())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    async fn record_attempt(&self, id: u32, outcome: &DeliveryOutcome) -> Result<(), Error> {
+        let (status, status_code, error, after) = match outcome {
+            DeliveryOutcome::Succeeded { status_code } => {
+                (DeliveryStatus::Succeeded, Some(*status_code), None, 0)
+            }
+            DeliveryOutcome::Retry {
+                status_code,
+                error,
+                after,
+            } => (
+                DeliveryStatus::Pending,
+                *status_code,
+                Some(error.as_str()),
+                after.num_seconds(),
+            ),
+            DeliveryOutcome::Failed { status_code, error } => (
+                DeliveryStatus::Failed,
+                *status_code,
+                Some(error.as_str()),
+                0,
+            ),
+        };
+        sqlx::query(
+            r#"
+            UPDATE t_webhook_delivery
+            SET
+                status = ?,
+                attempts = attempts + 1,
+                last_status_code = ?,
+                last_error = ?,
+                next_attempt_at = DATE_ADD(NOW(), INTERVAL ? SECOND)
+            WHERE
+                id = ?
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(status_code)
+        .bind(error)
+        .bind(after)
+        .bind(id)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err, fields(tenant = %tenant))]
+    async fn enqueue_event(
+        &self,
+        tenant: &str,
+        event_type: &str,
+        payload: &str,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO t_webhook_delivery (webhook_id, event_type, payload, status, next_attempt_at)
+            SELECT
+                id,
+                ?,
+                ?,
+                ?,
+                NOW()
+            FROM
+                t_webhook
+            WHERE
+                tenant_id = (SELECT id FROM t_tenant WHERE code = ?)
+                and active = 1
+                and (JSON_LENGTH(events) = 0 or JSON_CONTAINS(events, JSON_QUOTE(?)))
+            "#,
+        )
+        .bind(event_type)
+        .bind(payload)
+        .bind(DeliveryStatus::Pending.as_str())
+        .bind(tenant)
+        .bind(event_type)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
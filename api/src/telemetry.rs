@@ -0,0 +1,52 @@
+use opentelemetry::{trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::config::LogConfig;
+
+/// OTLPに送るspanに付けるサービス名
+const SERVICE_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// ログとspanの出力先を設定する。logクレートのログもspanの中のイベントとして出力する。
+/// OTLPに送る場合は、終了時にshutdownを呼んで残りのspanを送る
+pub fn init(config: &LogConfig) -> Result<Option<TracerProvider>, String> {
+    // RUST_LOGが設定されている場合はそちらを使う
+    let filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(&config.level));
+    let filter = filter.map_err(|e| format!("invalid log level: {}", e))?;
+    let fmt = match config.format.as_str() {
+        "text" => tracing_subscriber::fmt::layer().boxed(),
+        _ => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+    let provider = config
+        .otlp_endpoint
+        .as_ref()
+        .map(|endpoint| {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint)
+                .build()
+                .map_err(|e| format!("failed to create OTLP exporter: {}", e))?;
+            Ok::<_, String>(
+                TracerProvider::builder()
+                    .with_batch_exporter(exporter, runtime::TokioCurrentThread)
+                    .with_resource(Resource::new([KeyValue::new("service.name", SERVICE_NAME)]))
+                    .build(),
+            )
+        })
+        .transpose()?;
+    let otlp = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .with(otlp)
+        .try_init()
+        .map_err(|e| e.to_string())?;
+    Ok(provider)
+}
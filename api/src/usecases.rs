@@ -1,2 +1,13 @@
+pub mod audit;
+pub mod calendars;
 pub mod data;
+pub mod emails;
+pub mod health;
+pub mod holidays;
+pub mod idempotency;
+pub mod meetings;
+pub mod out_of_offices;
+pub mod outbox;
+pub mod resources;
 pub mod user_slots;
+pub mod webhooks;
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    audit::{AuditEntry, AuditFilter},
+    data_clients::audit_client::AuditClient,
+    error::Error,
+};
+
+pub struct AuditUsecase {
+    pool: Arc<dyn AuditClient>,
+}
+impl AuditUsecase {
+    pub fn new(pool: Arc<dyn AuditClient>) -> Self {
+        Self { pool }
+    }
+    /// 条件に合う監査ログを新しい順に返す
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn search(
+        &self,
+        tenant: &str,
+        filter: AuditFilter,
+    ) -> Result<Vec<AuditEntry>, Error> {
+        filter.validate()?;
+        self.pool.fetch_audit_entries(tenant, &filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domains::{
+        audit::{AuditAction, DEFAULT_AUDIT_LIMIT},
+        data_clients::audit_client::MockAuditClient,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let mut mock = MockAuditClient::new();
+        mock.expect_fetch_audit_entries()
+            .withf(|tenant, filter| {
+                tenant == "default"
+                    && filter.account.as_deref() == Some("test1@example.com")
+                    && filter.action == Some(AuditAction::Cancel)
+            })
+            .times(1)
+            .returning(|_, _| Ok(vec![]));
+
+        let uc = AuditUsecase::new(Arc::new(mock));
+        let filter = |limit| {
+            AuditFilter::new(
+                None,
+                Some("test1@example.com".to_string()),
+                Some(AuditAction::Cancel),
+                None,
+                None,
+                limit,
+            )
+        };
+        let entries =
+            futures::executor::block_on(uc.search("default", filter(DEFAULT_AUDIT_LIMIT))).unwrap();
+        assert!(entries.is_empty());
+
+        let ret = futures::executor::block_on(uc.search("default", filter(0)));
+        assert!(matches!(ret, Err(Error::InvalidInput(_))));
+    }
+}
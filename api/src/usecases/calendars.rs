@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+
+use crate::domains::{
+    calendar_import::{import_from_ics, CalendarImport},
+    data_clients::calendar_client::CalendarClient,
+    error::Error,
+    slot::Slot,
+};
+
+pub struct CalendarUsecase {
+    pool: Arc<dyn CalendarClient>,
+    /// 取り込んだ時間をこの長さの枠に分ける
+    slot_duration: Duration,
+}
+impl CalendarUsecase {
+    pub fn new(pool: Arc<dyn CalendarClient>) -> Self {
+        Self {
+            pool,
+            slot_duration: Slot::default_duration(),
+        }
+    }
+    pub fn with_slot_duration(mut self, slot_duration: Duration) -> Self {
+        self.slot_duration = slot_duration;
+        self
+    }
+    /// ICSの空き時間と予定をaccountに取り込む。同じICSを何度取り込んでも結果は変わらない
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn import_ics(
+        &self,
+        tenant: &str,
+        account: &str,
+        ics: &str,
+    ) -> Result<CalendarImport, Error> {
+        let import = import_from_ics(ics, self.slot_duration)?;
+        self.pool
+            .replace_imported_slots(tenant, account, &import.free, &import.busy)
+            .await?;
+        Ok(import)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domains::data_clients::calendar_client::MockCalendarClient;
+
+    use super::*;
+
+    #[test]
+    fn test_import_ics() {
+        let mut mock = MockCalendarClient::new();
+        mock.expect_replace_imported_slots()
+            .withf(|tenant, account, free, busy| {
+                tenant == "default"
+                    && account == "test1@example.com"
+                    && free.is_empty()
+                    && busy.len() == 2
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let uc = CalendarUsecase::new(Arc::new(mock));
+        let ics = "BEGIN:VCALENDAR\n\
+                   BEGIN:VEVENT\n\
+                   DTSTART:20200101T100000\n\
+                   DTEND:20200101T110000\n\
+                   END:VEVENT\n\
+                   END:VCALENDAR\n";
+        let import =
+            futures::executor::block_on(uc.import_ics("default", "test1@example.com", ics))
+                .unwrap();
+        assert_eq!(import.busy.len(), 2);
+        assert!(import.skipped.is_empty());
+    }
+}
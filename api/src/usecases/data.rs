@@ -1,6 +1,10 @@
 use std::{collections::HashMap, sync::Arc};
 
-use crate::domains::{data_clients::test_client::TestClient, error::Error};
+use crate::domains::{
+    audit::Actor,
+    data_clients::{test_client::TestClient, user_slot_client::UserSlots},
+    error::Error,
+};
 
 pub struct DataUsecase {
     pool: Arc<dyn TestClient>,
@@ -9,10 +13,16 @@ impl DataUsecase {
     pub fn new(pool: Arc<dyn TestClient>) -> Self {
         Self { pool }
     }
-    pub async fn dump(&self) -> Result<HashMap<String, String>, Error> {
-        self.pool.dump_data().await
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn dump(&self, tenant: &str) -> Result<HashMap<String, String>, Error> {
+        self.pool.dump_data(tenant).await
     }
-    pub async fn clear(&self) -> Result<(), Error> {
-        self.pool.clear_data().await
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn dump_user_slots(&self, tenant: &str) -> Result<Vec<UserSlots>, Error> {
+        self.pool.dump_user_slots(tenant).await
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn clear(&self, tenant: &str, actor: &Actor) -> Result<(), Error> {
+        self.pool.clear_data(tenant, actor).await
     }
 }
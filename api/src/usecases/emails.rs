@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::email_client::{EmailClient, EmailSender},
+    error::Error,
+};
+
+/// 1回の実行で送信するメールの最大数
+const EMAIL_BATCH_SIZE: u32 = 50;
+
+pub struct EmailUsecase {
+    pool: Arc<dyn EmailClient>,
+}
+impl EmailUsecase {
+    pub fn new(pool: Arc<dyn EmailClient>) -> Self {
+        Self { pool }
+    }
+    /// 送信予定時刻を過ぎたメールを送信して結果を記録し、送信した数を返す
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn deliver_pending(&self, sender: &dyn EmailSender) -> Result<usize, Error> {
+        let emails = self.pool.fetch_due_emails(EMAIL_BATCH_SIZE).await?;
+        for pending in &emails {
+            let result = sender.send(&pending.email).await;
+            let outcome = pending.outcome(result);
+            self.pool.record_attempt(pending.id, &outcome).await?;
+        }
+        Ok(emails.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::domains::{
+        data_clients::email_client::{MockEmailClient, MockEmailSender},
+        email::{Email, PendingEmail},
+        webhook::DeliveryOutcome,
+    };
+
+    use super::*;
+
+    fn to_email(recipient: &str) -> Email {
+        Email::new(
+            recipient.to_string(),
+            "Invitation".to_string(),
+            "body".to_string(),
+            "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n".to_string(),
+            "REQUEST".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_deliver_pending() {
+        let mut mock = MockEmailClient::new();
+        mock.expect_fetch_due_emails().times(1).returning(|_| {
+            Ok(vec![
+                PendingEmail::new(1, to_email("test1@example.com"), 0),
+                PendingEmail::new(2, to_email("test2@example.com"), 0),
+            ])
+        });
+        mock.expect_record_attempt()
+            .withf(|id, outcome| {
+                *id == 1 && *outcome == DeliveryOutcome::Succeeded { status_code: 250 }
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock.expect_record_attempt()
+            .withf(|id, outcome| {
+                *id == 2
+                    && *outcome
+                        == DeliveryOutcome::Retry {
+                            status_code: None,
+                            error: "connection refused".to_string(),
+                            after: Duration::seconds(30),
+                        }
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut sender = MockEmailSender::new();
+        sender
+            .expect_send()
+            .withf(|email| email.recipient == "test1@example.com")
+            .times(1)
+            .returning(|_| Ok(250));
+        sender
+            .expect_send()
+            .withf(|email| email.recipient == "test2@example.com")
+            .times(1)
+            .returning(|_| Err("connection refused".to_string()));
+
+        let uc = EmailUsecase::new(Arc::new(mock));
+        let count = futures::executor::block_on(uc.deliver_pending(&sender)).unwrap();
+        assert_eq!(count, 2);
+    }
+}
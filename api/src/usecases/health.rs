@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::domains::{
+    data_clients::health_client::HealthClient,
+    error::Error,
+    health::{PoolStatus, Readiness},
+};
+
+/// DBの確認にかける時間の上限。接続を待つ間もロードバランサから外れるように短くする
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct HealthUsecase {
+    pool: Arc<dyn HealthClient>,
+    required_migration: Option<i64>,
+}
+impl HealthUsecase {
+    pub fn new(pool: Arc<dyn HealthClient>) -> Self {
+        Self {
+            pool,
+            required_migration: None,
+        }
+    }
+    /// このバージョンまでスキーマの変更が適用されるまでは、トラフィックを受けない
+    pub fn with_required_migration(mut self, version: Option<i64>) -> Self {
+        self.required_migration = version;
+        self
+    }
+    pub fn pool_status(&self) -> PoolStatus {
+        self.pool.pool_status()
+    }
+    /// DBに接続できて、スキーマの変更を途中で失敗せずに適用し終えているかを確認する
+    #[tracing::instrument(skip_all)]
+    pub async fn readiness(&self) -> Readiness {
+        let check = async {
+            self.pool.ping().await?;
+            self.pool.fetch_migration_status().await
+        };
+        let result = match actix_web::rt::time::timeout(CHECK_TIMEOUT, check).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::DbError(format!(
+                "no connection within {}s",
+                CHECK_TIMEOUT.as_secs()
+            ))),
+        };
+        let pool = self.pool.pool_status();
+        match result {
+            Ok(migrations) => Readiness {
+                database: Ok(()),
+                migrations: Some(migrations),
+                required_migration: self.required_migration,
+                pool,
+            },
+            Err(e) => Readiness {
+                database: Err(e.to_string()),
+                migrations: None,
+                required_migration: self.required_migration,
+                pool,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::{data_clients::health_client::MockHealthClient, health::MigrationStatus};
+
+    #[actix_web::test]
+    async fn test_readiness() {
+        let mut mock = MockHealthClient::new();
+        mock.expect_ping().times(1).returning(|| Ok(()));
+        mock.expect_fetch_migration_status()
+            .times(1)
+            .returning(|| Ok(MigrationStatus::Unmanaged));
+        mock.expect_pool_status()
+            .returning(|| PoolStatus::new(2, 1, 10));
+
+        let readiness = HealthUsecase::new(Arc::new(mock)).readiness().await;
+        assert!(readiness.is_ready());
+        assert_eq!(readiness.migrations, Some(MigrationStatus::Unmanaged));
+        assert_eq!(readiness.pool.in_use(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_readiness_without_database() {
+        let mut mock = MockHealthClient::new();
+        mock.expect_ping()
+            .times(1)
+            .returning(|| Err(Error::DbError("connection refused".to_string())));
+        mock.expect_fetch_migration_status().times(0);
+        mock.expect_pool_status()
+            .returning(|| PoolStatus::new(0, 0, 10));
+
+        let readiness = HealthUsecase::new(Arc::new(mock)).readiness().await;
+        assert!(!readiness.is_ready());
+        assert_eq!(
+            readiness.database,
+            Err("DbError: connection refused".to_string())
+        );
+    }
+}
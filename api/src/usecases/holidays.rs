@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::holiday_client::HolidayClient, error::Error, holiday::holidays_from_ics,
+};
+
+pub struct HolidayUsecase {
+    pool: Arc<dyn HolidayClient>,
+}
+impl HolidayUsecase {
+    pub fn new(pool: Arc<dyn HolidayClient>) -> Self {
+        Self { pool }
+    }
+    /// ICSファイルの内容でカレンダーを置き換え、取り込んだ休日の数を返す
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn import(
+        &self,
+        tenant: &str,
+        calendar: &str,
+        region: Option<String>,
+        ics: &str,
+    ) -> Result<usize, Error> {
+        let holidays = holidays_from_ics(ics)?;
+        self.pool
+            .import_holidays(tenant, calendar, region, &holidays)
+            .await?;
+        Ok(holidays.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domains::data_clients::holiday_client::MockHolidayClient;
+
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let mut mock = MockHolidayClient::new();
+        mock.expect_import_holidays()
+            .withf(|tenant, calendar, region, holidays| {
+                tenant == "default"
+                    && calendar == "jp"
+                    && region.as_deref() == Some("tokyo")
+                    && holidays.len() == 2
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let uc = HolidayUsecase::new(Arc::new(mock));
+        let ics = "BEGIN:VCALENDAR\n\
+                   BEGIN:VEVENT\n\
+                   DTSTART;VALUE=DATE:20200101\n\
+                   DTEND;VALUE=DATE:20200103\n\
+                   SUMMARY:New Year\n\
+                   END:VEVENT\n\
+                   END:VCALENDAR\n";
+        let count =
+            futures::executor::block_on(uc.import("default", "jp", Some("tokyo".to_string()), ics))
+                .unwrap();
+        assert_eq!(count, 2);
+    }
+}
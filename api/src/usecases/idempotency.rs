@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::idempotency_client::IdempotencyClient,
+    error::Error,
+    idempotency::{validate_key, StoredResponse},
+};
+
+/// キーを登録した結果
+#[derive(Debug, PartialEq)]
+pub enum Reservation {
+    /// 初めてのキーなので処理する
+    New,
+    /// 処理済みのキーなので、保存したレスポンスをそのまま返す
+    Replay(StoredResponse),
+}
+
+pub struct IdempotencyUsecase {
+    pool: Arc<dyn IdempotencyClient>,
+}
+impl IdempotencyUsecase {
+    pub fn new(pool: Arc<dyn IdempotencyClient>) -> Self {
+        Self { pool }
+    }
+    /// キーを処理中として登録する。処理が終わったらfinishを呼ぶ
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn reserve(
+        &self,
+        tenant: &str,
+        account: &str,
+        key: &str,
+        request_hash: &str,
+    ) -> Result<Reservation, Error> {
+        validate_key(key)?;
+        match self
+            .pool
+            .reserve_key(tenant, account, key, request_hash)
+            .await?
+        {
+            None => Ok(Reservation::New),
+            Some(record) => Ok(Reservation::Replay(record.replay(request_hash)?)),
+        }
+    }
+    /// レスポンスを保存する。サーバのエラーの場合はキーを消して再送できるようにする
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn finish(
+        &self,
+        tenant: &str,
+        account: &str,
+        key: &str,
+        response: &StoredResponse,
+    ) -> Result<(), Error> {
+        if response.is_replayable() {
+            self.pool
+                .save_response(tenant, account, key, response)
+                .await
+        } else {
+            self.pool.release_key(tenant, account, key).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::domains::{
+        data_clients::idempotency_client::MockIdempotencyClient, idempotency::IdempotencyRecord,
+    };
+
+    #[test]
+    fn test_reserve() {
+        let mut mock = MockIdempotencyClient::new();
+        mock.expect_reserve_key()
+            .withf(|_, _, key, _| key == "new")
+            .times(1)
+            .returning(|_, _, _, _| Ok(None));
+        mock.expect_reserve_key()
+            .withf(|_, _, key, _| key == "done")
+            .times(2)
+            .returning(|_, _, _, _| {
+                Ok(Some(IdempotencyRecord::new(
+                    "hash".to_string(),
+                    Some(StoredResponse::new(201, None, vec![])),
+                )))
+            });
+
+        let uc = IdempotencyUsecase::new(Arc::new(mock));
+        let reserve = |key| block_on(uc.reserve("default", "test1@example.com", key, "hash"));
+        assert_eq!(reserve("new").unwrap(), Reservation::New);
+        assert_eq!(
+            reserve("done").unwrap(),
+            Reservation::Replay(StoredResponse::new(201, None, vec![]))
+        );
+        assert!(matches!(reserve(""), Err(Error::InvalidInput(_))));
+
+        let ret = block_on(uc.reserve("default", "test1@example.com", "done", "other"));
+        assert!(matches!(ret, Err(Error::KeyReused(_))));
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut mock = MockIdempotencyClient::new();
+        mock.expect_save_response()
+            .withf(|_, _, _, response| response.status == 409)
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+        mock.expect_release_key()
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let uc = IdempotencyUsecase::new(Arc::new(mock));
+        let finish = |status| {
+            let response = StoredResponse::new(status, None, vec![]);
+            block_on(uc.finish("default", "test1@example.com", "key", &response))
+        };
+        assert!(finish(409).is_ok());
+        assert!(finish(500).is_ok());
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+
+use crate::domains::{
+    audit::Actor, auth::Principal, data_clients::meeting_client::MeetingClient, error::Error,
+    meeting::meetings_to_ics, slot::Slot,
+};
+
+pub struct MeetingUsecase {
+    pool: Arc<dyn MeetingClient>,
+    slot_duration: Duration,
+}
+impl MeetingUsecase {
+    pub fn new(pool: Arc<dyn MeetingClient>) -> Self {
+        Self {
+            pool,
+            slot_duration: Slot::default_duration(),
+        }
+    }
+    pub fn with_slot_duration(mut self, slot_duration: Duration) -> Self {
+        self.slot_duration = slot_duration;
+        self
+    }
+    /// accountの会議をiCalendar形式で返す
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn export_ics(&self, tenant: &str, account: &str) -> Result<String, Error> {
+        let meetings = self
+            .pool
+            .fetch_user_meetings(tenant, account, self.slot_duration)
+            .await?;
+        Ok(meetings_to_ics(&meetings))
+    }
+    /// 参加者か管理者だけが取り消せる
+    #[tracing::instrument(skip_all)]
+    pub async fn cancel(&self, id: u32, principal: &Principal, actor: &Actor) -> Result<(), Error> {
+        if !principal.is_admin() {
+            let attendees = self
+                .pool
+                .fetch_meeting_attendees(principal.tenant(), id)
+                .await?;
+            principal.authorize_attendees(&attendees)?;
+        }
+        self.pool
+            .cancel_meeting(principal.tenant(), actor, id, self.slot_duration)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+    use crate::domains::data_clients::meeting_client::MockMeetingClient;
+
+    #[test]
+    fn test_cancel_by_other_user() {
+        let mut mock = MockMeetingClient::new();
+        mock.expect_fetch_meeting_attendees()
+            .times(1)
+            .returning(|_, _| Ok(vec!["test1@example.com".to_string()]));
+        mock.expect_cancel_meeting().times(0);
+
+        let uc = MeetingUsecase::new(Arc::new(mock));
+        let principal = Principal::new("test2@example.com".to_string(), vec![]);
+        let actor = Actor::new(principal.account.clone(), None);
+        let ret = block_on(uc.cancel(1, &principal, &actor));
+        assert!(matches!(ret, Err(Error::Forbidden(_))));
+    }
+
+    #[test]
+    fn test_cancel_by_admin() {
+        let mut mock = MockMeetingClient::new();
+        mock.expect_fetch_meeting_attendees().times(0);
+        mock.expect_cancel_meeting()
+            .withf(|tenant, actor, id, _| {
+                tenant == "default" && actor.account == "admin@example.com" && *id == 1
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let uc = MeetingUsecase::new(Arc::new(mock));
+        let principal = Principal::new("admin@example.com".to_string(), vec!["admin".to_string()]);
+        let actor = Actor::new(principal.account.clone(), Some("req-1".to_string()));
+        assert!(block_on(uc.cancel(1, &principal, &actor)).is_ok());
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::out_of_office_client::OutOfOfficeClient, error::Error, out_of_office::OutOfOffice,
+};
+
+pub struct OutOfOfficeUsecase {
+    pool: Arc<dyn OutOfOfficeClient>,
+}
+impl OutOfOfficeUsecase {
+    pub fn new(pool: Arc<dyn OutOfOfficeClient>) -> Self {
+        Self { pool }
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn register(
+        &self,
+        tenant: &str,
+        account: &str,
+        out_of_office: OutOfOffice,
+    ) -> Result<(), Error> {
+        out_of_office.validate()?;
+        self.pool
+            .register_out_of_office(tenant, account, &out_of_office)
+            .await
+    }
+    /// まだ終わっていない不在期間を返す
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn fetch_upcoming(
+        &self,
+        tenant: &str,
+        account: &str,
+    ) -> Result<Vec<OutOfOffice>, Error> {
+        self.pool
+            .fetch_upcoming_out_of_offices(tenant, account)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use crate::domains::data_clients::out_of_office_client::MockOutOfOfficeClient;
+
+    use super::*;
+
+    fn to_date(date: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+    #[test]
+    fn test_register_invalid_period() {
+        let mut mock = MockOutOfOfficeClient::new();
+        mock.expect_register_out_of_office().times(0);
+
+        let uc = OutOfOfficeUsecase::new(Arc::new(mock));
+        let ooo = OutOfOffice::new(
+            to_date("2020-01-02 10:00:00"),
+            to_date("2020-01-01 10:00:00"),
+            "vacation".to_string(),
+        );
+        let ret = futures::executor::block_on(uc.register("default", "test1@example.com", ooo));
+        assert!(matches!(ret, Err(Error::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,285 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::outbox_client::{EventSink, OutboxClient},
+    error::Error,
+};
+
+/// 1回の実行で1つのsinkに配信するイベントの最大数
+const DISPATCH_BATCH_SIZE: u32 = 100;
+
+pub struct OutboxUsecase {
+    pool: Arc<dyn OutboxClient>,
+    sinks: Vec<Arc<dyn EventSink>>,
+}
+impl OutboxUsecase {
+    pub fn new(pool: Arc<dyn OutboxClient>, sinks: Vec<Arc<dyn EventSink>>) -> Self {
+        Self { pool, sinks }
+    }
+    /// 未配信のイベントを古いものからsinkごとに配信し、配信した数の合計を返す。
+    /// 進み具合はsinkごとに持つので、失敗したsinkだけが次回に続きから配信し直す
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn dispatch_pending(&self) -> Result<usize, Error> {
+        let mut published = 0;
+        let mut positions = vec![];
+        let mut result = Ok(());
+        for sink in &self.sinks {
+            match self.dispatch_to(sink.as_ref()).await {
+                Ok(Some((last_id, count))) => {
+                    positions.push(last_id);
+                    published += count;
+                }
+                Ok(None) => {}
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+        // 全てのsinkに配信できた場合だけ、全てに配信済みになったところまでを記録する
+        if published > 0 && positions.len() == self.sinks.len() {
+            if let Some(last_id) = positions.into_iter().min().filter(|id| *id > 0) {
+                self.pool.mark_published(last_id).await?;
+            }
+        }
+        result.map(|_| published)
+    }
+    /// 1つのsinkに配信し、進み具合と配信した数を返す。他のプロセスが配信中の場合はNone。
+    /// 順番を守るため、失敗したイベント以降は次回に持ち越す
+    async fn dispatch_to(&self, sink: &dyn EventSink) -> Result<Option<(u64, usize)>, Error> {
+        let Some(mut cursor) = self.pool.claim_cursor(sink.name()).await? else {
+            return Ok(None);
+        };
+        let events = match self
+            .pool
+            .fetch_unpublished(cursor.last_id, DISPATCH_BATCH_SIZE)
+            .await
+        {
+            Ok(events) => events,
+            Err(e) => {
+                self.pool.release_cursor(&cursor).await?;
+                return Err(e);
+            }
+        };
+        let mut count = 0;
+        let mut result = Ok(());
+        for event in &events {
+            if let Err(e) = sink.publish(event).await {
+                result = Err(Error::PublishError(format!(
+                    "{} failed at event {}: {}",
+                    sink.name(),
+                    event.id,
+                    e
+                )));
+                break;
+            }
+            cursor.last_id = event.id;
+            count += 1;
+        }
+        self.pool.release_cursor(&cursor).await?;
+        result.map(|_| Some((cursor.last_id, count)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use chrono::NaiveDateTime;
+    use mockall::Sequence;
+
+    use crate::domains::{
+        data_clients::outbox_client::{MockEventSink, MockOutboxClient},
+        outbox::{OutboxCursor, OutboxEvent},
+    };
+
+    use super::*;
+
+    fn to_event(id: u64) -> OutboxEvent {
+        OutboxEvent::new(
+            id,
+            "meeting.confirmed".to_string(),
+            1,
+            "{}".to_string(),
+            NaiveDateTime::parse_from_str("2020-01-01 10:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+        )
+    }
+
+    /// sinkごとの進み具合を覚えておき、その後のイベントを返すモック
+    fn mock_outbox(events: Vec<OutboxEvent>) -> MockOutboxClient {
+        let cursors = Arc::new(Mutex::new(HashMap::<String, u64>::new()));
+        let mut mock = MockOutboxClient::new();
+        let claimed = cursors.clone();
+        mock.expect_claim_cursor().returning(move |sink| {
+            let last_id = *claimed.lock().unwrap().entry(sink.to_string()).or_default();
+            Ok(Some(OutboxCursor::new(
+                sink.to_string(),
+                "token".to_string(),
+                last_id,
+            )))
+        });
+        mock.expect_fetch_unpublished()
+            .returning(move |after_id, _| {
+                Ok(events
+                    .iter()
+                    .filter(|event| event.id > after_id)
+                    .cloned()
+                    .collect())
+            });
+        mock.expect_release_cursor().returning(move |cursor| {
+            cursors
+                .lock()
+                .unwrap()
+                .insert(cursor.sink.clone(), cursor.last_id);
+            Ok(())
+        });
+        mock
+    }
+
+    fn mock_sink(name: &str) -> MockEventSink {
+        let mut sink = MockEventSink::new();
+        sink.expect_name().return_const(name.to_string());
+        sink
+    }
+
+    #[test]
+    fn test_dispatch_pending() {
+        let mut mock = mock_outbox(vec![to_event(1), to_event(2)]);
+        mock.expect_mark_published()
+            .withf(|last_id| *last_id == 2)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut seq = Sequence::new();
+        let mut sink = mock_sink("file");
+        for id in 1..=2 {
+            sink.expect_publish()
+                .withf(move |event| event.id == id)
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|_| Ok(()));
+        }
+
+        let uc = OutboxUsecase::new(Arc::new(mock), vec![Arc::new(sink)]);
+        let count = futures::executor::block_on(uc.dispatch_pending()).unwrap();
+        assert_eq!(count, 2);
+        // 配信済みのイベントは次回に配信しない
+        let count = futures::executor::block_on(uc.dispatch_pending()).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_dispatch_pending_failed() {
+        let mut mock = mock_outbox(vec![to_event(1), to_event(2), to_event(3)]);
+        mock.expect_mark_published()
+            .withf(|last_id| *last_id == 3)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut sink = mock_sink("file");
+        sink.expect_publish()
+            .withf(|event| event.id == 1)
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut failed = false;
+        sink.expect_publish()
+            .withf(|event| event.id == 2)
+            .times(2)
+            .returning(move |_| {
+                if failed {
+                    return Ok(());
+                }
+                failed = true;
+                Err(Error::PublishError("disk full".to_string()))
+            });
+        sink.expect_publish()
+            .withf(|event| event.id == 3)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let uc = OutboxUsecase::new(Arc::new(mock), vec![Arc::new(sink)]);
+        let ret = futures::executor::block_on(uc.dispatch_pending());
+        assert!(matches!(ret, Err(Error::PublishError(_))));
+        // 失敗したイベントから配信し直す
+        let count = futures::executor::block_on(uc.dispatch_pending()).unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_dispatch_pending_second_sink_failed() {
+        let mut mock = mock_outbox(vec![to_event(1), to_event(2)]);
+        mock.expect_mark_published()
+            .withf(|last_id| *last_id == 2)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        // 先のsinkには1回ずつしか配信しない
+        let mut webhook = mock_sink("webhook");
+        for id in 1..=2 {
+            webhook
+                .expect_publish()
+                .withf(move |event| event.id == id)
+                .times(1)
+                .returning(|_| Ok(()));
+        }
+        let mut seq = Sequence::new();
+        let mut email = mock_sink("email");
+        email
+            .expect_publish()
+            .withf(|event| event.id == 1)
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Err(Error::PublishError("connection refused".to_string())));
+        for id in 1..=2 {
+            email
+                .expect_publish()
+                .withf(move |event| event.id == id)
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|_| Ok(()));
+        }
+
+        let uc = OutboxUsecase::new(Arc::new(mock), vec![Arc::new(webhook), Arc::new(email)]);
+        let ret = futures::executor::block_on(uc.dispatch_pending());
+        assert!(matches!(ret, Err(Error::PublishError(_))));
+        let count = futures::executor::block_on(uc.dispatch_pending()).unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_dispatch_pending_claimed() {
+        // 他のプロセスが配信中のsinkには配信しない
+        let mut mock = MockOutboxClient::new();
+        mock.expect_claim_cursor().times(1).returning(|_| Ok(None));
+        let sink = mock_sink("file");
+
+        let uc = OutboxUsecase::new(Arc::new(mock), vec![Arc::new(sink)]);
+        let count = futures::executor::block_on(uc.dispatch_pending()).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    /// 他のプロセスが確保している間は配信せず、解いた後は続きから配信する。
+    /// 実際のMySQLを使うので`cargo test -- --ignored`で実行する
+    #[actix_web::test]
+    #[ignore]
+    async fn test_dispatch_pending_against_mysql() {
+        use crate::sql_clients::test_database;
+
+        let _lock = test_database::lock().await;
+        let pool = test_database::migrated_database().await;
+        sqlx::query("INSERT INTO t_outbox (event_type, aggregate_id, payload) VALUES (?, ?, ?)")
+            .bind("meeting.confirmed")
+            .bind(1)
+            .bind("{}")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let other = pool.claim_cursor("file").await.unwrap().unwrap();
+        assert!(pool.claim_cursor("file").await.unwrap().is_none());
+
+        let mut sink = mock_sink("file");
+        sink.expect_publish().times(1).returning(|_| Ok(()));
+        let uc = OutboxUsecase::new(Arc::new(pool.clone()), vec![Arc::new(sink)]);
+        assert_eq!(uc.dispatch_pending().await.unwrap(), 0);
+        pool.release_cursor(&other).await.unwrap();
+        assert_eq!(uc.dispatch_pending().await.unwrap(), 1);
+        assert_eq!(uc.dispatch_pending().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::resource_client::ResourceClient, error::Error, resource::Resource,
+};
+
+pub struct ResourceUsecase {
+    pool: Arc<dyn ResourceClient>,
+}
+impl ResourceUsecase {
+    pub fn new(pool: Arc<dyn ResourceClient>) -> Self {
+        Self { pool }
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn create(&self, tenant: &str, resource: Resource) -> Result<(), Error> {
+        if resource.capacity == 0 {
+            return Err(Error::InvalidInput(
+                "capacity must be greater than 0".to_string(),
+            ));
+        }
+        self.pool.create_resource(tenant, &resource).await
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn fetch_all(&self, tenant: &str) -> Result<Vec<Resource>, Error> {
+        self.pool.fetch_resources(tenant).await
+    }
+}
@@ -1,59 +1,294 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use chrono::NaiveDateTime;
+use futures::Stream;
 use itertools::Itertools;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
 
-use crate::domains::{
-    data_clients::user_slot_client::UserSlotClient,
-    error::Error,
-    slot::{collect_slot_ranges, Slot},
-    slot_range::intersect_slot_ranges_array,
+use crate::{
+    domains::{
+        audit::Actor,
+        buffer::Buffer,
+        business_hours::BusinessHours,
+        data_clients::user_slot_client::UserSlotClient,
+        error::Error,
+        resource::{RoomRequirement, RoomSlot},
+        slot::{collect_slot_ranges, Slot},
+        slot_events::{SlotEvents, SlotWatch},
+        slot_range::intersect_slot_ranges_array,
+        webhook::MeetingEvent,
+    },
+    metrics::metrics,
+    shutdown::Shutdown,
 };
 
+/// 変化が無い間、接続を保つために送る間隔
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// 確定がデッドロックで中断された場合にやり直す回数
+const DEADLOCK_RETRIES: u32 = 2;
+
+/// watch_confirmable_slotsが返す通知
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlotUpdate {
+    /// 確定可能な枠の全体
+    Slots(Vec<Slot>),
+    KeepAlive,
+}
+
 pub struct UserSlotUsecase {
     pool: Arc<dyn UserSlotClient>,
+    /// ユーザ個別の設定が無い場合に使うバッファ
+    buffer: Buffer,
+    /// 設定されている場合は、この時間帯に収まる枠だけを確定できる
+    business_hours: Option<BusinessHours>,
+    /// 枠の長さ
+    slot_duration: chrono::Duration,
 }
 impl UserSlotUsecase {
-    pub fn new(pool: Arc<dyn UserSlotClient>) -> Self {
-        Self { pool }
+    pub fn new(pool: Arc<dyn UserSlotClient>, buffer: Buffer) -> Self {
+        Self {
+            pool,
+            buffer,
+            business_hours: None,
+            slot_duration: Slot::default_duration(),
+        }
+    }
+    pub fn with_business_hours(mut self, business_hours: Option<BusinessHours>) -> Self {
+        self.business_hours = business_hours;
+        self
     }
+    pub fn with_slot_duration(mut self, slot_duration: chrono::Duration) -> Self {
+        self.slot_duration = slot_duration;
+        self
+    }
+    /// start_timeから始まる設定の長さの枠
+    pub fn slot(&self, start_time: NaiveDateTime) -> Slot {
+        Slot::with_duration(start_time, self.slot_duration)
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
     pub async fn fetch_confirmable_slots(
         &self,
+        tenant: &str,
         accounts: &[String],
         start_time: chrono::NaiveDateTime,
         end_time: chrono::NaiveDateTime,
     ) -> Result<Vec<Slot>, Error> {
+        let _timer = metrics().slots_fetch_duration.start_timer();
+        metrics().slots_attendees.observe(accounts.len() as f64);
         let user_slots = self
             .pool
-            .fetch_user_slots(accounts, start_time, end_time)
+            .fetch_user_slots(
+                tenant,
+                accounts,
+                start_time,
+                end_time,
+                self.slot_duration,
+                &self.buffer,
+            )
             .await?;
         if user_slots.iter().any(|us| us.slots.is_empty()) {
             // 一つもスロットがないユーザがいる場合は空になる
             return Ok(vec![]);
         }
 
-        let slots_list = user_slots
+        let buffered_ranges = user_slots
             .into_iter()
-            .map(|us| us.slots.into_iter().map(Slot::new).collect_vec())
-            .map(|x| collect_slot_ranges(&x))
+            .map(|us| {
+                let slots = us.slots.into_iter().map(|s| self.slot(s)).collect_vec();
+                (collect_slot_ranges(&slots), us.buffer)
+            })
+            .collect_vec();
+        let slots_list = buffered_ranges
+            .iter()
+            .map(|(ranges, _)| ranges.clone())
             .collect_vec();
 
+        let user_caps = self
+            .pool
+            .fetch_user_caps(tenant, accounts, start_time, end_time)
+            .await?;
+        let user_holidays = self
+            .pool
+            .fetch_user_holidays(tenant, accounts, start_time, end_time)
+            .await?;
+        let user_out_of_offices = self
+            .pool
+            .fetch_user_out_of_offices(tenant, accounts, start_time, end_time + self.slot_duration)
+            .await?;
+
+        // 前後のバッファも全員の空き時間に収まり、誰の上限にも達しておらず、
+        // 誰の休日・不在期間でもなく、業務時間内の枠だけを残す
         let intersected_slots = intersect_slot_ranges_array(slots_list)
             .into_iter()
-            .flat_map(|sr| sr.to_slots())
+            .flat_map(|sr| sr.to_slots(self.slot_duration))
             .sorted_by_key(|x| x.start_date)
             .dedup()
+            .filter(|slot| {
+                buffered_ranges.iter().all(|(ranges, buffer)| {
+                    let occupied = buffer.around(slot);
+                    ranges.iter().any(|r| r.contains(&occupied))
+                })
+            })
+            .filter(|slot| user_caps.iter().all(|caps| caps.allows(slot)))
+            .filter(|slot| !user_holidays.iter().any(|holidays| holidays.includes(slot)))
+            .filter(|slot| {
+                user_out_of_offices
+                    .iter()
+                    .all(|ooo| ooo.find_overlap(slot).is_none())
+            })
+            .filter(|slot| self.business_hours.iter().all(|hours| hours.contains(slot)))
             .collect_vec();
 
         Ok(intersected_slots)
     }
 
+    /// 確定可能な枠のうち、条件を満たす会議室が空いている枠を会議室と合わせて返す
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn fetch_confirmable_room_slots(
+        &self,
+        tenant: &str,
+        accounts: &[String],
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        requirement: &RoomRequirement,
+    ) -> Result<Vec<RoomSlot>, Error> {
+        let slots = self
+            .fetch_confirmable_slots(tenant, accounts, start_time, end_time)
+            .await?;
+        if slots.is_empty() {
+            return Ok(vec![]);
+        }
+        let rooms = self
+            .pool
+            .fetch_room_bookings(
+                tenant,
+                requirement.min_capacity,
+                start_time,
+                end_time,
+                self.slot_duration,
+            )
+            .await?;
+
+        // 空いている会議室のうち、一番小さい部屋を提案する
+        let room_slots = slots
+            .into_iter()
+            .filter_map(|slot| {
+                rooms
+                    .iter()
+                    .filter(|r| requirement.matches(&r.resource) && r.is_free(&slot))
+                    .min_by_key(|r| r.resource.capacity)
+                    .map(|r| RoomSlot::new(slot, r.resource.clone()))
+            })
+            .collect_vec();
+        Ok(room_slots)
+    }
+
+    /// 最初に確定可能な枠を返し、その後は確定・取り消しで枠が変わるたびに新しい枠を返し続ける。
+    /// エラーを返した後と、サーバを止める際は終了する
+    pub fn watch_confirmable_slots(
+        self: Arc<Self>,
+        events: &SlotEvents,
+        watch: SlotWatch,
+        shutdown: Shutdown,
+    ) -> impl Stream<Item = Result<SlotUpdate, Error>> {
+        struct State {
+            uc: Arc<UserSlotUsecase>,
+            receiver: Receiver<MeetingEvent>,
+            watch: SlotWatch,
+            shutdown: Shutdown,
+            last: Option<Vec<Slot>>,
+            done: bool,
+        }
+        let state = State {
+            uc: self,
+            receiver: events.subscribe(),
+            watch,
+            shutdown,
+            last: None,
+            done: false,
+        };
+        futures::stream::unfold(state, |mut state| async move {
+            if state.done || state.shutdown.is_stopping() {
+                return None;
+            }
+            loop {
+                if state.last.is_some() {
+                    let received = tokio::select! {
+                        received = actix_web::rt::time::timeout(KEEP_ALIVE, state.receiver.recv()) => received,
+                        // 接続を閉じ、クライアントには別のサーバに繋ぎ直してもらう
+                        _ = state.shutdown.stopped() => return None,
+                    };
+                    match received {
+                        Err(_) => return Some((Ok(SlotUpdate::KeepAlive), state)),
+                        Ok(Err(RecvError::Closed)) => return None,
+                        // 取りこぼしたイベントがあるので計算し直す
+                        Ok(Err(RecvError::Lagged(_))) => {}
+                        Ok(Ok(event)) if !state.watch.is_affected_by(&event) => continue,
+                        Ok(Ok(_)) => {}
+                    }
+                }
+                let slots = state
+                    .uc
+                    .fetch_confirmable_slots(
+                        &state.watch.tenant,
+                        &state.watch.accounts,
+                        state.watch.range.start,
+                        state.watch.range.end,
+                    )
+                    .await;
+                match slots {
+                    Err(e) => {
+                        state.done = true;
+                        return Some((Err(e), state));
+                    }
+                    Ok(slots) if state.last.as_ref() != Some(&slots) => {
+                        state.last = Some(slots.clone());
+                        return Some((Ok(SlotUpdate::Slots(slots)), state));
+                    }
+                    // 枠が変わっていなければ送らない
+                    Ok(_) => {}
+                }
+            }
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
     pub async fn confirm_users_slot(
         &self,
+        tenant: &str,
+        actor: &Actor,
         accounts: &[String],
         start_time: NaiveDateTime,
-    ) -> Result<(), Error> {
-        self.pool.confirm_user_slots(accounts, start_time).await
+        room: Option<String>,
+    ) -> Result<u32, Error> {
+        let slot = self.slot(start_time);
+        if let Some(hours) = self.business_hours {
+            if !hours.contains(&slot) {
+                return Err(Error::Unavailable(format!(
+                    "{} is outside business hours",
+                    start_time
+                )));
+            }
+        }
+        // 複数のユーザをロックするので、同時に確定するとデッドロックすることがある
+        let mut retries = 0;
+        loop {
+            let ret = self
+                .pool
+                .confirm_user_slots(tenant, actor, accounts, &slot, &self.buffer, room.clone())
+                .await;
+            match ret {
+                Err(Error::Deadlock(e)) if retries < DEADLOCK_RETRIES => {
+                    retries += 1;
+                    metrics()
+                        .transaction_retries
+                        .with_label_values(&["confirm"])
+                        .inc();
+                    log::warn!("retrying confirmation after a deadlock: {}", e);
+                }
+                ret => return ret,
+            }
+        }
     }
 }
 
@@ -61,7 +296,17 @@ impl UserSlotUsecase {
 mod tests {
     use chrono::NaiveDateTime;
 
-    use crate::domains::data_clients::user_slot_client::{MockUserSlotClient, UserSlots};
+    use crate::domains::{
+        data_clients::{
+            holiday_client::UserHolidays,
+            out_of_office_client::UserOutOfOffices,
+            resource_client::ResourceBookings,
+            user_slot_client::{MockUserSlotClient, UserCaps, UserSlots},
+        },
+        meeting_cap::{CapPeriod, MeetingCap},
+        out_of_office::OutOfOffice,
+        resource::{Resource, ResourceKind},
+    };
 
     use super::*;
 
@@ -73,7 +318,7 @@ mod tests {
         let mut mock = MockUserSlotClient::new();
         mock.expect_fetch_user_slots()
             .times(1)
-            .returning(|_, _, _| {
+            .returning(|_, _, _, _, _, _| {
                 let us1 = UserSlots::new(
                     "test1@example.com".to_string(),
                     vec![
@@ -93,11 +338,21 @@ mod tests {
                         to_date("2020-01-01 14:30:00"),
                     ],
                 );
-                
+
                 Ok(vec![us1, us2])
             });
 
-        let uc = UserSlotUsecase::new(Arc::new(mock));
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
         let accounts = vec![
             "test1@example.com".to_string(),
             "test2@example.com".to_string(),
@@ -105,7 +360,7 @@ mod tests {
         let start_time = to_date("2020-01-01 10:00:00");
         let end_time = to_date("2020-01-01 20:00:00");
         let slots = futures::executor::block_on(
-            uc.fetch_confirmable_slots(&accounts, start_time, end_time),
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
         )
         .unwrap();
         assert_eq!(slots.len(), 3);
@@ -118,7 +373,7 @@ mod tests {
         let mut mock = MockUserSlotClient::new();
         mock.expect_fetch_user_slots()
             .times(1)
-            .returning(|_, _, _| {
+            .returning(|_, _, _, _, _, _| {
                 let us1 = UserSlots::new(
                     "test1@example.com".to_string(),
                     vec![
@@ -130,11 +385,11 @@ mod tests {
                     ],
                 );
                 let us2 = UserSlots::new("test2@example.com".to_string(), vec![]);
-                
+
                 Ok(vec![us1, us2])
             });
 
-        let uc = UserSlotUsecase::new(Arc::new(mock));
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
         let accounts = vec![
             "test1@example.com".to_string(),
             "test2@example.com".to_string(),
@@ -142,9 +397,562 @@ mod tests {
         let start_time = to_date("2020-01-01 10:00:00");
         let end_time = to_date("2020-01-01 20:00:00");
         let slots = futures::executor::block_on(
-            uc.fetch_confirmable_slots(&accounts, start_time, end_time),
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
         )
         .unwrap();
         assert_eq!(slots.len(), 0);
     }
+    #[test]
+    fn test_fetch_confirmable_slots_with_buffer() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                let us1 = UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 10:30:00"),
+                        to_date("2020-01-01 11:00:00"),
+                        to_date("2020-01-01 11:30:00"),
+                    ],
+                );
+                let mut us2 = UserSlots::new(
+                    "test2@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 10:30:00"),
+                        to_date("2020-01-01 11:00:00"),
+                        to_date("2020-01-01 11:30:00"),
+                    ],
+                );
+                // test2は会議の後ろに30分確保する
+                us2.buffer = Buffer::from_minutes(10, 30);
+
+                Ok(vec![us1, us2])
+            });
+
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::from_minutes(10, 10));
+        let accounts = vec![
+            "test1@example.com".to_string(),
+            "test2@example.com".to_string(),
+        ];
+        let start_time = to_date("2020-01-01 10:00:00");
+        let end_time = to_date("2020-01-01 20:00:00");
+        let slots = futures::executor::block_on(
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
+        )
+        .unwrap();
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0].start_date, to_date("2020-01-01 10:30:00"));
+        assert_eq!(slots[1].start_date, to_date("2020-01-01 11:00:00"));
+    }
+    #[test]
+    fn test_confirm_users_slot_retries_deadlock() {
+        let mut mock = MockUserSlotClient::new();
+        let mut seq = mockall::Sequence::new();
+        mock.expect_confirm_user_slots()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _, _, _, _, _| Err(Error::Deadlock("1213".to_string())));
+        mock.expect_confirm_user_slots()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _, _, _, _, _| Ok(1));
+        mock.expect_confirm_user_slots()
+            .times(DEADLOCK_RETRIES as usize + 1)
+            .in_sequence(&mut seq)
+            .returning(|_, _, _, _, _, _| Err(Error::Deadlock("1213".to_string())));
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
+        let actor = Actor::new("test1@example.com".to_string(), None);
+        let accounts = vec!["test1@example.com".to_string()];
+        let confirm = || {
+            futures::executor::block_on(uc.confirm_users_slot(
+                "default",
+                &actor,
+                &accounts,
+                to_date("2020-01-01 10:00:00"),
+                None,
+            ))
+        };
+        assert_eq!(confirm().unwrap(), 1);
+        // やり直しても中断される場合は諦める
+        assert!(matches!(confirm(), Err(Error::Deadlock(_))));
+    }
+    #[test]
+    fn test_fetch_confirmable_slots_with_business_hours() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                Ok(vec![UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 08:30:00"),
+                        to_date("2020-01-01 09:00:00"),
+                        to_date("2020-01-01 17:30:00"),
+                        to_date("2020-01-01 18:00:00"),
+                    ],
+                )])
+            });
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_confirm_user_slots().times(0);
+
+        let hours = BusinessHours::parse("09:00", "18:00").unwrap();
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default())
+            .with_business_hours(Some(hours));
+        let accounts = vec!["test1@example.com".to_string()];
+        let slots = futures::executor::block_on(uc.fetch_confirmable_slots(
+            "default",
+            &accounts,
+            to_date("2020-01-01 08:00:00"),
+            to_date("2020-01-01 20:00:00"),
+        ))
+        .unwrap();
+        assert_eq!(
+            slots.iter().map(|x| x.start_date).collect_vec(),
+            vec![
+                to_date("2020-01-01 09:00:00"),
+                to_date("2020-01-01 17:30:00")
+            ]
+        );
+
+        // 業務時間外の枠は確定できない
+        let actor = Actor::new("test1@example.com".to_string(), None);
+        let ret = futures::executor::block_on(uc.confirm_users_slot(
+            "default",
+            &actor,
+            &accounts,
+            to_date("2020-01-01 18:00:00"),
+            None,
+        ));
+        assert!(matches!(ret, Err(Error::Unavailable(_))));
+    }
+    #[test]
+    fn test_fetch_confirmable_slots_with_cap() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                let us1 = UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-02 10:00:00"),
+                    ],
+                );
+                let us2 = UserSlots::new(
+                    "test2@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-02 10:00:00"),
+                    ],
+                );
+                Ok(vec![us1, us2])
+            });
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| {
+                // test2は1/1に既に2件の予定があり、1日2件まで
+                Ok(vec![UserCaps::new(
+                    "test2@example.com".to_string(),
+                    vec![MeetingCap::new(CapPeriod::Day, Some(2), None)],
+                    vec![
+                        to_date("2020-01-01 13:00:00"),
+                        to_date("2020-01-01 14:00:00"),
+                    ],
+                )])
+            });
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
+        let accounts = vec![
+            "test1@example.com".to_string(),
+            "test2@example.com".to_string(),
+        ];
+        let start_time = to_date("2020-01-01 10:00:00");
+        let end_time = to_date("2020-01-02 20:00:00");
+        let slots = futures::executor::block_on(
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
+        )
+        .unwrap();
+        assert_eq!(slots, vec![Slot::new(to_date("2020-01-02 10:00:00"))]);
+    }
+    #[test]
+    fn test_fetch_confirmable_slots_with_holiday() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                let us1 = UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-02 10:00:00"),
+                    ],
+                );
+                let us2 = UserSlots::new(
+                    "test2@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-02 10:00:00"),
+                    ],
+                );
+                Ok(vec![us1, us2])
+            });
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(vec![
+                    UserHolidays::new("test1@example.com".to_string(), vec![]),
+                    UserHolidays::new(
+                        "test2@example.com".to_string(),
+                        vec![to_date("2020-01-01 00:00:00").date()],
+                    ),
+                ])
+            });
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
+        let accounts = vec![
+            "test1@example.com".to_string(),
+            "test2@example.com".to_string(),
+        ];
+        let start_time = to_date("2020-01-01 10:00:00");
+        let end_time = to_date("2020-01-02 20:00:00");
+        let slots = futures::executor::block_on(
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
+        )
+        .unwrap();
+        assert_eq!(slots, vec![Slot::new(to_date("2020-01-02 10:00:00"))]);
+    }
+    #[test]
+    fn test_fetch_confirmable_slots_with_out_of_office() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                let us1 = UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 10:30:00"),
+                        to_date("2020-01-01 11:00:00"),
+                    ],
+                );
+                Ok(vec![us1])
+            });
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| {
+                Ok(vec![UserOutOfOffices::new(
+                    "test1@example.com".to_string(),
+                    vec![OutOfOffice::new(
+                        to_date("2020-01-01 10:15:00"),
+                        to_date("2020-01-01 11:00:00"),
+                        "dentist".to_string(),
+                    )],
+                )])
+            });
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
+        let accounts = vec!["test1@example.com".to_string()];
+        let start_time = to_date("2020-01-01 10:00:00");
+        let end_time = to_date("2020-01-01 20:00:00");
+        let slots = futures::executor::block_on(
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
+        )
+        .unwrap();
+        assert_eq!(slots, vec![Slot::new(to_date("2020-01-01 11:00:00"))]);
+    }
+    #[test]
+    fn test_fetch_confirmable_slots_with_slot_duration() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .withf(|_, _, _, _, slot_duration, _| *slot_duration == chrono::Duration::hours(1))
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                let us1 = UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 11:00:00"),
+                    ],
+                );
+                Ok(vec![us1])
+            });
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .withf(|_, _, _, end_time| *end_time == to_date("2020-01-01 21:00:00"))
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default())
+            .with_slot_duration(chrono::Duration::hours(1));
+        let accounts = vec!["test1@example.com".to_string()];
+        let start_time = to_date("2020-01-01 10:00:00");
+        let end_time = to_date("2020-01-01 20:00:00");
+        let slots = futures::executor::block_on(
+            uc.fetch_confirmable_slots("default", &accounts, start_time, end_time),
+        )
+        .unwrap();
+        assert_eq!(
+            slots,
+            vec![
+                uc.slot(to_date("2020-01-01 10:00:00")),
+                uc.slot(to_date("2020-01-01 11:00:00")),
+            ]
+        );
+        assert_eq!(slots[1].end_date(), to_date("2020-01-01 12:00:00"));
+    }
+    #[test]
+    fn test_fetch_confirmable_room_slots() {
+        let mut mock = MockUserSlotClient::new();
+        mock.expect_fetch_user_slots()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                let us1 = UserSlots::new(
+                    "test1@example.com".to_string(),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 10:30:00"),
+                        to_date("2020-01-01 11:00:00"),
+                    ],
+                );
+                Ok(vec![us1])
+            });
+        mock.expect_fetch_user_caps()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_room_bookings()
+            .withf(|tenant, min_capacity, _, _, _| tenant == "default" && *min_capacity == 4)
+            .times(1)
+            .returning(|_, _, _, _, _| {
+                let small = ResourceBookings::new(
+                    Resource::new("small".to_string(), ResourceKind::Room, 4, vec![]),
+                    vec![to_date("2020-01-01 10:00:00")],
+                );
+                let large = ResourceBookings::new(
+                    Resource::new("large".to_string(), ResourceKind::Room, 10, vec![]),
+                    vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 11:00:00"),
+                    ],
+                );
+                Ok(vec![large, small])
+            });
+
+        let uc = UserSlotUsecase::new(Arc::new(mock), Buffer::default());
+        let accounts = vec!["test1@example.com".to_string()];
+        let start_time = to_date("2020-01-01 10:00:00");
+        let end_time = to_date("2020-01-01 20:00:00");
+        let slots = futures::executor::block_on(uc.fetch_confirmable_room_slots(
+            "default",
+            &accounts,
+            start_time,
+            end_time,
+            &RoomRequirement::new(4, vec![]),
+        ))
+        .unwrap();
+        // 10:00はどちらも埋まっているので提案されない
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0].slot.start_date, to_date("2020-01-01 10:30:00"));
+        assert_eq!(slots[0].room.name, "small");
+        assert_eq!(slots[1].slot.start_date, to_date("2020-01-01 11:00:00"));
+        assert_eq!(slots[1].room.name, "small");
+    }
+
+    #[actix_web::test]
+    async fn test_watch_confirmable_slots() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use futures::StreamExt;
+
+        use crate::domains::{slot_range::SlotRange, webhook::EventType};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut mock = MockUserSlotClient::new();
+        let counter = calls.clone();
+        mock.expect_fetch_user_slots()
+            .times(2)
+            .returning(move |_, _, _, _, _, _| {
+                // 2回目は確定された10:30の枠が埋まっている
+                let slots = match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => vec![
+                        to_date("2020-01-01 10:00:00"),
+                        to_date("2020-01-01 10:30:00"),
+                    ],
+                    _ => vec![to_date("2020-01-01 10:00:00")],
+                };
+                Ok(vec![UserSlots::new("test1@example.com".to_string(), slots)])
+            });
+        mock.expect_fetch_user_caps()
+            .times(2)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_holidays()
+            .times(2)
+            .returning(|_, _, _, _| Ok(vec![]));
+        mock.expect_fetch_user_out_of_offices()
+            .times(2)
+            .returning(|_, _, _, _| Ok(vec![]));
+
+        let uc = Arc::new(UserSlotUsecase::new(Arc::new(mock), Buffer::default()));
+        let events = SlotEvents::new(16);
+        let watch = SlotWatch::new(
+            "default".to_string(),
+            vec!["test1@example.com".to_string()],
+            SlotRange::new(
+                to_date("2020-01-01 10:00:00"),
+                to_date("2020-01-01 11:00:00"),
+            ),
+        );
+        let (stop, shutdown) = Shutdown::channel();
+        let mut stream = Box::pin(uc.watch_confirmable_slots(&events, watch, shutdown));
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            SlotUpdate::Slots(vec![
+                Slot::new(to_date("2020-01-01 10:00:00")),
+                Slot::new(to_date("2020-01-01 10:30:00")),
+            ])
+        );
+
+        let event = |account: &str| {
+            MeetingEvent::new(
+                "default".to_string(),
+                EventType::MeetingConfirmed,
+                1,
+                Slot::new(to_date("2020-01-01 10:30:00")),
+                vec![account.to_string()],
+                None,
+            )
+        };
+        // 購読していないアカウントのイベントでは計算し直さない
+        events.publish(event("test2@example.com"));
+        events.publish(event("test1@example.com"));
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            SlotUpdate::Slots(vec![Slot::new(to_date("2020-01-01 10:00:00"))])
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // サーバを止める際は次のイベントを待たずに終わる
+        stop.send_replace(true);
+        assert!(stream.next().await.is_none());
+    }
+
+    /// ICSから取り込んだ空き時間に確定でき、確定した後は同じ時間に重ねられない。
+    /// 実際のMySQLを使うので`cargo test -- --ignored`で実行する
+    #[actix_web::test]
+    #[ignore]
+    async fn test_confirm_imported_slot_against_mysql() {
+        use crate::{
+            domains::data_clients::calendar_client::CalendarClient, sql_clients::test_database,
+        };
+
+        let _lock = test_database::lock().await;
+        let pool = test_database::migrated_database().await;
+        let start = to_date("2030-01-07 10:00:00");
+        pool.replace_imported_slots("default", "test1@example.com", &[Slot::new(start)], &[])
+            .await
+            .unwrap();
+
+        let uc = UserSlotUsecase::new(Arc::new(pool), Buffer::default());
+        let actor = Actor::new("test1@example.com".to_string(), None);
+        let accounts = vec!["test1@example.com".to_string()];
+        assert!(uc
+            .confirm_users_slot("default", &actor, &accounts, start, None)
+            .await
+            .is_ok());
+        assert!(matches!(
+            uc.confirm_users_slot("default", &actor, &accounts, start, None)
+                .await,
+            Err(Error::Conflicts)
+        ));
+    }
+
+    /// 上限は会議だけを数え、取り込んだ空き時間は数えない。
+    /// 実際のMySQLを使うので`cargo test -- --ignored`で実行する
+    #[actix_web::test]
+    #[ignore]
+    async fn test_confirm_with_cap_against_mysql() {
+        use crate::{
+            domains::data_clients::calendar_client::CalendarClient, sql_clients::test_database,
+        };
+
+        let _lock = test_database::lock().await;
+        let pool = test_database::migrated_database().await;
+        sqlx::query(
+            "INSERT INTO t_user_cap (user_id, period, max_meetings) SELECT id, 'day', 1 FROM t_user WHERE email = ?",
+        )
+        .bind("test1@example.com")
+        .execute(&pool)
+        .await
+        .unwrap();
+        let free = [
+            Slot::new(to_date("2030-01-07 10:00:00")),
+            Slot::new(to_date("2030-01-07 11:00:00")),
+        ];
+        pool.replace_imported_slots("default", "test1@example.com", &free, &[])
+            .await
+            .unwrap();
+
+        let uc = UserSlotUsecase::new(Arc::new(pool), Buffer::default());
+        let actor = Actor::new("test1@example.com".to_string(), None);
+        let accounts = vec!["test1@example.com".to_string()];
+        assert!(uc
+            .confirm_users_slot("default", &actor, &accounts, free[0].start_date, None)
+            .await
+            .is_ok());
+        assert!(matches!(
+            uc.confirm_users_slot("default", &actor, &accounts, free[1].start_date, None)
+                .await,
+            Err(Error::LimitExceeded(_))
+        ));
+    }
 }
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use crate::domains::{
+    data_clients::webhook_client::{WebhookClient, WebhookSender},
+    error::Error,
+    webhook::{sign, Delivery, EventType, Webhook},
+};
+
+/// 1回の実行で送信する配信の最大数
+const DELIVERY_BATCH_SIZE: u32 = 50;
+
+pub struct WebhookUsecase {
+    pool: Arc<dyn WebhookClient>,
+}
+impl WebhookUsecase {
+    pub fn new(pool: Arc<dyn WebhookClient>) -> Self {
+        Self { pool }
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn register(
+        &self,
+        tenant: &str,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> Result<u32, Error> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(Error::InvalidInput(format!("invalid url: {}", url)));
+        }
+        if secret.is_empty() {
+            return Err(Error::InvalidInput("secret must not be empty".to_string()));
+        }
+        for event in events {
+            event.parse::<EventType>()?;
+        }
+        self.pool
+            .register_webhook(tenant, url, secret, events)
+            .await
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn fetch_all(&self, tenant: &str) -> Result<Vec<Webhook>, Error> {
+        self.pool.fetch_webhooks(tenant).await
+    }
+    #[tracing::instrument(skip_all, fields(tenant = %tenant))]
+    pub async fn fetch_deliveries(
+        &self,
+        tenant: &str,
+        webhook_id: u32,
+    ) -> Result<Vec<Delivery>, Error> {
+        self.pool.fetch_deliveries(tenant, webhook_id).await
+    }
+    /// 送信予定時刻を過ぎた配信を送信して結果を記録し、送信した数を返す。
+    /// 署名するタイムスタンプ(UNIX時刻の秒)は、送信するたびにnowから取る
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn deliver_pending(
+        &self,
+        sender: &dyn WebhookSender,
+        now: &dyn Fn() -> i64,
+    ) -> Result<usize, Error> {
+        let deliveries = self.pool.fetch_due_deliveries(DELIVERY_BATCH_SIZE).await?;
+        for delivery in &deliveries {
+            let timestamp = now();
+            let signature = sign(&delivery.secret, timestamp, &delivery.payload);
+            let result = sender
+                .send(&delivery.url, &delivery.payload, timestamp, &signature)
+                .await;
+            let outcome = delivery.outcome(result);
+            self.pool.record_attempt(delivery.id, &outcome).await?;
+        }
+        Ok(deliveries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use chrono::Duration;
+
+    use crate::domains::{
+        data_clients::webhook_client::{MockWebhookClient, MockWebhookSender},
+        webhook::{DeliveryOutcome, PendingDelivery},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_register() {
+        let mut mock = MockWebhookClient::new();
+        mock.expect_register_webhook()
+            .withf(|tenant, url, secret, events| {
+                tenant == "default"
+                    && url == "https://example.com/hook"
+                    && secret == "secret"
+                    && events == ["meeting.confirmed".to_string()]
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(1));
+
+        let uc = WebhookUsecase::new(Arc::new(mock));
+        let events = vec!["meeting.confirmed".to_string()];
+        let id = futures::executor::block_on(uc.register(
+            "default",
+            "https://example.com/hook",
+            "secret",
+            &events,
+        ))
+        .unwrap();
+        assert_eq!(id, 1);
+
+        let events = vec!["meeting.moved".to_string()];
+        let ret = futures::executor::block_on(uc.register(
+            "default",
+            "https://example.com/hook",
+            "secret",
+            &events,
+        ));
+        assert!(matches!(ret, Err(Error::InvalidInput(_))));
+        let ret =
+            futures::executor::block_on(uc.register("default", "ftp://example.com", "secret", &[]));
+        assert!(matches!(ret, Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_deliver_pending() {
+        let mut mock = MockWebhookClient::new();
+        mock.expect_fetch_due_deliveries().times(1).returning(|_| {
+            Ok(vec![
+                PendingDelivery::new(
+                    1,
+                    "http://localhost/ok".to_string(),
+                    "secret".to_string(),
+                    "{}".to_string(),
+                    0,
+                ),
+                PendingDelivery::new(
+                    2,
+                    "http://localhost/ng".to_string(),
+                    "secret".to_string(),
+                    "{}".to_string(),
+                    1,
+                ),
+            ])
+        });
+        mock.expect_record_attempt()
+            .withf(|id, outcome| {
+                *id == 1 && *outcome == DeliveryOutcome::Succeeded { status_code: 200 }
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock.expect_record_attempt()
+            .withf(|id, outcome| {
+                *id == 2
+                    && *outcome
+                        == DeliveryOutcome::Retry {
+                            status_code: Some(503),
+                            error: "unexpected status: 503".to_string(),
+                            after: Duration::seconds(60),
+                        }
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut sender = MockWebhookSender::new();
+        sender
+            .expect_send()
+            .withf(|url, body, timestamp, signature| {
+                url.ends_with("/ok")
+                    && body == "{}"
+                    && *timestamp == 1700000000
+                    && signature == sign("secret", 1700000000, "{}")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(200));
+        // 後の送信は、送信した時刻で署名する
+        sender
+            .expect_send()
+            .withf(|url, _, timestamp, signature| {
+                url.ends_with("/ng")
+                    && *timestamp == 1700000001
+                    && signature == sign("secret", 1700000001, "{}")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(503));
+
+        let uc = WebhookUsecase::new(Arc::new(mock));
+        let clock = Cell::new(1700000000);
+        let now = || clock.replace(clock.get() + 1);
+        let count = futures::executor::block_on(uc.deliver_pending(&sender, &now)).unwrap();
+        assert_eq!(count, 2);
+    }
+
+    /// 取り出した配信は、結果を記録するまで他のプロセスに返さない。
+    /// 実際のMySQLを使うので`cargo test -- --ignored`で実行する
+    #[actix_web::test]
+    #[ignore]
+    async fn test_fetch_due_deliveries_against_mysql() {
+        use sqlx::MySqlPool;
+
+        use crate::sql_clients::test_database;
+
+        let _lock = test_database::lock().await;
+        let pool = test_database::migrated_database().await;
+        pool.register_webhook("default", "https://example.com/hook", "secret", &[])
+            .await
+            .unwrap();
+        pool.enqueue_event("default", "meeting.confirmed", "{}")
+            .await
+            .unwrap();
+
+        let other = MySqlPool::connect(&test_database::url()).await.unwrap();
+        let deliveries = pool
+            .fetch_due_deliveries(DELIVERY_BATCH_SIZE)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert!(other
+            .fetch_due_deliveries(DELIVERY_BATCH_SIZE)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // 再送する場合は、次の予定時刻にまた取り出せる
+        let outcome = DeliveryOutcome::Retry {
+            status_code: Some(503),
+            error: "unexpected status: 503".to_string(),
+            after: Duration::seconds(0),
+        };
+        pool.record_attempt(deliveries[0].id, &outcome)
+            .await
+            .unwrap();
+        assert_eq!(
+            other
+                .fetch_due_deliveries(DELIVERY_BATCH_SIZE)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}